@@ -3,7 +3,7 @@ use std::fmt::{Display, Formatter};
 
 use crate::SchemaError;
 use crate::errors::InitError;
-use crate::serial::SerialError;
+use crate::serial::{SerialError, SerialValue};
 use crate::store::StoreError;
 use crate::state::StateError;
 
@@ -15,9 +15,22 @@ pub enum EffectError {
     Store(StoreError),
     Schema(SchemaError),
     Stack(String, Box<EffectError>),
+    // Processing was stopped with a payload for the caller, e.g. a validation report.
+    Reject(SerialValue),
     Internal(String)
 }
 
+impl EffectError {
+    // The payload of a rejection, wherever it happened in the stack.
+    pub fn rejection(&self) -> Option<&SerialValue> {
+        match self {
+            Self::Reject(payload) => Some(payload),
+            Self::Stack(_, inner) => inner.rejection(),
+            _ => None
+        }
+    }
+}
+
 impl From<StateError> for EffectError {
     fn from(err: StateError) -> Self {
         Self::State(err)
@@ -58,6 +71,7 @@ impl Display for EffectError {
             Self::Store(err) => write!(f, "persistence layer error: {}", err),
             Self::Schema(err) => write!(f, "invalid schema: {}", err),
             Self::Stack(name, inner) => write!(f, "{}/ {}", name, inner),
+            Self::Reject(_) => write!(f, "rejected"),
             Self::Internal(message) => write!(f, "internal: {}", message)
         }
     }
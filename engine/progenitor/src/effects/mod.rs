@@ -8,4 +8,4 @@ mod primitives;
 pub use self::errors::EffectError;
 pub use self::effect::EffectFn;
 pub use self::context::Context;
-pub use self::primitives::{store_read, store_write, open_store};
\ No newline at end of file
+pub use self::primitives::{store_read, store_write, store_patch, open_store, compute};
\ No newline at end of file
@@ -1,4 +1,4 @@
-use super::super::schema::{Type, Value, Condition};
+use super::super::schema::{Type, Value, Condition, Mutation, LogicTree, LogicScope};
 use super::super::store::Store;
 use super::errors::EffectError;
 use super::effect::effect_fn;
@@ -10,7 +10,7 @@ pub async fn open_store<'ef>(context: &'ef mut Context) -> Result<(), EffectErro
 
     let driver_name: String = archetype.lookup("driver")?.try_into()?;
     let store_name: String = archetype.lookup("name")?.try_into()?;
-    let schema: Type = archetype.lookup("schema")?.try_into()?;
+    let schema = context.registry().resolve_type(Type::parse_from_value(archetype.lookup("schema")?)?)?;
 
     let store = context.registry().create_store(schema, &driver_name, store_name.clone())?;
 
@@ -29,8 +29,12 @@ pub async fn store_read<'ef>(context: &'ef mut Context) -> Result<(), EffectErro
     let store = context.get::<Store>(store_name)?;
 
     let mut query = store.query();
+    // Either the text syntax or the value notation.
     if let Ok(filter_value) = archetype.lookup("filter") {
-        query = query.filter(Condition::parse_from_value(filter_value)?);
+        query = query.filter(match filter_value {
+            Value::Str(src) => Condition::parse_filter(&src)?,
+            other => Condition::parse_from_value(other)?
+        });
     }
 
     let one: bool = archetype.lookup("one").is_ok();
@@ -60,3 +64,141 @@ pub async fn store_write<'ef>(context: &'ef mut Context) -> Result<(), EffectErr
 
     Ok(())
 }
+
+// Apply a patch in state (e.g. a request body) to the stored items the filter matches,
+// as a JSON Patch or a JSON Merge Patch (the default) per the format key. The filter is
+// required, so that a missing one can't patch every item. The number of items patched
+// goes to to_state if given.
+#[apply(effect_fn)]
+pub async fn store_patch<'ef>(context: &'ef mut Context) -> Result<(), EffectError> {
+    let archetype = context.archetype()?;
+
+    let store_name: String = archetype.lookup("to_store")?.try_into()?;
+    let state_key_name: String = archetype.lookup("from_state")?.try_into()?;
+
+    let filter = match archetype.lookup("filter")? {
+        Value::Str(src) => Condition::parse_filter(&src)?,
+        filter_value => Condition::parse_from_value(filter_value)?
+    };
+
+    let patch = state_value(context, &state_key_name)?;
+    let mutation = match archetype.lookup("format").and_then(String::try_from).as_deref() {
+        Ok("json_patch") => Mutation::parse_json_patch(patch)?,
+        Ok("merge_patch") | Err(_) => Mutation::parse_merge_patch(patch),
+        Ok(other) => return Err(EffectError::Internal(format!("invalid patch format {}", other)))
+    };
+
+    let store = context.get::<Store>(store_name)?;
+    let patched = store.update(filter, &mutation).await?;
+
+    if let Ok(count_key) = archetype.lookup("to_state") {
+        let count_key: String = count_key.try_into()?;
+
+        context.set(count_key, Value::Uint64(patched as u64))?;
+    }
+
+    Ok(())
+}
+
+// A value in state, which may have been stored as an optional (e.g. by store_read with
+// one), in which case absence is null.
+fn state_value(context: &Context, key: &str) -> Result<Value, EffectError> {
+    match context.get::<Value>(key) {
+        Ok(value) => Ok(value.clone()),
+        Err(_) => Ok(context.get::<Option<Value>>(key)?.clone().unwrap_or(Value::Null))
+    }
+}
+
+fn state_keys(archetype: &Value, key: &str) -> Result<Vec<String>, EffectError> {
+    let mut keys = Vec::new();
+
+    if let Ok(keys_value) = archetype.lookup(key) {
+        for key_value in keys_value.elements()? {
+            keys.push(key_value.clone().try_into()?);
+        }
+    }
+
+    Ok(keys)
+}
+
+// Run a logic tree with the from_state keys bound as variables, then write the to_state
+// variables back.
+#[apply(effect_fn)]
+pub async fn compute<'ef>(context: &'ef mut Context) -> Result<(), EffectError> {
+    let archetype = context.archetype()?;
+
+    let logic = LogicTree::parse_from_value(archetype.lookup("logic")?)?;
+    let from_state = state_keys(archetype, "from_state")?;
+    let to_state = state_keys(archetype, "to_state")?;
+
+    let mut scope = LogicScope::with_functions(context.registry().functions());
+    for key in from_state {
+        let value = state_value(context, &key)?;
+
+        scope.bind(key, value);
+    }
+
+    logic.validate(&scope.types(), scope.functions())?;
+    logic.execute(&mut scope)?;
+
+    for key in to_state {
+        let value = scope.get(&key).cloned().unwrap_or(Value::Null);
+
+        context.set(key, value)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::sync::Arc;
+    use std::task::{Context as TaskContext, Poll, Waker};
+
+    use super::*;
+    use crate::{InitError, Registry};
+    use crate::schema::Expression;
+
+    // The effects under test never wait on anything, so a single poll completes them.
+    fn complete<T>(future: impl Future<Output = T>) -> T {
+        match std::pin::pin!(future).poll(&mut TaskContext::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("effect is waiting")
+        }
+    }
+
+    #[test]
+    fn compute_effect() {
+        let registry = Registry::new(
+            vec![("compute", compute)], vec![], vec![], vec![], Box::new(|key| Err(InitError::Config(key)))
+        ).unwrap();
+        let mut context = Context::new(Arc::from(registry));
+        context.set("order", Value::map_from([
+            ("count".into(), Value::Uint32(30)),
+            ("price".into(), Value::Uint32(4))
+        ])).unwrap();
+
+        let total = Expression::parse_from_value(Value::map_from([("op".into(), Value::List(Vec::from([
+            Value::str_from("mul"),
+            Value::map_from([("ref".into(), Value::str_from("order.count"))]),
+            Value::map_from([("ref".into(), Value::str_from("order.price"))])
+        ])))])).unwrap();
+        let archetype = Value::map_from([
+            ("logic".into(), Value::map_from([("block".into(), Value::List(Vec::from([
+                Value::map_from([("assign".into(), Value::List(Vec::from([Value::str_from("total"), total.to_value()])))])
+            ])))])),
+            ("from_state".into(), Value::List(Vec::from([Value::str_from("order")]))),
+            ("to_state".into(), Value::List(Vec::from([Value::str_from("total"), Value::str_from("unset")])))
+        ]);
+
+        complete(context.execute("compute".into(), Some(archetype.clone()))).unwrap();
+        assert_eq!(context.get::<Value>("total").unwrap(), &Value::Uint32(120));
+        // Variables the logic didn't assign are written as null.
+        assert_eq!(context.get::<Value>("unset").unwrap(), &Value::Null);
+
+        // Logic that doesn't fit the state fails before it runs.
+        context.set("order", Value::map_from([("count".into(), Value::str_from("30"))])).unwrap();
+        assert!(complete(context.execute("compute".into(), Some(archetype))).is_err());
+    }
+}
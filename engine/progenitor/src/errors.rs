@@ -5,7 +5,9 @@ use std::fmt::{Display, Formatter};
 pub enum InitError {
     Archetype(String),
     Config(String),
-    State(String)
+    State(String),
+    // A registered type that's invalid, e.g. referring to a name that isn't registered.
+    Type(String)
 }
 
 impl Display for InitError {
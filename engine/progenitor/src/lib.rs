@@ -13,7 +13,12 @@ mod registry;
 pub use log;
 
 pub use self::errors::InitError;
-pub use self::schema::{Type, SchemaError, Value, Condition, Mutation};
+pub use self::schema::{
+    Type, TypeRef, Generator, SchemaError, Value, Condition, Comparator, Conjunctive, ValueReference, Mutation, Timestamp, Uuid, Decimal, Constraint, Format, Pattern,
+    ValidationReport, Violation, ValidationMode, Path, Segment, Operator, Expression, Statement, LogicTree, LogicScope,
+    Functions, NativeFunction, SignatureFn, CallFn, FromValue, IntoValue, Typed,
+    ContentHash
+};
 pub use self::serial::{SerialError, SerialFormat, SerialValue};
 pub use self::store::{Store, StoreError};
 pub use self::state::StateError;
@@ -22,7 +27,7 @@ pub use self::registry::Registry;
 
 // TODO: Different packaging.
 pub mod effect {
-    pub use super::effects::{store_read, store_write, open_store};
+    pub use super::effects::{store_read, store_write, store_patch, open_store, compute};
 }
 
 pub mod ext {
@@ -1,14 +1,17 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use super::serial::{SerialFormat, SerialError};
 use super::errors::InitError;
-use super::schema::Type;
+use super::schema::{Type, SchemaError, Functions, NativeFunction};
 use super::store::{Store, StoreError};
 use super::store::ext::StoreDriver;
 use super::effects::{EffectFn, EffectError};
 
 pub struct Registry {
     effects: HashMap<String, EffectFn>,
+    types: HashMap<String, Type>,
+    functions: Arc<Functions>,
     store_drivers: HashMap<String, Box<dyn Fn(&Registry, String) -> Box<dyn StoreDriver>>>,
     serial_formats: HashMap<String, Box<dyn SerialFormat>>,
     config_src: Box<dyn Fn(String) -> Result<String, InitError>>
@@ -17,15 +20,29 @@ pub struct Registry {
 impl Registry {
     pub fn new(
         effect_set: Vec<(&'static str, EffectFn)>,
+        type_set: Vec<(&'static str, Type)>,
         store_driver_set: Vec<(&'static str, Box<dyn Fn(&Registry, String) -> Box<dyn StoreDriver>>)>,
         serial_formats_set: Vec<(&'static str, Box<dyn SerialFormat>)>,
         config_src: Box<dyn Fn(String) -> Result<String, InitError>>
-    ) -> Self {
+    ) -> Result<Self, InitError> {
         let mut effects = HashMap::with_capacity(effect_set.len());
         for (key, effect) in effect_set {
             effects.insert(key.to_owned(), effect);
         }
 
+        let mut types = HashMap::with_capacity(type_set.len());
+        for (key, typ) in type_set {
+            types.insert(key.to_owned(), typ);
+        }
+
+        // Named types may refer to each other (or themselves), so they're linked once all
+        // are known.
+        for (key, typ) in types.iter() {
+            if let Err(err) = typ.link(&|name| types.get(name).cloned()) {
+                return Err(InitError::Type(format!("type {} can't be linked: {}", key, err)));
+            }
+        }
+
         let mut store_drivers = HashMap::with_capacity(store_driver_set.len());
         for (key, factory_fn) in store_driver_set {
             store_drivers.insert(key.to_owned(), factory_fn);
@@ -36,12 +53,25 @@ impl Registry {
             serial_formats.insert(key.to_owned(), format);
         }
 
-        Self {
+        Ok(Self {
             effects,
+            types,
+            functions: Arc::new(Functions::standard()),
             store_drivers,
             serial_formats,
             config_src
+        })
+    }
+
+    // Native functions for logic to call, which extend (or replace) the standard library.
+    pub fn with_functions(mut self, function_set: Vec<(&'static str, NativeFunction)>) -> Self {
+        let mut functions = (*self.functions).clone();
+        for (key, function) in function_set {
+            functions.register(key, function);
         }
+        self.functions = Arc::new(functions);
+
+        self
     }
 
     pub fn create_store(&self, schema: Type, driver_name: &str, store_name: String) -> Result<Store, StoreError> {
@@ -55,6 +85,25 @@ impl Registry {
         Ok(Store::new(schema, driver))
     }
 
+    pub fn get_type(&self, type_name: &str) -> Result<Type, SchemaError> {
+        match self.types.get(type_name) {
+            Some(typ) => Ok(typ.clone()),
+            None => Err(SchemaError::UnresolvedType(type_name.to_owned()))
+        }
+    }
+
+    // Link the named types referenced by a type that was defined elsewhere, e.g. in an
+    // archetype.
+    pub fn resolve_type(&self, typ: Type) -> Result<Type, SchemaError> {
+        typ.link(&|name| self.types.get(name).cloned())?;
+
+        Ok(typ)
+    }
+
+    pub fn functions(&self) -> Arc<Functions> {
+        self.functions.clone()
+    }
+
     pub fn get_serial_format(&self, format_name: &str) -> Result<&Box<dyn SerialFormat>, SerialError> {
         match self.serial_formats.get(format_name) {
             Some(format) => Ok(format),
@@ -73,3 +122,40 @@ impl Registry {
         (self.config_src)(key.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{TypeRef, Value};
+
+    fn with_types(type_set: Vec<(&'static str, Type)>) -> Result<Registry, InitError> {
+        Registry::new(vec![], type_set, vec![], vec![], Box::new(|key| Err(InitError::Config(key))))
+    }
+
+    #[test]
+    fn link_types() {
+        let registry = with_types(Vec::from([
+            ("user", Type::Map(HashMap::from([("friends".into(), Type::List(Box::new(Type::Ref(TypeRef::new("user")))))]))),
+            ("team", Type::List(Box::new(Type::Ref(TypeRef::new("user")))))
+        ])).unwrap();
+        assert!(registry.get_type("team").is_ok());
+
+        // A reference to an unregistered name fails startup rather than validation.
+        assert!(matches!(
+            with_types(Vec::from([("team", Type::List(Box::new(Type::Ref(TypeRef::new("usr")))))])),
+            Err(InitError::Type(_))
+        ));
+    }
+
+    #[test]
+    fn register_functions() {
+        let registry = with_types(Vec::new()).unwrap().with_functions(Vec::from([(
+            "answer",
+            NativeFunction::new(|_| Ok(Type::Uint32), |_| Ok(Value::Uint32(42)))
+        )]));
+
+        assert_eq!(registry.functions().call("answer", &[]).unwrap(), Value::Uint32(42));
+        // The standard library is still there.
+        assert_eq!(registry.functions().call("upper", &[Value::str_from("a")]).unwrap(), Value::str_from("A"));
+    }
+}
@@ -0,0 +1,212 @@
+// A canonical byte encoding of values, and a stable content hash over it, e.g. for ETags,
+// deduplication and memoization. Values that are equal as data encode the same way:
+//
+//  numbers are encoded by value, not variant; integers of any variant, and floats and
+//  decimals with integral values, are all integers (so Uint32(1), Int64(1), Float64(1.0)
+//  and Decimal 1.00 are the same). Other floats and decimals stay distinct from each
+//  other, since a float is rarely exactly the decimal it was written as
+//  -0.0 is 0, and every NaN is the same NaN
+//  decimals are normalized, so 1.50 and 1.5 are the same
+//  map members are ordered by the bytes of their (UTF-8) keys
+//  strings are encoded as they are, without Unicode normalization
+//
+// Each value is a tag byte followed by its content, with lengths and numbers big-endian.
+// The encoding is a stable format: changing it changes every hash.
+use std::fmt::{Display, Formatter};
+
+use sha2::{Digest, Sha256};
+
+use super::primitives::Value;
+
+const NULL: u8 = 0x00;
+const BOOL: u8 = 0x01;
+const INTEGER: u8 = 0x02;
+const FLOAT: u8 = 0x03;
+const DECIMAL: u8 = 0x04;
+const STR: u8 = 0x05;
+const BYTES: u8 = 0x06;
+const TIMESTAMP: u8 = 0x07;
+const UUID: u8 = 0x08;
+const LIST: u8 = 0x09;
+const MAP: u8 = 0x0a;
+
+const CANONICAL_NAN: u64 = 0x7ff8_0000_0000_0000;
+
+fn encode_length(length: usize, out: &mut Vec<u8>) {
+    out.extend_from_slice(&(length as u64).to_be_bytes());
+}
+
+fn encode_integer(integer: i128, out: &mut Vec<u8>) {
+    out.push(INTEGER);
+    out.extend_from_slice(&integer.to_be_bytes());
+}
+
+fn encode_into(value: &Value, out: &mut Vec<u8>) {
+    match value {
+        Value::Null => out.push(NULL),
+        Value::Bool(truth) => out.extend_from_slice(&[BOOL, *truth as u8]),
+        Value::Int32(_) | Value::Uint32(_) | Value::Int64(_) | Value::Uint64(_) => {
+            encode_integer(value.as_integer().unwrap_or_default(), out);
+        },
+        // Integral floats below 2^127 convert to i128 exactly.
+        Value::Float64(float) if float.fract() == 0.0 && float.abs() < 2f64.powi(127) => {
+            encode_integer(*float as i128, out);
+        },
+        Value::Float64(float) => {
+            let bits = match float.is_nan() {
+                true => CANONICAL_NAN,
+                false => float.to_bits()
+            };
+
+            out.push(FLOAT);
+            out.extend_from_slice(&bits.to_be_bytes());
+        },
+        Value::Decimal(decimal) => {
+            let normalized = decimal.normalized();
+
+            match normalized.scale() {
+                0 => encode_integer(normalized.mantissa(), out),
+                scale => {
+                    out.push(DECIMAL);
+                    out.extend_from_slice(&normalized.mantissa().to_be_bytes());
+                    out.extend_from_slice(&scale.to_be_bytes());
+                }
+            }
+        },
+        Value::Str(string) => {
+            out.push(STR);
+            encode_length(string.len(), out);
+            out.extend_from_slice(string.as_bytes());
+        },
+        Value::Bytes(bytes) => {
+            out.push(BYTES);
+            encode_length(bytes.len(), out);
+            out.extend_from_slice(bytes);
+        },
+        Value::Timestamp(timestamp) => {
+            out.push(TIMESTAMP);
+            out.extend_from_slice(&timestamp.micros().to_be_bytes());
+        },
+        Value::Uuid(uuid) => {
+            out.push(UUID);
+            out.extend_from_slice(uuid.as_bytes());
+        },
+        Value::List(elements) => {
+            out.push(LIST);
+            encode_length(elements.len(), out);
+
+            for element in elements {
+                encode_into(element, out);
+            }
+        },
+        Value::Map(members) => {
+            let mut keys: Vec<&String> = members.keys().collect();
+            keys.sort();
+
+            out.push(MAP);
+            encode_length(keys.len(), out);
+
+            for key in keys {
+                encode_length(key.len(), out);
+                out.extend_from_slice(key.as_bytes());
+                encode_into(&members[key], out);
+            }
+        }
+    }
+}
+
+// A SHA-256 digest of a canonical encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ContentHash([u8; 32]);
+
+impl ContentHash {
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+// Lowercase hex.
+impl Display for ContentHash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for byte in self.0.iter() {
+            write!(f, "{:02x}", byte)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Value {
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        encode_into(self, &mut out);
+
+        out
+    }
+
+    pub fn content_hash(&self) -> ContentHash {
+        ContentHash(Sha256::digest(self.canonical_bytes()).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use super::super::scalars::Decimal;
+
+    #[test]
+    fn equal_data_encodes_equally() {
+        let same = [
+            (Value::Uint32(1), Value::Int64(1)),
+            (Value::Int32(-3), Value::Float64(-3.0)),
+            (Value::Uint64(2), Value::Decimal(Decimal::new(200, 2))),
+            (Value::Float64(-0.0), Value::Uint32(0)),
+            (Value::Float64(f64::NAN), Value::Float64(-f64::NAN)),
+            (Value::Decimal(Decimal::new(150, 2)), Value::Decimal(Decimal::new(15, 1)))
+        ];
+        for (a, b) in same {
+            assert_eq!(a.canonical_bytes(), b.canonical_bytes(), "{:?} and {:?}", a, b);
+        }
+
+        let different = [
+            (Value::Float64(1.5), Value::Decimal(Decimal::new(15, 1))),
+            (Value::Null, Value::Bool(false)),
+            (Value::Str("1".into()), Value::Uint32(1)),
+            (Value::Str("ab".into()), Value::Bytes(b"ab".to_vec())),
+            (Value::List(Vec::from([Value::str_from("ab")])), Value::List(Vec::from([Value::str_from("a"), Value::str_from("b")])))
+        ];
+        for (a, b) in different {
+            assert_ne!(a.canonical_bytes(), b.canonical_bytes(), "{:?} and {:?}", a, b);
+        }
+
+        // Members are in the order of their keys' bytes.
+        let keys = ["b", "a", "é", "Z", "aa"];
+        let encoded = Value::Map(keys.iter().map(|key| (key.to_string(), Value::Null)).collect::<HashMap<_, _>>())
+            .canonical_bytes();
+        let position = |key: &str| {
+            let mut member = Vec::new();
+            encode_length(key.len(), &mut member);
+            member.extend_from_slice(key.as_bytes());
+            member.push(NULL);
+
+            encoded.windows(member.len()).position(|window| window == member.as_slice()).unwrap()
+        };
+
+        let mut ordered = keys.to_vec();
+        ordered.sort_by_key(|key| position(key));
+        assert_eq!(ordered, ["Z", "a", "aa", "b", "é"]);
+    }
+
+    #[test]
+    fn stable() {
+        // Pinned, so a change to the encoding can't go unnoticed.
+        let value = Value::map_from([
+            ("name".into(), Value::str_from("ada")),
+            ("tags".into(), Value::List(Vec::from([Value::Uint32(1), Value::Float64(2.5), Value::Null])))
+        ]);
+
+        assert_eq!(value.content_hash().to_string(), "8944d66c80c7d34147c6221e07f9f0d8d5bd5b67d08315583e829d9b04081e5e");
+    }
+}
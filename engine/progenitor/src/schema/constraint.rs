@@ -0,0 +1,255 @@
+// Constraints on values beyond their shape, attached to a type with Type::Constrained.
+use std::cmp::Ordering;
+use std::fmt::{Debug, Formatter};
+
+use regex::Regex;
+
+use super::errors::SchemaError;
+use super::primitives::{Type, Value};
+
+// A compiled regular expression that compares by its source.
+#[derive(Clone)]
+pub struct Pattern {
+    regex: Regex
+}
+
+impl Pattern {
+    pub fn new(source: &str) -> Result<Self, SchemaError> {
+        match Regex::new(source) {
+            Ok(regex) => Ok(Self { regex }),
+            Err(err) => Err(SchemaError::InvalidDefinition(format!("invalid pattern: {}", err)))
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        self.regex.as_str()
+    }
+
+    pub fn is_match(&self, string: &str) -> bool {
+        self.regex.is_match(string)
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.source() == other.source()
+    }
+}
+
+impl Debug for Pattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Pattern({:?})", self.source())
+    }
+}
+
+// Well known string formats. These are pragmatic checks rather than full grammars.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Format {
+    // local@domain.tld, without whitespace.
+    Email,
+    // scheme://host[...], where the scheme is alphanumeric (or +, -, .).
+    Url
+}
+
+impl Format {
+    pub fn parse_from_value(value: Value) -> Result<Self, SchemaError> {
+        let which: String = value.try_into()?;
+
+        match which.as_str() {
+            "email" => Ok(Self::Email),
+            "url" => Ok(Self::Url),
+            _ => Err(SchemaError::InvalidDefinition(format!("unknown format {}", which)))
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Email => "email",
+            Self::Url => "url"
+        }
+    }
+
+    pub fn is_match(&self, string: &str) -> bool {
+        if string.chars().any(char::is_whitespace) {
+            return false;
+        }
+
+        match self {
+            Self::Email => {
+                match string.split_once('@') {
+                    Some((local, domain)) => {
+                        !local.is_empty() && !domain.contains('@')
+                            && domain.split('.').count() > 1
+                            && domain.split('.').all(|label| !label.is_empty())
+                    },
+                    None => false
+                }
+            },
+            Self::Url => {
+                match string.split_once("://") {
+                    Some((scheme, rest)) => {
+                        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                            && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+                            && !rest.is_empty() && !rest.starts_with('/')
+                    },
+                    None => false
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constraint {
+    // Inclusive numeric bounds.
+    Min(Value),
+    Max(Value),
+    // Inclusive bounds on the length of a string (in characters), bytes, list, or map.
+    MinLength(usize),
+    MaxLength(usize),
+    Pattern(Pattern),
+    Format(Format),
+    // List elements are pairwise distinct.
+    Unique
+}
+
+// Names of the definition notation members each constraint is read from.
+pub(super) const CONSTRAINT_KEYS: [&str; 7] = [
+    "min", "max", "min_length", "max_length", "pattern", "format", "unique"
+];
+
+fn length_of(value: &Value) -> Option<usize> {
+    match value {
+        Value::Str(string) => Some(string.chars().count()),
+        Value::Bytes(data) => Some(data.len()),
+        Value::List(members) => Some(members.len()),
+        Value::Map(members) => Some(members.len()),
+        _ => None
+    }
+}
+
+impl Constraint {
+    pub fn parse_from_value(key: &str, value: Value) -> Result<Self, SchemaError> {
+        let length = |value: Value| -> Result<usize, SchemaError> {
+            let length: u64 = value.try_into()?;
+
+            usize::try_from(length).map_err(|_| SchemaError::InvalidDefinition("length out of range".into()))
+        };
+        // A bound nothing compares with would fail every value.
+        let bound = |value: Value| -> Result<Value, SchemaError> {
+            match value {
+                Value::Float64(float) if !float.is_finite() => {},
+                Value::Int32(_) | Value::Uint32(_) | Value::Int64(_) | Value::Uint64(_)
+                    | Value::Float64(_) | Value::Decimal(_) => return Ok(value),
+                _ => {}
+            }
+
+            Err(SchemaError::InvalidDefinition(format!("{} bound must be a finite number", key)))
+        };
+
+        Ok(match key {
+            "min" => Self::Min(bound(value)?),
+            "max" => Self::Max(bound(value)?),
+            "min_length" => Self::MinLength(length(value)?),
+            "max_length" => Self::MaxLength(length(value)?),
+            "pattern" => Self::Pattern(Pattern::new(&String::try_from(value)?)?),
+            "format" => Self::Format(Format::parse_from_value(value)?),
+            "unique" => Self::Unique,
+            _ => return Err(SchemaError::InvalidDefinition(format!("unknown constraint {}", key)))
+        })
+    }
+
+    // The definition notation member for this constraint.
+    pub fn to_member(&self) -> (String, Value) {
+        let (key, value) = match self {
+            Self::Min(bound) => ("min", bound.clone()),
+            Self::Max(bound) => ("max", bound.clone()),
+            Self::MinLength(length) => ("min_length", Value::Uint64(*length as u64)),
+            Self::MaxLength(length) => ("max_length", Value::Uint64(*length as u64)),
+            Self::Pattern(pattern) => ("pattern", Value::str_from(pattern.source())),
+            Self::Format(format) => ("format", Value::str_from(format.name())),
+            Self::Unique => ("unique", Value::Bool(true))
+        };
+
+        (key.to_owned(), value)
+    }
+
+    // Whether this constraint is meaningful for values of the given type.
+    pub fn applies_to(&self, typ: &Type) -> bool {
+        match (self, typ.base()) {
+            (Self::Min(_) | Self::Max(_), base) => base.is_numeric(),
+            (Self::MinLength(_) | Self::MaxLength(_), base) => matches!(
+                base, Type::String | Type::Bytes | Type::List(_) | Type::Map(_) | Type::Any
+            ),
+            (Self::Pattern(_) | Self::Format(_), base) => matches!(base, Type::String | Type::Any),
+            (Self::Unique, base) => matches!(base, Type::List(_) | Type::Any)
+        }
+    }
+
+    pub fn check(&self, value: &Value) -> Result<(), SchemaError> {
+        let satisfied = match self {
+            Self::Min(bound) => matches!(
+                value.compare_numeric(bound), Some(Ordering::Greater | Ordering::Equal)
+            ),
+            Self::Max(bound) => matches!(
+                value.compare_numeric(bound), Some(Ordering::Less | Ordering::Equal)
+            ),
+            Self::MinLength(min) => length_of(value).is_some_and(|length| length >= *min),
+            Self::MaxLength(max) => length_of(value).is_some_and(|length| length <= *max),
+            Self::Pattern(pattern) => matches!(value, Value::Str(string) if pattern.is_match(string)),
+            Self::Format(format) => matches!(value, Value::Str(string) if format.is_match(string)),
+            Self::Unique => {
+                match value {
+                    Value::List(members) => members.iter()
+                        .enumerate()
+                        .all(|(i, member)| !members[i + 1..].contains(member)),
+                    _ => false
+                }
+            }
+        };
+
+        match satisfied {
+            true => Ok(()),
+            false => Err(SchemaError::ConstraintViolated(self.clone()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_constraints() {
+        assert!(Constraint::Min(Value::Uint32(3)).check(&Value::Int64(3)).is_ok());
+        assert!(Constraint::Min(Value::Uint32(3)).check(&Value::Float64(2.5)).is_err());
+        assert!(Constraint::Max(Value::Float64(1.5)).check(&Value::Int32(-4)).is_ok());
+
+        assert!(Constraint::MinLength(1).check(&Value::str_from("")).is_err());
+        assert!(Constraint::MaxLength(2).check(&Value::str_from("éé")).is_ok());
+
+        let pattern = Constraint::Pattern(Pattern::new("^[a-z]+$").unwrap());
+        assert!(pattern.check(&Value::str_from("abc")).is_ok());
+        assert!(pattern.check(&Value::str_from("Abc")).is_err());
+
+        assert!(Constraint::Format(Format::Email).check(&Value::str_from("a@b.co")).is_ok());
+        assert!(Constraint::Format(Format::Email).check(&Value::str_from("a@b")).is_err());
+        assert!(Constraint::Format(Format::Url).check(&Value::str_from("https://x.io/a")).is_ok());
+        assert!(Constraint::Format(Format::Url).check(&Value::str_from("x.io")).is_err());
+
+        assert!(Constraint::Unique.check(&Value::List(Vec::from([Value::Uint32(1), Value::Uint32(2)]))).is_ok());
+        assert!(Constraint::Unique.check(&Value::List(Vec::from([Value::Uint32(1), Value::Uint32(1)]))).is_err());
+    }
+
+    #[test]
+    fn parse_bounds() {
+        assert_eq!(Constraint::parse_from_value("min", Value::Int32(-1)).unwrap(), Constraint::Min(Value::Int32(-1)));
+        assert_eq!(Constraint::parse_from_value("max", Value::Float64(0.5)).unwrap(), Constraint::Max(Value::Float64(0.5)));
+
+        for bound in [Value::str_from("10"), Value::Null, Value::Bool(true), Value::Float64(f64::NAN)] {
+            assert!(matches!(
+                Constraint::parse_from_value("min", bound.clone()), Err(SchemaError::InvalidDefinition(_))
+            ), "{:?} should be rejected", bound);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+// Conversions between Rust types and indirectly represented data, so custom code can
+// work with typed data that still agrees with archetype schemas. progenitor_derive
+// implements these (and TryFrom<Value> and Into<Value>) for structs and enums.
+use super::errors::SchemaError;
+use super::primitives::{Type, Value};
+use super::scalars::{Timestamp, Uuid, Decimal};
+
+pub trait FromValue: Sized {
+    fn from_value(value: Value) -> Result<Self, SchemaError>;
+}
+
+pub trait IntoValue {
+    fn into_value(self) -> Value;
+}
+
+// A Rust type's description as a Type.
+pub trait Typed {
+    fn value_type() -> Type;
+}
+
+macro_rules! primitive_conversions {
+    ($v: path, $t: ty, $typ: expr) => {
+        impl FromValue for $t {
+            fn from_value(value: Value) -> Result<Self, SchemaError> {
+                value.try_into()
+            }
+        }
+
+        impl IntoValue for $t {
+            fn into_value(self) -> Value {
+                $v(self)
+            }
+        }
+
+        impl Typed for $t {
+            fn value_type() -> Type {
+                $typ
+            }
+        }
+    };
+}
+
+primitive_conversions!(Value::Bool, bool, Type::Bool);
+primitive_conversions!(Value::Int32, i32, Type::Int32);
+primitive_conversions!(Value::Uint32, u32, Type::Uint32);
+primitive_conversions!(Value::Int64, i64, Type::Int64);
+primitive_conversions!(Value::Uint64, u64, Type::Uint64);
+primitive_conversions!(Value::Float64, f64, Type::Float64);
+primitive_conversions!(Value::Decimal, Decimal, Type::Decimal);
+primitive_conversions!(Value::Str, String, Type::String);
+primitive_conversions!(Value::Timestamp, Timestamp, Type::Timestamp);
+primitive_conversions!(Value::Uuid, Uuid, Type::Uuid);
+
+impl FromValue for Value {
+    fn from_value(value: Value) -> Result<Self, SchemaError> {
+        Ok(value)
+    }
+}
+
+impl IntoValue for Value {
+    fn into_value(self) -> Value {
+        self
+    }
+}
+
+impl Typed for Value {
+    fn value_type() -> Type {
+        Type::Any
+    }
+}
+
+// Null is None.
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: Value) -> Result<Self, SchemaError> {
+        match value {
+            Value::Null => Ok(None),
+            other => Ok(Some(T::from_value(other)?))
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Option<T> {
+    fn into_value(self) -> Value {
+        self.map_or(Value::Null, T::into_value)
+    }
+}
+
+impl<T: Typed> Typed for Option<T> {
+    fn value_type() -> Type {
+        Type::Optional(Box::new(T::value_type()))
+    }
+}
+
+// Lists. u8 has no Value of its own, so Vec<u8> is bytes through TryFrom, and fields
+// of it are bytes where Typed is derived.
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: Value) -> Result<Self, SchemaError> {
+        match value {
+            Value::List(elements) => elements.into_iter().map(T::from_value).collect(),
+            _ => Err(SchemaError::InvalidCast(Type::List(Box::new(Type::Any))))
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn into_value(self) -> Value {
+        Value::List(self.into_iter().map(T::into_value).collect())
+    }
+}
+
+impl<T: Typed> Typed for Vec<T> {
+    fn value_type() -> Type {
+        Type::List(Box::new(T::value_type()))
+    }
+}
+
+impl<T: FromValue> FromValue for Box<T> {
+    fn from_value(value: Value) -> Result<Self, SchemaError> {
+        Ok(Box::new(T::from_value(value)?))
+    }
+}
+
+impl<T: IntoValue> IntoValue for Box<T> {
+    fn into_value(self) -> Value {
+        (*self).into_value()
+    }
+}
+
+impl<T: Typed> Typed for Box<T> {
+    fn value_type() -> Type {
+        T::value_type()
+    }
+}
@@ -0,0 +1,406 @@
+// Declarative schema notation, so that types don't have to be inferred from examples.
+//
+// A definition is a map with a "$type" member naming the type, plus members specific to
+// that type:
+//
+//  {"$type": "string"}
+//  {"$type": "int32", "optional": true}
+//  {"$type": "list", "of": <definition>}
+//  {"$type": "map", "fields": {"name": <definition>, ...}}
+//  {"$type": "optional", "of": <definition>}
+//  {"$type": "enum", "values": ["a", "b"]}
+//  {"$type": "union", "discriminator": "kind", "variants": {"a": <definition>, ...}}
+//  {"$type": "ref", "name": "user"}  (a named type from the Registry)
+//
+// Any definition may also carry constraints:
+//
+//  {"$type": "string", "min_length": 1, "max_length": 64, "pattern": "^[a-z]+$"}
+//  {"$type": "string", "format": "email"}  (or "url")
+//  {"$type": "int32", "min": 0, "max": 100}
+//  {"$type": "list", "of": <definition>, "unique": true}
+//
+// and generated values, either the current time or a constant:
+//
+//  {"$type": "timestamp", "computed": "now"}  (always generated)
+//  {"$type": "uint32", "default": {"constant": 0}}  (generated when absent or null)
+//
+// Anything without a "$type" member is treated as an example value and the type is
+// inferred from it, so existing example-based schemas keep working as a shorthand.
+// The marker can't be mistaken for an example's own "type" member, and a definition
+// naming an unknown type is an error rather than an example.
+use std::collections::HashMap;
+
+use super::constraint::{Constraint, CONSTRAINT_KEYS};
+use super::errors::SchemaError;
+use super::generated::Generator;
+use super::named::TypeRef;
+use super::primitives::{Type, Value};
+
+const SCALAR_NAMES: [(&str, Type); 12] = [
+    ("any", Type::Any),
+    ("bool", Type::Bool),
+    ("int32", Type::Int32),
+    ("uint32", Type::Uint32),
+    ("int64", Type::Int64),
+    ("uint64", Type::Uint64),
+    ("float64", Type::Float64),
+    ("decimal", Type::Decimal),
+    ("string", Type::String),
+    ("bytes", Type::Bytes),
+    ("timestamp", Type::Timestamp),
+    ("uuid", Type::Uuid)
+];
+
+const COMPOSITE_NAMES: [&str; 6] = ["list", "map", "optional", "enum", "union", "ref"];
+
+// The member that marks a map as a definition.
+const MARKER: &str = "$type";
+
+fn invalid(message: impl Into<String>) -> SchemaError {
+    SchemaError::InvalidDefinition(message.into())
+}
+
+// The type name of a definition, or None for an example.
+fn definition_name(value: &Value) -> Result<Option<String>, SchemaError> {
+    let name = match value {
+        Value::Map(members) => match members.get(MARKER) {
+            Some(Value::Str(name)) => name.clone(),
+            Some(_) => return Err(invalid(format!("expected a type name at \"{}\"", MARKER))),
+            None => return Ok(None)
+        },
+        _ => return Ok(None)
+    };
+
+    let known = SCALAR_NAMES.iter().any(|(scalar, _)| *scalar == name)
+        || COMPOSITE_NAMES.contains(&name.as_str());
+
+    match known {
+        true => Ok(Some(name)),
+        false => Err(invalid(format!("unknown type {}", name)))
+    }
+}
+
+fn parse_members(value: Value, key: &str) -> Result<HashMap<String, Type>, SchemaError> {
+    let members = match value.lookup(key) {
+        Ok(Value::Map(members)) => members,
+        _ => return Err(invalid(format!("expected a map of definitions at \"{}\"", key)))
+    };
+
+    let mut parsed = HashMap::with_capacity(members.len());
+    for (name, member) in members {
+        parsed.insert(name, Type::parse_from_value(member)?);
+    }
+
+    Ok(parsed)
+}
+
+fn parse_constraints(value: &Value, typ: &Type) -> Result<Vec<Constraint>, SchemaError> {
+    let mut constraints = Vec::new();
+
+    for key in CONSTRAINT_KEYS {
+        let member = match value.lookup(key) {
+            Ok(member) => member,
+            Err(_) => continue
+        };
+
+        // "unique": false is the same as leaving it out.
+        if key == "unique" && !bool::try_from(member.clone())? {
+            continue;
+        }
+
+        let constraint = Constraint::parse_from_value(key, member)?;
+        if !constraint.applies_to(typ) {
+            return Err(invalid(format!("constraint {} doesn't apply to {:?}", key, typ)));
+        }
+
+        constraints.push(constraint);
+    }
+
+    Ok(constraints)
+}
+
+fn members_to_value(members: &HashMap<String, Type>) -> Value {
+    Value::Map(members.iter()
+        .map(|(name, member)| (name.clone(), member.to_value()))
+        .collect())
+}
+
+impl Type {
+    pub fn parse_from_value(value: Value) -> Result<Self, SchemaError> {
+        let name = match definition_name(&value)? {
+            Some(name) => name,
+            None => return value.try_into()
+        };
+
+        let parsed = match name.as_str() {
+            "list" => Self::List(Box::new(Self::parse_from_value(value.lookup("of")?)?)),
+            "optional" => Self::Optional(Box::new(Self::parse_from_value(value.lookup("of")?)?)),
+            "map" => Self::Map(parse_members(value.clone(), "fields")?),
+            "enum" => {
+                let mut options = Vec::new();
+                for option in value.lookup("values")?.elements()? {
+                    options.push(option.clone().try_into()?);
+                }
+
+                Self::Enum(options)
+            },
+            "union" => Self::Union(
+                value.lookup("discriminator")?.try_into()?,
+                parse_members(value.clone(), "variants")?
+            ),
+            "ref" => Self::Ref(TypeRef::new(String::try_from(value.lookup("name")?)?)),
+            scalar => {
+                SCALAR_NAMES.iter()
+                    .find(|(candidate, _)| *candidate == scalar)
+                    .map(|(_, typ)| typ.clone())
+                    .ok_or_else(|| invalid(format!("unknown type {}", scalar)))?
+            }
+        };
+
+        let constraints = parse_constraints(&value, &parsed)?;
+        let parsed = match constraints.len() {
+            0 => parsed,
+            _ => Self::Constrained(Box::new(parsed), constraints)
+        };
+
+        let optional = match value.lookup("optional") {
+            Ok(flag) => flag.try_into()?,
+            Err(_) => false
+        };
+
+        let parsed = match optional {
+            true => Self::Optional(Box::new(parsed)),
+            false => parsed
+        };
+
+        let generated = |key: &str| -> Result<Option<Generator>, SchemaError> {
+            let generator = match value.lookup(key) {
+                Ok(generator) => Generator::parse_from_value(generator)?,
+                Err(_) => return Ok(None)
+            };
+            generator.check_produces(&parsed)?;
+
+            Ok(Some(generator))
+        };
+
+        Ok(match (generated("default")?, generated("computed")?) {
+            (Some(_), Some(_)) => return Err(invalid("a value can't be both defaulted and computed")),
+            (Some(generator), None) => Self::Default(Box::new(parsed), generator),
+            (None, Some(generator)) => Self::Computed(Box::new(parsed), generator),
+            (None, None) => parsed
+        })
+    }
+
+    // The inverse of parse_from_value, always in the explicit notation.
+    pub fn to_value(&self) -> Value {
+        let named = |name: &str| -> Vec<(String, Value)> {
+            Vec::from([(MARKER.to_owned(), Value::str_from(name))])
+        };
+        // Wrappers add members to the definition of what they wrap.
+        let extended = |inner: &Type| -> Vec<(String, Value)> {
+            match inner.to_value() {
+                Value::Map(members) => members.into_iter().collect(),
+                _ => unreachable!()
+            }
+        };
+
+        let members = match self {
+            Self::List(inner) => {
+                let mut members = named("list");
+                members.push(("of".into(), inner.to_value()));
+
+                members
+            },
+            Self::Map(fields) => {
+                let mut members = named("map");
+                members.push(("fields".into(), members_to_value(fields)));
+
+                members
+            },
+            Self::Optional(inner) => {
+                let mut members = named("optional");
+                members.push(("of".into(), inner.to_value()));
+
+                members
+            },
+            Self::Enum(options) => {
+                let mut members = named("enum");
+                members.push((
+                    "values".into(),
+                    Value::List(options.iter().map(Value::str_from).collect())
+                ));
+
+                members
+            },
+            Self::Union(discriminator, variants) => {
+                let mut members = named("union");
+                members.push(("discriminator".into(), Value::str_from(discriminator)));
+                members.push(("variants".into(), members_to_value(variants)));
+
+                members
+            },
+            Self::Ref(reference) => {
+                let mut members = named("ref");
+                members.push(("name".into(), Value::str_from(reference.name())));
+
+                members
+            },
+            Self::Constrained(inner, constraints) => {
+                let mut members = extended(inner);
+                members.extend(constraints.iter().map(Constraint::to_member));
+
+                members
+            },
+            Self::Default(inner, generator) => {
+                let mut members = extended(inner);
+                members.push(("default".into(), generator.to_value()));
+
+                members
+            },
+            Self::Computed(inner, generator) => {
+                let mut members = extended(inner);
+                members.push(("computed".into(), generator.to_value()));
+
+                members
+            },
+            scalar => {
+                let name = SCALAR_NAMES.iter()
+                    .find(|(_, candidate)| candidate == scalar)
+                    .map(|(name, _)| *name)
+                    .unwrap_or("any");
+
+                named(name)
+            }
+        };
+
+        Value::Map(members.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::Format;
+
+    #[test]
+    fn parse_definition() {
+        let typ = Type::parse_from_value(Value::map_from([
+            ("$type".into(), Value::str_from("map")),
+            ("fields".into(), Value::map_from([
+                ("tags".into(), Value::map_from([
+                    ("$type".into(), Value::str_from("list")),
+                    ("of".into(), Value::map_from([("$type".into(), Value::str_from("string"))]))
+                ])),
+                ("score".into(), Value::map_from([
+                    ("$type".into(), Value::str_from("float64")),
+                    ("optional".into(), Value::Bool(true))
+                ])),
+                // Example shorthand is accepted at any depth.
+                ("name".into(), Value::str_from("james"))
+            ]))
+        ])).unwrap();
+
+        assert_eq!(typ, Type::Map(HashMap::from([
+            ("tags".into(), Type::List(Box::new(Type::String))),
+            ("score".into(), Type::Optional(Box::new(Type::Float64))),
+            ("name".into(), Type::String)
+        ])));
+    }
+
+    #[test]
+    fn parse_example() {
+        assert_eq!(
+            Type::parse_from_value(Value::map_from([("type".into(), Value::Uint32(1))])).unwrap(),
+            Type::Map(HashMap::from([("type".into(), Type::Uint32)]))
+        );
+
+        // A sample's own "type" member doesn't make it a definition.
+        assert_eq!(
+            Type::parse_from_value(Value::map_from([("type".into(), Value::str_from("string"))])).unwrap(),
+            Type::Map(HashMap::from([("type".into(), Type::String)]))
+        );
+    }
+
+    #[test]
+    fn parse_unknown_type() {
+        assert!(matches!(
+            Type::parse_from_value(Value::map_from([("$type".into(), Value::str_from("strnig"))])),
+            Err(SchemaError::InvalidDefinition(_))
+        ));
+        assert!(Type::parse_from_value(Value::map_from([("$type".into(), Value::Uint32(1))])).is_err());
+    }
+
+    #[test]
+    fn definition_round_trip() {
+        let typ = Type::Union("kind".into(), HashMap::from([
+            ("card".into(), Type::Map(HashMap::from([
+                ("number".into(), Type::String),
+                ("expiry".into(), Type::Optional(Box::new(Type::Timestamp)))
+            ]))),
+            ("bank".into(), Type::Map(HashMap::from([
+                ("currency".into(), Type::Enum(Vec::from(["cad".into(), "usd".into()])))
+            ])))
+        ]));
+
+        assert_eq!(Type::parse_from_value(typ.to_value()).unwrap(), typ);
+    }
+
+    #[test]
+    fn parse_constrained() {
+        let typ = Type::parse_from_value(Value::map_from([
+            ("$type".into(), Value::str_from("string")),
+            ("format".into(), Value::str_from("email")),
+            ("max_length".into(), Value::Uint32(64)),
+            ("optional".into(), Value::Bool(true))
+        ])).unwrap();
+
+        assert_eq!(typ, Type::Optional(Box::new(Type::Constrained(Box::new(Type::String), Vec::from([
+            Constraint::MaxLength(64),
+            Constraint::Format(Format::Email)
+        ])))));
+        assert_eq!(Type::parse_from_value(typ.to_value()).unwrap(), typ);
+
+        assert!(typ.validate(&Value::Null).is_ok());
+        assert!(typ.validate(&Value::str_from("a@b.co")).is_ok());
+        assert!(matches!(
+            typ.report(&Value::str_from("nope")).violations()[0].cause(),
+            SchemaError::ConstraintViolated(Constraint::Format(_))
+        ));
+
+        assert!(Type::parse_from_value(Value::map_from([
+            ("$type".into(), Value::str_from("bool")),
+            ("min".into(), Value::Uint32(1))
+        ])).is_err());
+    }
+
+    #[test]
+    fn parse_generated() {
+        let typ = Type::parse_from_value(Value::map_from([
+            ("$type".into(), Value::str_from("map")),
+            ("fields".into(), Value::map_from([
+                ("visits".into(), Value::map_from([
+                    ("$type".into(), Value::str_from("uint32")),
+                    ("default".into(), Value::map_from([("constant".into(), Value::Uint32(0))]))
+                ])),
+                ("created".into(), Value::map_from([
+                    ("$type".into(), Value::str_from("timestamp")),
+                    ("computed".into(), Value::str_from("now"))
+                ]))
+            ]))
+        ])).unwrap();
+
+        assert_eq!(Type::parse_from_value(typ.to_value()).unwrap(), typ);
+
+        let completed = typ.normalize(&Value::map_from([
+            ("created".into(), Value::str_from("yesterday"))
+        ]), Default::default()).unwrap();
+
+        assert_eq!(completed.lookup("visits").unwrap(), Value::Uint32(0));
+        assert!(matches!(completed.lookup("created").unwrap(), Value::Timestamp(_)));
+
+        assert!(Type::parse_from_value(Value::map_from([
+            ("$type".into(), Value::str_from("string")),
+            ("computed".into(), Value::str_from("now"))
+        ])).is_err());
+    }
+}
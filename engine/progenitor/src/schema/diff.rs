@@ -0,0 +1,391 @@
+// Structural differences between values, as mutations that turn one into the other.
+//
+// Maps are diffed member by member and lists by their longest common subsequence of
+// elements, so an element inserted or removed in the middle of a list is one edit rather
+// than a change to every element after it. Where elements are removed and inserted at
+// the same place they're diffed as a change instead. Lists whose elements differ in too
+// many places between their common start and end are replaced as a whole.
+use std::iter;
+
+use super::errors::SchemaError;
+use super::primitives::Value;
+use super::path::{Path, Segment};
+use super::expr::{Comparator, Condition, Conjunctive, ValueReference};
+use super::mutation::Mutation;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Edit {
+    Keep,
+    Remove,
+    Add
+}
+
+// The most cells the table of common subsequence lengths may have, past which the whole
+// list is replaced instead.
+const MAX_TABLE: usize = 1 << 20;
+
+// The edits turning one list into another, in order, if they're few enough to find.
+fn list_edits(old: &[Value], new: &[Value]) -> Option<Vec<Edit>> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let (old, new) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+
+    if (old.len() + 1).saturating_mul(new.len() + 1) > MAX_TABLE {
+        return None;
+    }
+
+    // common[i][j] is the length of the longest common subsequence of old[i..] and new[j..].
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = match old[i] == new[j] {
+                true => common[i + 1][j + 1] + 1,
+                false => common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut edits = vec![Edit::Keep; prefix];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            edits.push(Edit::Keep);
+            (i, j) = (i + 1, j + 1);
+        }
+        else if j < new.len() && (i == old.len() || common[i][j + 1] >= common[i + 1][j]) {
+            edits.push(Edit::Add);
+            j += 1;
+        }
+        else {
+            edits.push(Edit::Remove);
+            i += 1;
+        }
+    }
+    edits.extend(iter::repeat_n(Edit::Keep, suffix));
+
+    Some(edits)
+}
+
+fn diff_lists(path: &Path, old: &[Value], new: &[Value], parts: &mut Vec<Mutation>) {
+    let edits = match list_edits(old, new) {
+        Some(edits) => edits,
+        None => return parts.push(Mutation::Set(path.clone(), Value::List(new.to_vec())))
+    };
+
+    // The index in the list as edited so far.
+    let mut at = 0;
+    let (mut i, mut j, mut k) = (0, 0, 0);
+    while k < edits.len() {
+        let run_end = edits[k..].iter().position(|edit| *edit == Edit::Keep).map_or(edits.len(), |end| k + end);
+        if run_end == k {
+            (at, i, j, k) = (at + 1, i + 1, j + 1, k + 1);
+            continue;
+        }
+
+        // A run of removals and additions: pair them up as changes, then remove or add
+        // whatever is left over.
+        let removed = edits[k..run_end].iter().filter(|edit| **edit == Edit::Remove).count();
+        let added = run_end - k - removed;
+
+        for _ in 0..removed.min(added) {
+            diff_at(&path.clone().child(Segment::Index(at)), &old[i], &new[j], parts);
+            (at, i, j) = (at + 1, i + 1, j + 1);
+        }
+        for _ in added..removed {
+            parts.push(Mutation::Unset(path.clone().child(Segment::Index(at))));
+            i += 1;
+        }
+        for _ in removed..added {
+            parts.push(Mutation::Insert(path.clone().child(Segment::Index(at)), new[j].clone()));
+            (at, j) = (at + 1, j + 1);
+        }
+
+        k = run_end;
+    }
+}
+
+fn diff_at(path: &Path, old: &Value, new: &Value, parts: &mut Vec<Mutation>) {
+    match (old, new) {
+        _ if old == new => {},
+        (Value::Map(old_members), Value::Map(new_members)) => {
+            let mut keys: Vec<&String> = old_members.keys().chain(new_members.keys()).collect();
+            keys.sort();
+            keys.dedup();
+
+            for key in keys {
+                let member_path = path.clone().child(Segment::Key(key.clone()));
+
+                match (old_members.get(key), new_members.get(key)) {
+                    (Some(old_member), Some(new_member)) => diff_at(&member_path, old_member, new_member, parts),
+                    (Some(_), None) => parts.push(Mutation::Unset(member_path)),
+                    (None, Some(new_member)) => parts.push(Mutation::Set(member_path, new_member.clone())),
+                    (None, None) => {}
+                }
+            }
+        },
+        (Value::List(old_elements), Value::List(new_elements)) => diff_lists(path, old_elements, new_elements, parts),
+        _ => parts.push(Mutation::Set(path.clone(), new.clone()))
+    }
+}
+
+impl Value {
+    // The mutation that turns this value into another when executed on it; empty if
+    // they're equal.
+    pub fn diff(&self, other: &Value) -> Mutation {
+        let mut parts = Vec::new();
+        diff_at(&Path::root(), self, other, &mut parts);
+
+        Mutation::Bulk(parts)
+    }
+}
+
+// The path a condition requires to exist, if that's all it does.
+fn existence_of(condition: &Condition) -> Option<&Path> {
+    match condition {
+        Condition::Comparison(Comparator::Exists, ValueReference::Reference(path), _) => Some(path),
+        _ => None
+    }
+}
+
+impl Mutation {
+    // The mutation as a JSON Patch (see parse_json_patch), for those that have one: Set is
+    // an add unless it's of a list element, requirements that a value equals another or
+    // is null are tests, and a requirement that a value exists is only written as part of
+    // a remove or replace of it that follows, as parse_json_patch reads them.
+    pub fn to_json_patch(&self) -> Result<Value, SchemaError> {
+        let mut operations = Vec::new();
+        self.json_patch_into(&mut operations)?;
+
+        Ok(Value::List(operations))
+    }
+
+    fn json_patch_into(&self, operations: &mut Vec<Value>) -> Result<(), SchemaError> {
+        let operation = |op: &str, path: &Path, rest: Option<(&str, Value)>| -> Result<Value, SchemaError> {
+            let mut members = Vec::from([
+                ("op".to_owned(), Value::str_from(op)), ("path".to_owned(), Value::Str(path.to_pointer()?))
+            ]);
+            members.extend(rest.map(|(key, value)| (key.to_owned(), value)));
+
+            Ok(Value::Map(members.into_iter().collect()))
+        };
+        let pointer = |path: &Path| -> Result<Value, SchemaError> { Ok(Value::Str(path.to_pointer()?)) };
+
+        operations.push(match self {
+            Self::Set(path, value) => match path.segments().last() {
+                Some(Segment::Index(_)) => operation("replace", path, Some(("value", value.clone())))?,
+                _ => operation("add", path, Some(("value", value.clone())))?
+            },
+            Self::Unset(path) => operation("remove", path, None)?,
+            Self::Insert(path, value) => operation("add", path, Some(("value", value.clone())))?,
+            Self::Copy(from, to) => operation("copy", to, Some(("from", pointer(from)?)))?,
+            Self::Move(from, to) => operation("move", to, Some(("from", pointer(from)?)))?,
+            Self::Require(Condition::Comparison(Comparator::Eq, ValueReference::Reference(path), ValueReference::Value(value))) => {
+                operation("test", path, Some(("value", value.clone())))?
+            },
+            Self::Require(Condition::Conjunctive(Conjunctive::And, conditions)) => match conditions.as_slice() {
+                [exists, Condition::Comparison(Comparator::IsNull, ValueReference::Reference(path), _)]
+                    if existence_of(exists) == Some(path) => operation("test", path, Some(("value", Value::Null)))?,
+                _ => return Err(SchemaError::NotImplemented(format!("{:?} has no JSON Patch equivalent", self)))
+            },
+            Self::Bulk(parts) => {
+                let mut rest = parts.as_slice();
+                while let Some((part, after)) = rest.split_first() {
+                    rest = after;
+
+                    let required = match part {
+                        Self::Require(condition) => existence_of(condition),
+                        _ => None
+                    };
+                    match (required, after.first()) {
+                        (Some(required), Some(Self::Unset(path))) if path == required => {
+                            operations.push(operation("remove", path, None)?);
+                            rest = &after[1..];
+                        },
+                        (Some(required), Some(Self::Set(path, value))) if path == required => {
+                            operations.push(operation("replace", path, Some(("value", value.clone())))?);
+                            rest = &after[1..];
+                        },
+                        _ => part.json_patch_into(operations)?
+                    }
+                }
+
+                return Ok(());
+            },
+            other => return Err(SchemaError::NotImplemented(format!("{:?} has no JSON Patch equivalent", other)))
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A small deterministic generator, so failures reproduce.
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, bound: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+
+            self.0 % bound
+        }
+    }
+
+    fn scalar(rng: &mut Rng) -> Value {
+        match rng.below(4) {
+            0 => Value::Null,
+            1 => Value::Bool(rng.below(2) == 0),
+            2 => Value::Uint32(rng.below(4) as u32),
+            _ => Value::Str(["a", "b", "c/d", "e~f"][rng.below(4) as usize].into())
+        }
+    }
+
+    fn value(rng: &mut Rng, depth: u32) -> Value {
+        match (depth, rng.below(3)) {
+            (0, _) | (_, 0) => scalar(rng),
+            (_, 1) => Value::List((0..rng.below(5)).map(|_| value(rng, depth - 1)).collect()),
+            _ => Value::Map((0..rng.below(4)).map(|i| (["a", "b", "1", "x.y"][i as usize].into(), value(rng, depth - 1))).collect())
+        }
+    }
+
+    // A value like another, with a few random edits.
+    fn perturb(rng: &mut Rng, original: &Value, depth: u32) -> Value {
+        match (original, rng.below(6)) {
+            (_, 0) => value(rng, depth),
+            (Value::List(elements), _) => {
+                let mut edited: Vec<Value> = elements.iter().map(|element| match rng.below(3) {
+                    0 => perturb(rng, element, depth.saturating_sub(1)),
+                    _ => element.clone()
+                }).collect();
+
+                for _ in 0..rng.below(3) {
+                    match (rng.below(2), edited.len()) {
+                        (0, len) if len > 0 => {
+                            edited.remove(rng.below(len as u64) as usize);
+                        },
+                        (_, len) => edited.insert(rng.below(len as u64 + 1) as usize, value(rng, depth.saturating_sub(1)))
+                    }
+                }
+
+                Value::List(edited)
+            },
+            (Value::Map(members), _) => {
+                let mut edited = members.clone();
+                for key in ["a", "b", "1", "x.y"] {
+                    match (rng.below(4), edited.get(key).cloned()) {
+                        (0, _) => {
+                            edited.remove(key);
+                        },
+                        (1, Some(member)) => {
+                            edited.insert(key.into(), perturb(rng, &member, depth.saturating_sub(1)));
+                        },
+                        (1, None) => {
+                            edited.insert(key.into(), value(rng, depth.saturating_sub(1)));
+                        },
+                        _ => {}
+                    }
+                }
+
+                Value::Map(edited)
+            },
+            _ => original.clone()
+        }
+    }
+
+    fn count(mutation: &Mutation) -> usize {
+        match mutation {
+            Mutation::Bulk(parts) => parts.iter().map(count).sum(),
+            _ => 1
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut rng = Rng(0x2545f4914f6cdd1d);
+
+        for _ in 0..500 {
+            let old = value(&mut rng, 3);
+            let new = perturb(&mut rng, &old, 3);
+            let diff = old.diff(&new);
+
+            assert_eq!(diff.execute(&old).unwrap(), new, "{:?} applied to {:?}", diff, old);
+
+            let patch = Mutation::parse_json_patch(diff.to_json_patch().unwrap()).unwrap();
+            assert_eq!(patch.execute(&old).unwrap(), new, "{:?} applied to {:?}", patch, old);
+
+            assert_eq!(count(&new.diff(&new)), 0);
+        }
+    }
+
+    #[test]
+    fn minimal() {
+        let n = |numbers: &[u32]| Value::List(numbers.iter().map(|number| Value::Uint32(*number)).collect());
+        let doc = |tags: Value, name: &str| Value::map_from([
+            ("tags".into(), tags), ("name".into(), Value::str_from(name))
+        ]);
+
+        let inserted = doc(n(&[1, 2, 3, 4]), "a").diff(&doc(n(&[1, 2, 9, 3, 4]), "a"));
+        assert_eq!(inserted, Mutation::Bulk(Vec::from([
+            Mutation::Insert(Path::parse("tags[2]").unwrap(), Value::Uint32(9))
+        ])));
+
+        let changed = doc(n(&[1, 2, 3]), "a").diff(&doc(n(&[1, 5, 3]), "b"));
+        assert_eq!(changed, Mutation::Bulk(Vec::from([
+            Mutation::Set(Path::parse("name").unwrap(), Value::str_from("b")),
+            Mutation::Set(Path::parse("tags[1]").unwrap(), Value::Uint32(5))
+        ])));
+        assert_eq!(changed.to_json_patch().unwrap(), Value::List(Vec::from([
+            Value::map_from([
+                ("op".into(), Value::str_from("add")), ("path".into(), Value::str_from("/name")), ("value".into(), Value::str_from("b"))
+            ]),
+            Value::map_from([
+                ("op".into(), Value::str_from("replace")), ("path".into(), Value::str_from("/tags/1")), ("value".into(), Value::Uint32(5))
+            ])
+        ])));
+
+        assert_eq!(count(&doc(n(&[1, 2, 3]), "a").diff(&doc(n(&[3]), "a"))), 2);
+        assert!(Mutation::Increment(Path::root(), Value::Uint32(1)).to_json_patch().is_err());
+    }
+
+    #[test]
+    fn json_patch_round_trip() {
+        let op = |op: &str, path: &str, rest: Option<(&str, Value)>| Value::Map([
+            ("op".into(), Value::str_from(op)), ("path".into(), Value::str_from(path))
+        ].into_iter().chain(rest.map(|(key, value)| (key.to_owned(), value))).collect());
+
+        let patch = Value::List(Vec::from([
+            op("add", "/a/b/1", Some(("value", Value::Uint32(9)))),
+            op("add", "", Some(("value", Value::map_from([])))),
+            op("remove", "/a/b/0", None),
+            op("replace", "/c~1d", Some(("value", Value::str_from("y")))),
+            op("copy", "/f", Some(("from", Value::str_from("/c~1d")))),
+            op("move", "/g", Some(("from", Value::str_from("/a/b/0")))),
+            op("test", "/e", Some(("value", Value::Null))),
+            op("test", "/a", Some(("value", Value::Uint32(1))))
+        ]));
+
+        assert_eq!(Mutation::parse_json_patch(patch.clone()).unwrap().to_json_patch().unwrap(), patch);
+
+        let exists = Condition::Comparison(Comparator::Exists, ValueReference::Reference(Path::root()), Value::Null.into());
+        assert!(Mutation::Require(exists).to_json_patch().is_err());
+    }
+
+    #[test]
+    fn long_lists() {
+        let old = Value::List((0..100_000).map(Value::Uint32).collect());
+        let mut elements: Vec<Value> = (0..100_000).map(|i| Value::Uint32(i * 2)).collect();
+        elements.insert(0, Value::Null);
+        let new = Value::List(elements);
+
+        assert_eq!(old.diff(&new), Mutation::Bulk(Vec::from([Mutation::Set(Path::root(), new)])));
+
+        // Only the elements between the common start and end count towards the table.
+        let mut elements: Vec<Value> = (0..100_000).map(Value::Uint32).collect();
+        elements[50_000] = Value::Null;
+        assert_eq!(count(&old.diff(&Value::List(elements))), 1);
+    }
+}
@@ -3,17 +3,38 @@ use std::fmt::{Display, Formatter};
 
 use super::primitives::Type;
 use super::expr::Comparator;
+use super::logic::Operator;
+use super::constraint::Constraint;
+use super::validation::ValidationReport;
 
 // TODO: Clean this up.
 #[derive(Debug, Clone)]
 pub enum SchemaError {
     UnknownableType,
-    InvalidComparison(Comparator, Type, Type),
+    InvalidComparison(Comparator, Box<Type>, Box<Type>),
+    InvalidOperation(Operator, Box<Type>, Box<Type>),
+    UnknownFunction(String),
+    InvalidArguments(Vec<Type>),
+    // An error within a called function.
+    Call(String, Box<SchemaError>),
     InvalidLookup(Option<Type>, String),
     InvalidIndex(Option<Type>, Option<usize>),
+    InvalidPath(String),
+    InvalidMutation(String),
+    // A message and the (byte) position in the filter text it concerns.
+    InvalidFilter(String, usize),
     InvalidCast(Type),
+    // A number that doesn't fit where it's going, e.g. an overflowing sum or cast.
+    OutOfRange(String),
     MissingKey(String),
-    InvalidType(Type, Type),
+    UnexpectedKey(String),
+    InvalidDefinition(String),
+    UnresolvedType(String),
+    InvalidType(Box<Type>, Box<Type>),
+    UnknownVariant(String),
+    // Every violation found while validating a value.
+    Invalid(ValidationReport),
+    ConstraintViolated(Constraint),
     NotImplemented(String)
 }
 
@@ -1,21 +1,71 @@
 // TODO: Rename and repackage.
 // Indirect Conditions applied to indirectly represented types or data.
-use std::cmp::{Eq, PartialOrd};
-use std::collections::HashMap;
+use std::cmp::Ordering;
 
 use super::errors::SchemaError;
 use super::primitives::{Type, Value};
-
-// TODO: Should be bitwise, Copy, and have more members.
-#[derive(Debug, Clone)]
+use super::path::Path;
+use super::constraint::Pattern;
+use super::scalars::encode_base64;
+
+// Comparison semantics, shared by every store driver:
+//  - Null is an unknown rather than a value, as in SQL. Comparisons involving null are
+//    neither true nor false (None from evaluate), except is_null and exists, which are
+//    always known. Conditions combine unknowns with three-valued logic and a condition
+//    that is unknown overall doesn't hold. in and contains are unknown rather than false
+//    when no element matches but one is null.
+//  - Numbers compare by magnitude across variants; see Value::compare_numeric. NaN is
+//    unequal to, and unordered with, everything.
+//  - Strings (and enum members) and timestamps are ordered. Bools, bytes, uuids, lists
+//    and maps only compare for (in)equality, and lists and maps compare structurally.
+//  - Anything else, including comparing values of different kinds, is a SchemaError.
+// TODO: Should be bitwise and Copy.
+#[derive(Debug, Clone, PartialEq)]
 pub enum Comparator {
     Eq,
     Neq,
     Lt,
-    Gt
+    Gt,
+    Gte,
+    Lte,
+    // Membership of the left side in a list on the right.
+    In,
+    NotIn,
+    // Membership of the right side in a list on the left, or a substring.
+    Contains,
+    StartsWith,
+    EndsWith,
+    EqIgnoreCase,
+    // SQL-style patterns on the right, where % matches any run of characters and _ any
+    // one character (\ escapes either).
+    Like,
+    Regex,
+    // Tests of the left side alone; a null member exists, an absent one doesn't.
+    IsNull,
+    Exists
+}
+
+// SQL LIKE patterns as (anchored) regular expressions.
+fn like_pattern(like: &str) -> Result<Pattern, SchemaError> {
+    let mut source = String::from("^(?s)");
+    let mut chars = like.chars();
+
+    while let Some(token) = chars.next() {
+        match token {
+            '%' => source.push_str(".*"),
+            '_' => source.push('.'),
+            '\\' => {
+                let escaped = chars.next().unwrap_or('\\');
+                source.push_str(&regex::escape(&escaped.to_string()));
+            },
+            other => source.push_str(&regex::escape(&other.to_string()))
+        }
+    }
+    source.push('$');
+
+    Pattern::new(&source)
 }
 
-// TODO: Currently evaluates as false when comparisons are actually impossible...
 impl Comparator {
     pub fn parse_from_value(value: Value) -> Result<Self, SchemaError> {
         let which: String = value.try_into()?;
@@ -25,71 +75,200 @@ impl Comparator {
             "neq" => Ok(Self::Neq),
             "lt" => Ok(Self::Lt),
             "gt" => Ok(Self::Gt),
+            "gte" => Ok(Self::Gte),
+            "lte" => Ok(Self::Lte),
+            "in" => Ok(Self::In),
+            "not_in" => Ok(Self::NotIn),
+            "contains" => Ok(Self::Contains),
+            "starts_with" => Ok(Self::StartsWith),
+            "ends_with" => Ok(Self::EndsWith),
+            "eq_ignore_case" => Ok(Self::EqIgnoreCase),
+            "like" => Ok(Self::Like),
+            "regex" => Ok(Self::Regex),
+            "is_null" => Ok(Self::IsNull),
+            "exists" => Ok(Self::Exists),
             _ => Err(SchemaError::NotImplemented("invalid comparator".into()))
         }
     }
 
-    fn compare_eq<T>(&self, a: T, b: T) -> bool
-    where
-        T: Eq
-    {
+    // The comparator that holds exactly when this one doesn't (and is unknown when it is),
+    // if there is one. Orderings have none since NaN is unordered.
+    pub fn negated(&self) -> Option<Self> {
         match self {
-            Self::Eq => a == b,
-            Self::Neq => a != b,
-            _ => false // ...here
+            Self::Eq => Some(Self::Neq),
+            Self::Neq => Some(Self::Eq),
+            Self::In => Some(Self::NotIn),
+            Self::NotIn => Some(Self::In),
+            _ => None
         }
     }
 
-    fn compare_ord<T>(&self, a: T, b: T) -> bool
-    where
-        T: PartialOrd
-    {
+    pub fn name(&self) -> &'static str {
         match self {
-            Self::Lt => a < b,
-            Self::Gt => a > b,
-            _ => false // ...here
+            Self::Eq => "eq",
+            Self::Neq => "neq",
+            Self::Lt => "lt",
+            Self::Gt => "gt",
+            Self::Gte => "gte",
+            Self::Lte => "lte",
+            Self::In => "in",
+            Self::NotIn => "not_in",
+            Self::Contains => "contains",
+            Self::StartsWith => "starts_with",
+            Self::EndsWith => "ends_with",
+            Self::EqIgnoreCase => "eq_ignore_case",
+            Self::Like => "like",
+            Self::Regex => "regex",
+            Self::IsNull => "is_null",
+            Self::Exists => "exists"
         }
     }
 
-    fn compare_full<T>(&self, a: T, b: T) -> bool
-    where
-        T: Eq + PartialOrd
-    {
-        match self {
-            Self::Eq => a == b,
-            Self::Neq => a != b,
-            Self::Lt => a < b,
-            Self::Gt => a > b
-        }
+    pub fn to_value(&self) -> Value {
+        Value::str_from(self.name())
+    }
+
+    // Whether the comparator ignores its right side.
+    pub fn is_unary(&self) -> bool {
+        matches!(self, Self::IsNull | Self::Exists)
+    }
+
+    fn is_ordering(&self) -> bool {
+        matches!(self, Self::Lt | Self::Gt | Self::Lte | Self::Gte)
+    }
+
+    fn mismatch(&self, left: &Value, right: &Value) -> SchemaError {
+        let sample = |value: &Value| Box::new(Type::try_from(value).unwrap_or(Type::Any));
+
+        SchemaError::InvalidComparison(self.clone(), sample(left), sample(right))
     }
 
     pub fn validate(&self, typ: &Type, left: &ValueReference, right: &ValueReference) -> Result<(), SchemaError> {
         let a = left.lookup_type(typ)?;
-        let b = right.lookup_type(typ)?;
 
-        if !a.primitive_eq(&b) {
-            Err(SchemaError::InvalidComparison(self.clone(), a.into(), b.into()))
+        if self.is_unary() {
+            return Ok(());
         }
-        else {
-            Ok(())
+
+        let b = match right {
+            // The element type of an empty list is unknowable, but it can't matter.
+            ValueReference::Value(Value::List(elements)) if elements.is_empty() => Type::List(Box::new(Type::Any)),
+            _ => right.lookup_type(typ)?
+        };
+
+        let is_text = |typ: &Type| matches!(typ.base(), Type::String | Type::Enum(_) | Type::Any);
+
+        let valid = match self {
+            Self::In | Self::NotIn => match b.base() {
+                Type::List(inner) => a.comparable_with(inner),
+                other => *other == Type::Any
+            },
+            Self::Contains => match a.base() {
+                Type::List(inner) => inner.comparable_with(&b),
+                Type::Any => true,
+                _ => is_text(&a) && is_text(&b)
+            },
+            Self::StartsWith | Self::EndsWith | Self::EqIgnoreCase | Self::Like | Self::Regex => {
+                is_text(&a) && is_text(&b)
+            },
+            _ if self.is_ordering() => {
+                a.comparable_with(&b) && (a.is_numeric() || matches!(
+                    a.base(), Type::String | Type::Enum(_) | Type::Timestamp | Type::Any
+                ))
+            },
+            _ => a.comparable_with(&b)
+        };
+
+        if !valid {
+            return Err(SchemaError::InvalidComparison(self.clone(), a.into(), b.into()));
+        }
+
+        // Literal patterns are checked up front.
+        match (self, right) {
+            (Self::Like, ValueReference::Value(Value::Str(like))) => like_pattern(like).map(|_| ()),
+            (Self::Regex, ValueReference::Value(Value::Str(source))) => Pattern::new(source).map(|_| ()),
+            _ => Ok(())
         }
     }
 
-    // TODO: Branch casing here is brutal, macroize.
-    pub fn evaluate(&self, left: &Value, right: &Value) -> bool {
-        match (left, right) {
-            (Value::Null, Value::Null) => true,
-            (Value::Bool(a), Value::Bool(b)) => self.compare_eq(a, b),
-            (Value::Float64(a), Value::Float64(b)) => self.compare_ord(a, b),
-            (Value::Str(a), Value::Str(b)) => self.compare_full(a, b),
-            (Value::Int32(a), Value::Int32(b)) => self.compare_full(a, b),
-            (Value::Uint32(a), Value::Uint32(b)) => self.compare_full(a, b),
-            _ => false
+    // Whether an element of a list equals a value, if that's known.
+    fn any_equal(needle: &Value, elements: &[Value]) -> Result<Option<bool>, SchemaError> {
+        let mut unknown = false;
+
+        for element in elements {
+            match Self::Eq.evaluate(needle, element)? {
+                Some(true) => return Ok(Some(true)),
+                Some(false) => {},
+                None => unknown = true
+            }
         }
+
+        Ok(match unknown {
+            true => None,
+            false => Some(false)
+        })
+    }
+
+    // Whether the comparison holds, or None if that's unknown because of a null.
+    pub fn evaluate(&self, left: &Value, right: &Value) -> Result<Option<bool>, SchemaError> {
+        Ok(match (self, left, right) {
+            (Self::IsNull, _, _) => Some(*left == Value::Null),
+            // Absent members look up as null; see Condition::evaluate.
+            (Self::Exists, _, _) => Some(*left != Value::Null),
+            (Self::In, _, Value::List(elements)) => Self::any_equal(left, elements)?,
+            (Self::NotIn, _, Value::List(elements)) => Self::any_equal(left, elements)?.map(|found| !found),
+            (Self::Contains, Value::List(elements), _) => Self::any_equal(right, elements)?,
+            (_, Value::Null, _) | (_, _, Value::Null) => None,
+            (Self::Contains, Value::Str(a), Value::Str(b)) => Some(a.contains(b.as_str())),
+            (Self::StartsWith, Value::Str(a), Value::Str(b)) => Some(a.starts_with(b.as_str())),
+            (Self::EndsWith, Value::Str(a), Value::Str(b)) => Some(a.ends_with(b.as_str())),
+            (Self::EqIgnoreCase, Value::Str(a), Value::Str(b)) => Some(a.to_lowercase() == b.to_lowercase()),
+            (Self::Like, Value::Str(a), Value::Str(b)) => Some(like_pattern(b)?.is_match(a)),
+            (Self::Regex, Value::Str(a), Value::Str(b)) => Some(Pattern::new(b)?.is_match(a)),
+            (Self::Eq | Self::Neq, _, _) => Some(self.compare_scalar(left, right)?),
+            _ if self.is_ordering() => Some(self.compare_scalar(left, right)?),
+            _ => return Err(self.mismatch(left, right))
+        })
+    }
+
+    // As evaluate, for a like or regex whose pattern is already compiled.
+    fn evaluate_pattern(&self, left: &Value, pattern: &Pattern) -> Result<Option<bool>, SchemaError> {
+        match left {
+            Value::Null => Ok(None),
+            Value::Str(string) => Ok(Some(pattern.is_match(string))),
+            _ => Err(self.mismatch(left, &Value::str_from(pattern.source())))
+        }
+    }
+
+    fn compare_scalar(&self, left: &Value, right: &Value) -> Result<bool, SchemaError> {
+        let ordering = match (left, right) {
+            (Value::Str(a), Value::Str(b)) => Some(a.cmp(b)),
+            (Value::Timestamp(a), Value::Timestamp(b)) => Some(a.cmp(b)),
+            (a, b) if a.as_f64().is_some() && b.as_f64().is_some() => a.compare_numeric(b),
+            (Value::Bool(_), Value::Bool(_)) | (Value::Bytes(_), Value::Bytes(_)) | (Value::Uuid(_), Value::Uuid(_))
+            | (Value::List(_), Value::List(_)) | (Value::Map(_), Value::Map(_)) => {
+                return match self {
+                    Self::Eq => Ok(left == right),
+                    Self::Neq => Ok(left != right),
+                    _ => Err(self.mismatch(left, right))
+                };
+            },
+            _ => return Err(self.mismatch(left, right))
+        };
+
+        Ok(match self {
+            Self::Eq => ordering == Some(Ordering::Equal),
+            Self::Neq => ordering != Some(Ordering::Equal),
+            Self::Lt => ordering == Some(Ordering::Less),
+            Self::Gt => ordering == Some(Ordering::Greater),
+            Self::Lte => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            Self::Gte => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+            _ => false
+        })
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Conjunctive {
     And,
     Or
@@ -105,24 +284,63 @@ impl Conjunctive {
             _ => Err(SchemaError::NotImplemented("invalid conjunctive".into()))
         }
     }
+
+    pub fn to_value(&self) -> Value {
+        Value::str_from(match self {
+            Self::And => "and",
+            Self::Or => "or"
+        })
+    }
 }
 
 // An evaluatable indirect condition.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Condition {
     Comparison(Comparator, ValueReference, ValueReference),
-    Conjunctive(Conjunctive, Vec<Condition>)
+    // An empty and always holds, an empty or never does.
+    Conjunctive(Conjunctive, Vec<Condition>),
+    Not(Box<Condition>),
+    True,
+    False
 }
 
 impl Condition {
+    // A comparison, with a literal like or regex pattern compiled once here rather than
+    // on every evaluation.
+    pub fn comparison(comparator: Comparator, left: ValueReference, right: ValueReference) -> Result<Self, SchemaError> {
+        let right = match (&comparator, right) {
+            (Comparator::Like, ValueReference::Value(Value::Str(like))) => {
+                let pattern = like_pattern(&like)?;
+                ValueReference::Pattern(like, pattern)
+            },
+            (Comparator::Regex, ValueReference::Value(Value::Str(source))) => {
+                let pattern = Pattern::new(&source)?;
+                ValueReference::Pattern(source, pattern)
+            },
+            (_, right) => right
+        };
+
+        Ok(Self::Comparison(comparator, left, right))
+    }
+
     // TODO: Bad schema.
     pub fn parse_from_value(value: Value) -> Result<Self, SchemaError> {
-        if let Ok(cmp_value) = value.lookup("compare") {
-            Ok(Self::Comparison(
-                Comparator::parse_from_value(cmp_value.index(0)?)?,
-                ValueReference::parse_from_value(cmp_value.index(1)?)?,
-                ValueReference::parse_from_value(cmp_value.index(2)?)?
-            ))
+        if let Value::Bool(constant) = value {
+            Ok(Self::constant(constant))
+        }
+        else if let Ok(inner) = value.lookup("not") {
+            Ok(Self::Not(Box::new(Condition::parse_from_value(inner)?)))
+        }
+        else if let Ok(cmp_value) = value.lookup("compare") {
+            let comparator = Comparator::parse_from_value(cmp_value.index(0)?)?;
+            let left = ValueReference::parse_from_value(cmp_value.index(1)?)?;
+            // Unary comparisons may omit their right side.
+            let right = match (comparator.is_unary(), cmp_value.index(2)) {
+                (true, Err(_)) => ValueReference::Value(Value::Null),
+                (_, right) => ValueReference::parse_from_value(right?)?
+            };
+
+            Self::comparison(comparator, left, right)
         }
         else if let Ok(conj_value) = value.lookup("many") {
             let elements_value = conj_value.index(1)?;
@@ -143,81 +361,215 @@ impl Condition {
         }
     }
 
+    // The inverse of parse_from_value.
+    pub fn to_value(&self) -> Value {
+        match self {
+            Self::Comparison(op, a, b) => {
+                let mut operands = Vec::from([op.to_value(), a.to_value()]);
+                if !op.is_unary() || *b != ValueReference::Value(Value::Null) {
+                    operands.push(b.to_value());
+                }
+
+                Value::map_from([("compare".into(), Value::List(operands))])
+            },
+            Self::Conjunctive(op, parts) => Value::map_from([
+                ("many".into(), Value::List(Vec::from([
+                    op.to_value(),
+                    Value::List(parts.iter().map(Self::to_value).collect())
+                ])))
+            ]),
+            Self::Not(inner) => Value::map_from([("not".into(), inner.to_value())]),
+            Self::True => Value::Bool(true),
+            Self::False => Value::Bool(false)
+        }
+    }
+
     pub fn validate(&self, typ: &Type) -> Result<(), SchemaError> {
         match self {
             Self::Comparison(op, a, b) => {
                 op.validate(typ, a, b)
             },
             Self::Conjunctive(_, parts) => {
-                if parts.len() == 0 {
-                    return Err(SchemaError::NotImplemented("Empty conjunctive".into()));
-                }
-
                 for expr in parts.as_slice() {
                     expr.validate(typ)?;
                 }
 
                 Ok(())
-            }
+            },
+            Self::Not(inner) => inner.validate(typ),
+            Self::True | Self::False => Ok(())
         }
     }
 
+    // Whether the condition holds for a value; unknowns (see Comparator) don't.
     pub fn evaluate(&self, value: &Value) -> Result<bool, SchemaError> {
+        Ok(self.truth(value)? == Some(true))
+    }
+
+    // The three-valued truth of the condition for a value.
+    pub fn truth(&self, value: &Value) -> Result<Option<bool>, SchemaError> {
         match self {
+            // Existence is the one thing an absent member and a null member differ on.
+            Self::Comparison(Comparator::Exists, ValueReference::Reference(path), _) => {
+                Ok(Some(path.select(value, false).is_ok()))
+            },
+            // References with wildcards select many values; the comparison holds if it
+            // does for any pair.
             Self::Comparison(op, a, b) => {
-                Ok(op.evaluate(&a.lookup_value(value)?, &b.lookup_value(value)?))
+                let lefts = a.lookup_values(value)?;
+                let rights = b.lookup_values(value)?;
+
+                let mut truth = Some(false);
+                for left in lefts.iter() {
+                    for right in rights.iter() {
+                        let holds = match (op, b) {
+                            (Comparator::Like | Comparator::Regex, ValueReference::Pattern(_, pattern)) => {
+                                op.evaluate_pattern(left, pattern)?
+                            },
+                            _ => op.evaluate(left, right)?
+                        };
+
+                        match holds {
+                            Some(true) => return Ok(Some(true)),
+                            Some(false) => {},
+                            None => truth = None
+                        }
+                    }
+                }
+
+                Ok(truth)
             },
+            // And is false if any part is, otherwise unknown if any part is; or is true if
+            // any part is, otherwise unknown if any part is.
             Self::Conjunctive(op, parts) => {
-                // TODO: PartialEq?
-                let is_and = match op {
-                    Conjunctive::Or => false,
-                    Conjunctive::And => true
-                };
+                let decisive = matches!(op, Conjunctive::Or);
 
-                let mut any = false;
+                let mut truth = Some(!decisive);
                 for expr in parts.as_slice() {
-                    match expr.evaluate(value) {
-                        Err(err) => return Err(err),
-                        Ok(subresult) => {
-                            if !subresult && is_and {
-                                return Ok(false);
-                            }
-
-                            any = any || subresult
-                        }
+                    match expr.truth(value)? {
+                        Some(part) if part == decisive => return Ok(Some(decisive)),
+                        Some(_) => {},
+                        None => truth = None
+                    }
+                }
+
+                Ok(truth)
+            },
+            Self::Not(inner) => Ok(inner.truth(value)?.map(|truth| !truth)),
+            Self::True => Ok(Some(true)),
+            Self::False => Ok(Some(false))
+        }
+    }
+
+    pub fn constant(holds: bool) -> Self {
+        match holds {
+            true => Self::True,
+            false => Self::False
+        }
+    }
+
+    // An equivalent (under three-valued logic) but simpler condition: negation is pushed
+    // inward as far as it can go, nested conjunctives of the same kind are flattened, and
+    // constants are folded away.
+    pub fn normalize(self) -> Self {
+        match self {
+            Self::Not(inner) => match *inner {
+                Self::Not(inner) => inner.normalize(),
+                Self::True => Self::False,
+                Self::False => Self::True,
+                Self::Conjunctive(op, parts) => {
+                    let flipped = match op {
+                        Conjunctive::And => Conjunctive::Or,
+                        Conjunctive::Or => Conjunctive::And
                     };
+
+                    Self::Conjunctive(flipped, parts.into_iter().map(|part| Self::Not(Box::new(part))).collect())
+                        .normalize()
+                },
+                Self::Comparison(op, a, b) => match (op.negated(), a.is_many() || b.is_many()) {
+                    (Some(negated), false) => Self::Comparison(negated, a, b),
+                    (_, _) => Self::Not(Box::new(Self::Comparison(op, a, b)))
+                }
+            },
+            Self::Conjunctive(op, parts) => {
+                // The constant that decides the conjunctive when a part is it.
+                let decisive = matches!(op, Conjunctive::Or);
+
+                let mut flattened = Vec::with_capacity(parts.len());
+                for part in parts.into_iter().map(Self::normalize) {
+                    match part {
+                        Self::True | Self::False if part == Self::constant(decisive) => return part,
+                        Self::True | Self::False => {},
+                        Self::Conjunctive(inner_op, inner_parts) if inner_op == op => flattened.extend(inner_parts),
+                        other => flattened.push(other)
+                    }
                 }
 
-                Ok(any)
-            }
+                match flattened.len() {
+                    0 => Self::constant(!decisive),
+                    1 => flattened.remove(0),
+                    _ => Self::Conjunctive(op, flattened)
+                }
+            },
+            other => other
         }
     }
 }
 
-pub struct LogicScope {
-    values: HashMap<String, Value>
+// The types of literals that have no JSON form. They're written as their string encoding
+// (see Type::decode_str) and tagged with the type's name, in filters and in the value
+// notation of conditions and mutations.
+pub(super) const TYPED_LITERALS: [(&str, Type); 4] = [
+    ("decimal", Type::Decimal), ("timestamp", Type::Timestamp), ("uuid", Type::Uuid), ("bytes", Type::Bytes)
+];
+
+// A literal as its string encoding and type name, if it's of one of those types or a list
+// of elements of one of them; anything else is returned as it is, without a name.
+pub(super) fn tag_literal(literal: &Value) -> (Value, Option<Value>) {
+    let encode = |value: &Value| -> Option<(&'static str, Value)> {
+        Some(match value {
+            Value::Decimal(decimal) => ("decimal", Value::Str(decimal.to_string())),
+            Value::Timestamp(timestamp) => ("timestamp", Value::Str(timestamp.to_string())),
+            Value::Uuid(uuid) => ("uuid", Value::Str(uuid.to_string())),
+            Value::Bytes(bytes) => ("bytes", Value::Str(encode_base64(bytes))),
+            _ => return None
+        })
+    };
+
+    let encoded = match literal {
+        Value::List(elements) if !elements.is_empty() => elements.iter().map(encode).collect::<Option<Vec<_>>>()
+            .filter(|encoded| encoded.iter().all(|(name, _)| *name == encoded[0].0))
+            .map(|encoded| (encoded[0].0, Value::List(encoded.into_iter().map(|(_, element)| element).collect()))),
+        _ => encode(literal)
+    };
+
+    match encoded {
+        Some((name, encoded)) => (encoded, Some(Value::str_from(name))),
+        None => (literal.clone(), None)
+    }
 }
 
-impl LogicScope {
-    pub fn evaluate(&self, reference: &ValueReference) -> Result<Value, SchemaError> {
-        match reference {
-            ValueReference::Value(val) => Ok(val.clone()),
-            ValueReference::Reference(name) => {
-                match self.values.get(name) {
-                    Some(value) => Ok(value.clone()),
-                    None => Err(SchemaError::NotImplemented("no such variable in scope".into()))
-                }
-            }
-        }
+// The inverse of tag_literal.
+pub(super) fn untag_literal(encoded: Value, name: Value) -> Result<Value, SchemaError> {
+    let name: String = name.try_into()?;
+    let typ = TYPED_LITERALS.iter().find(|(literal_name, _)| *literal_name == name).map(|(_, typ)| typ)
+        .ok_or_else(|| SchemaError::NotImplemented(format!("invalid literal type {}", name)))?;
+
+    let decode = |element: Value| typ.decode_str(&String::try_from(element)?);
+    match encoded {
+        Value::List(elements) => Ok(Value::List(elements.into_iter().map(decode).collect::<Result<_, _>>()?)),
+        other => decode(other)
     }
 }
 
 // An indirect reference to a value or a value.
 // TODO: Better reference encapsulation.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ValueReference {
     Value(Value),
-    Reference(String)
+    Reference(Path),
+    // A literal like or regex pattern and its compilation; see Condition::comparison.
+    Pattern(String, Pattern)
 }
 
 impl From<Value> for ValueReference {
@@ -229,104 +581,231 @@ impl From<Value> for ValueReference {
 impl ValueReference {
     pub fn parse_from_value(value: Value) -> Result<Self, SchemaError> {
         if let Ok(literal) = value.lookup("value") {
-            Ok(Self::Value(literal))
+            match value.lookup("type") {
+                Ok(name) => Ok(Self::Value(untag_literal(literal, name)?)),
+                Err(_) => Ok(Self::Value(literal))
+            }
         }
         else if let Ok(ref_str) = value.lookup("ref") {
-            Ok(Self::Reference(ref_str.try_into()?))
+            Ok(Self::Reference(Path::parse(&String::try_from(ref_str)?)?))
         }
         else {
             Err(SchemaError::NotImplemented("invalid value reference".into()))
         }
     }
 
+    // The inverse of parse_from_value.
+    pub fn to_value(&self) -> Value {
+        match self {
+            Self::Value(literal) => match tag_literal(literal) {
+                (encoded, Some(name)) => Value::map_from([("value".into(), encoded), ("type".into(), name)]),
+                (literal, None) => Value::map_from([("value".into(), literal)])
+            },
+            Self::Reference(path) => Value::map_from([("ref".into(), Value::Str(path.to_string()))]),
+            Self::Pattern(literal, _) => Value::map_from([("value".into(), Value::str_from(literal.as_str()))])
+        }
+    }
+
     pub fn lookup_type(&self, typ: &Type) -> Result<Type, SchemaError> {
         match self {
             Self::Value(value) => value.try_into(),
-            Self::Reference(lookup) => typ.lookup(lookup)
+            Self::Reference(path) => typ.lookup(path),
+            Self::Pattern(_, _) => Ok(Type::String)
         }
     }
 
-    pub fn lookup_value(&self, value: &Value) -> Result<Value, SchemaError> {
+    // Whether the reference may select more than one value.
+    pub fn is_many(&self) -> bool {
         match self {
-            Self::Value(value) => Ok(value.clone()),
-            Self::Reference(lookup) => value.lookup(lookup)
+            Self::Value(_) | Self::Pattern(_, _) => false,
+            Self::Reference(path) => path.has_wildcard()
         }
     }
-}
 
-#[derive(Debug)]
-pub enum Operator {
-    Add,
-    Sub,
-    Mul,
-    Div,
-    And,
-    Or
+    // Every value referenced; more than one only for paths with wildcards. Absent
+    // (optional) members compare as null.
+    pub fn lookup_values(&self, value: &Value) -> Result<Vec<Value>, SchemaError> {
+        match self {
+            Self::Value(literal) => Ok(Vec::from([literal.clone()])),
+            Self::Reference(path) => path.select(value, true),
+            Self::Pattern(literal, _) => Ok(Vec::from([Value::str_from(literal.as_str())]))
+        }
+    }
 }
 
-impl Operator {
-    // TODO: Can simplify with std::ops?
-    pub fn evaluate(&self, left: &Value, right: &Value) -> Result<Value, SchemaError> {
-        macro_rules! primitive_cases {
-            ($o: tt) => {
-                match (left, right) {
-                    (Value::Int32(a), Value::Int32(b)) => Value::Int32(a $o b),
-                    (Value::Int32(a), Value::Uint32(b)) => {
-                        let b_signed = match i32::try_from(*b) {
-                            Err(_) => return Err(SchemaError::NotImplemented("can't cast unsigned right side to signed".into())),
-                            Ok(signed) => signed
-                        };
-                        Value::Int32(a + &b_signed)
-                    },
-                    (Value::Uint32(a), Value::Uint32(b)) => Value::Uint32(a $o b),
-                    (Value::Float64(a), Value::Float64(b)) => Value::Float64(a $o b),
-                    (Value::Float64(a), Value::Int32(b)) => Value::Float64(a $o f64::from(*b)),
-                    (Value::Float64(a), Value::Uint32(b)) => Value::Float64(a $o f64::from(*b)),
-                    (_, _) => return Err(SchemaError::NotImplemented("invalid operator use".into()))
-                }
-            };
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn compare(comparator: &str, path: &str, right: Value) -> Condition {
+        Condition::parse_from_value(Value::map_from([
+            ("compare".into(), Value::List(Vec::from([
+                Value::str_from(comparator),
+                Value::map_from([("ref".into(), Value::str_from(path))]),
+                Value::map_from([("value".into(), right)])
+            ])))
+        ])).unwrap()
+    }
+
+    #[test]
+    fn comparators() {
+        let typ = Type::Map(HashMap::from([
+            ("name".into(), Type::String),
+            ("age".into(), Type::Uint32),
+            ("tags".into(), Type::List(Box::new(Type::String))),
+            ("note".into(), Type::Optional(Box::new(Type::String)))
+        ]));
+        let item = Value::map_from([
+            ("name".into(), Value::str_from("Ada_Lovelace")),
+            ("age".into(), Value::Uint32(36)),
+            ("tags".into(), Value::List(Vec::from([Value::str_from("math")]))),
+            ("note".into(), Value::Null)
+        ]);
+        let strings = |values: &[&str]| Value::List(values.iter().map(|value| Value::str_from(*value)).collect());
+
+        let holds = [
+            compare("gte", "age", Value::Uint32(36)),
+            compare("lte", "age", Value::Uint32(40)),
+            compare("in", "name", strings(&["Ada_Lovelace", "Grace"])),
+            compare("not_in", "name", strings(&[])),
+            compare("contains", "tags", Value::str_from("math")),
+            compare("contains", "name", Value::str_from("Love")),
+            compare("starts_with", "name", Value::str_from("Ada")),
+            compare("ends_with", "name", Value::str_from("lace")),
+            compare("eq_ignore_case", "name", Value::str_from("ada_lovelace")),
+            compare("like", "name", Value::str_from("A%\\_L_velace")),
+            compare("regex", "name", Value::str_from("^[A-Z][a-z]+_")),
+            compare("is_null", "note", Value::Null),
+            compare("exists", "note", Value::Null)
+        ];
+        for condition in holds.iter() {
+            condition.validate(&typ).unwrap();
+            assert!(condition.evaluate(&item).unwrap(), "{:?} should hold", condition);
         }
 
-        Ok(match self {
-            Self::Add => {
-                match left {
-                    Value::List(list) => {
-                        let mut new_list = list.clone();
-
-                        new_list.push(right.clone());
-
-                        Value::List(new_list)
-                    },
-                    Value::Str(a) => {
-                        match right {
-                            Value::Str(b) => Value::Str(a.to_owned() + b),
-                            _ => return Err(SchemaError::NotImplemented("add non-string to string".into()))
-                        }
-                    },
-                    _ => primitive_cases!(+)
-                }
-            },
-            Self::Sub => primitive_cases!(-),
-            Self::Div => primitive_cases!(/),
-            Self::Mul => primitive_cases!(*),
-            _ => return Err(SchemaError::NotImplemented("operator not implemented".into()))
-        })
+        let fails = [
+            compare("gte", "age", Value::Uint32(37)),
+            compare("not_in", "name", strings(&["Ada_Lovelace"])),
+            compare("contains", "tags", Value::str_from("art")),
+            compare("like", "name", Value::str_from("Ada")),
+            compare("like", "name", Value::str_from("Ada%Lovelac_e"))
+        ];
+        for condition in fails.iter() {
+            assert!(!condition.evaluate(&item).unwrap(), "{:?} shouldn't hold", condition);
+        }
+
+        let absent = Value::map_from([("name".into(), Value::str_from("Grace"))]);
+        assert!(compare("is_null", "note", Value::Null).evaluate(&absent).unwrap());
+        assert!(!compare("exists", "note", Value::Null).evaluate(&absent).unwrap());
+
+        assert!(compare("in", "name", Value::List(Vec::from([Value::Uint32(1)]))).validate(&typ).is_err());
+        assert!(compare("starts_with", "age", Value::str_from("3")).validate(&typ).is_err());
+
+        // Literal patterns are compiled as they're parsed.
+        assert!(matches!(
+            compare("like", "name", Value::str_from("A%")),
+            Condition::Comparison(_, _, ValueReference::Pattern(_, _))
+        ));
+        assert!(Condition::comparison(
+            Comparator::Regex, ValueReference::Reference(Path::parse("name").unwrap()), Value::str_from("(").into()
+        ).is_err());
     }
-}
 
-#[derive(Debug)]
-pub enum Expression {
-    Op(Operator, Box<Expression>, Box<Expression>),
-    Ref(ValueReference)
-}
+    #[test]
+    fn comparison_semantics() {
+        assert_eq!(Comparator::Lt.evaluate(&Value::Int32(-1), &Value::Uint32(0)).unwrap(), Some(true));
+        assert_eq!(Comparator::Eq.evaluate(&Value::Uint64(2), &Value::Float64(2.0)).unwrap(), Some(true));
+        assert_eq!(Comparator::Eq.evaluate(&Value::Float64(f64::NAN), &Value::Float64(f64::NAN)).unwrap(), Some(false));
+
+        assert_eq!(Comparator::Eq.evaluate(&Value::Null, &Value::Null).unwrap(), None);
+        assert_eq!(Comparator::Neq.evaluate(&Value::Null, &Value::Uint32(1)).unwrap(), None);
+        assert_eq!(Comparator::IsNull.evaluate(&Value::Null, &Value::Null).unwrap(), Some(true));
+
+        let with_null = Value::List(Vec::from([Value::Uint32(1), Value::Null]));
+        assert_eq!(Comparator::In.evaluate(&Value::Uint32(1), &with_null).unwrap(), Some(true));
+        assert_eq!(Comparator::In.evaluate(&Value::Uint32(2), &with_null).unwrap(), None);
+        assert_eq!(Comparator::NotIn.evaluate(&Value::Uint32(2), &with_null).unwrap(), None);
+
+        assert!(Comparator::Lt.evaluate(&Value::Bool(false), &Value::Bool(true)).is_err());
+        assert!(Comparator::Eq.evaluate(&Value::str_from("1"), &Value::Uint32(1)).is_err());
+
+        let item = Value::map_from([("age".into(), Value::Null)]);
+        let unknown = compare("gt", "age", Value::Uint32(3));
+        assert_eq!(unknown.truth(&item).unwrap(), None);
+        assert!(!compare("lte", "age", Value::Uint32(3)).evaluate(&item).unwrap());
+
+        let many = |conjunctive: Conjunctive, parts: Vec<Condition>| Condition::Conjunctive(conjunctive, parts);
+        let holds = || compare("is_null", "age", Value::Null);
+        let fails = || compare("exists", "other", Value::Null);
+        let unknown = || compare("gt", "age", Value::Uint32(3));
+
+        assert_eq!(many(Conjunctive::And, Vec::from([holds(), unknown()])).truth(&item).unwrap(), None);
+        assert_eq!(many(Conjunctive::And, Vec::from([unknown(), fails()])).truth(&item).unwrap(), Some(false));
+        assert_eq!(many(Conjunctive::Or, Vec::from([unknown(), holds()])).truth(&item).unwrap(), Some(true));
+        assert_eq!(many(Conjunctive::Or, Vec::from([unknown(), fails()])).truth(&item).unwrap(), None);
+    }
 
-#[derive(Debug)]
-pub enum Statement {
-    Assignment()
-}
+    #[test]
+    fn negation_and_normalization() {
+        let item = Value::map_from([("deleted".into(), Value::Bool(false)), ("age".into(), Value::Null)]);
+        let deleted = || compare("eq", "deleted", Value::Bool(true));
+        let unknown = || compare("gt", "age", Value::Uint32(3));
+        let not = |inner: Condition| Condition::Not(Box::new(inner));
+
+        assert!(not(deleted()).evaluate(&item).unwrap());
+        assert_eq!(not(unknown()).truth(&item).unwrap(), None);
+        assert!(Condition::Conjunctive(Conjunctive::And, Vec::new()).evaluate(&item).unwrap());
+        assert!(!Condition::Conjunctive(Conjunctive::Or, Vec::new()).evaluate(&item).unwrap());
+        assert!(Condition::parse_from_value(Value::Bool(true)).unwrap().evaluate(&item).unwrap());
+
+        let nested = not(Condition::Conjunctive(Conjunctive::Or, Vec::from([
+            deleted(),
+            not(unknown()),
+            Condition::Conjunctive(Conjunctive::Or, Vec::from([Condition::False]))
+        ])));
+        assert_eq!(nested.clone().normalize(), Condition::Conjunctive(Conjunctive::And, Vec::from([
+            compare("neq", "deleted", Value::Bool(true)),
+            unknown()
+        ])));
+        assert_eq!(nested.truth(&item).unwrap(), nested.clone().normalize().truth(&item).unwrap());
+
+        let wildcard = compare("eq", "tags[*]", Value::str_from("x"));
+        assert_eq!(not(wildcard.clone()).normalize(), not(wildcard));
+        assert_eq!(
+            Condition::Conjunctive(Conjunctive::And, Vec::from([Condition::True, deleted(), Condition::False])).normalize(),
+            Condition::False
+        );
+    }
+
+    #[test]
+    fn value_round_trip() {
+        let conditions = [
+            Condition::parse_filter(
+                "not (a.b[0] == 1.5 or c\\.d in [\"x\", null]) and e is not null and f exists and true"
+            ).unwrap(),
+            Condition::Conjunctive(Conjunctive::Or, Vec::new()),
+            Condition::comparison(Comparator::Regex, ValueReference::Reference(Path::root()), Value::str_from("^a").into()).unwrap(),
+            Condition::Comparison(Comparator::IsNull, Value::Uint32(1).into(), Value::Uint32(2).into()),
+            Condition::parse_filter(
+                "at >= timestamp(\"2022-11-03T18:04:05Z\") and id in [uuid(\"67e55044-10b1-426f-9247-bb680e5fe0c8\")]"
+            ).unwrap()
+        ];
+
+        for condition in conditions {
+            assert_eq!(Condition::parse_from_value(condition.to_value()).unwrap(), condition);
+        }
 
-#[derive(Debug)]
-pub enum LogicTree {
-    ConditionalBranch(Condition, Box<LogicTree>),
-    Block(Vec<Statement>)
+        // Literals without a JSON form are tagged with their type.
+        let price = ValueReference::Value(Type::Decimal.decode_str("1.50").unwrap());
+        assert_eq!(price.to_value(), Value::map_from([
+            ("value".into(), Value::str_from("1.50")),
+            ("type".into(), Value::str_from("decimal"))
+        ]));
+        assert!(ValueReference::parse_from_value(Value::map_from([
+            ("value".into(), Value::str_from("1.50")),
+            ("type".into(), Value::str_from("float"))
+        ])).is_err());
+    }
 }
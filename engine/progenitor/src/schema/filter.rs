@@ -0,0 +1,871 @@
+// A text syntax for conditions, e.g. for authored filters and query strings:
+//
+//  name == "x" and (age > 3 or vip)
+//  not deleted and tags contains "new" and email is not null
+//
+// Operands are paths (see Path; \ escapes any character, e.g. a\ b, or keywords as in \and) or
+// literals: strings, numbers, true, false, null, [lists], {"maps": ...}, and the typed
+// literals decimal("1.5"), timestamp("..."), uuid("...") and bytes("<base64>"). A bare
+// reference is shorthand for comparing it to true.
+//
+// Comparators are == != < > <= >= in, not in, contains, starts_with, ends_with,
+// eq_ignore_case, like and regex, plus the postfix is null, is not null and exists.
+// not binds tighter than and, which binds tighter than or.
+//
+// Conditions write back to this syntax with to_filter, escaping member names where needed,
+// except those with non-finite floats or references that don't start with a member.
+use std::fmt::{Display, Formatter};
+
+use super::errors::SchemaError;
+use super::primitives::Value;
+use super::path::{Path, Segment};
+use super::expr::{Comparator, Condition, Conjunctive, ValueReference, TYPED_LITERALS};
+use super::scalars::encode_base64;
+
+const KEYWORDS: [&str; 15] = [
+    "and", "or", "not", "in", "is", "null", "true", "false", "exists",
+    "contains", "starts_with", "ends_with", "eq_ignore_case", "like", "regex"
+];
+
+// Bounds the recursion of nested nots, parentheses and literals.
+const MAX_DEPTH: usize = 128;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open(char),
+    Close(char),
+    Comma,
+    Colon,
+    Symbol(Comparator),
+    Str(String),
+    Number(Value),
+    Word(String)
+}
+
+fn is_word_start(token: char) -> bool {
+    token.is_alphabetic() || token == '_' || token == '*' || token == '\\'
+}
+
+fn is_word_part(token: char) -> bool {
+    token.is_alphanumeric() || matches!(token, '_' | '*' | '\\' | '.' | '[' | ']')
+}
+
+struct Lexer<'s> {
+    chars: std::iter::Peekable<std::str::CharIndices<'s>>
+}
+
+impl<'s> Lexer<'s> {
+    fn invalid(&self, position: usize, message: &str) -> SchemaError {
+        SchemaError::InvalidFilter(message.into(), position)
+    }
+
+    fn lex(mut self) -> Result<Vec<(usize, Token)>, SchemaError> {
+        let mut tokens = Vec::new();
+
+        while let Some((position, token)) = self.chars.next() {
+            let lexed = match token {
+                ' ' | '\t' | '\n' | '\r' => continue,
+                '(' | '[' | '{' => Token::Open(token),
+                ')' | ']' | '}' => Token::Close(token),
+                ',' => Token::Comma,
+                ':' => Token::Colon,
+                '=' | '!' | '<' | '>' => self.lex_symbol(position, token)?,
+                '"' => Token::Str(self.lex_str(position)?),
+                '-' | '0'..='9' => self.lex_number(position, token)?,
+                _ if is_word_start(token) => {
+                    let mut word = String::from(token);
+                    let mut escaped = token == '\\';
+                    // A ] only belongs to the word if it closes an index, e.g. in [1, a[0]].
+                    let mut open_indices = 0;
+
+                    while let Some((_, next)) = self.chars.peek() {
+                        let closes_list = *next == ']' && open_indices == 0;
+                        if !escaped && (!is_word_part(*next) || closes_list) {
+                            break;
+                        }
+
+                        if !escaped {
+                            match *next {
+                                '[' => open_indices += 1,
+                                ']' => open_indices -= 1,
+                                _ => {}
+                            }
+                        }
+                        escaped = !escaped && *next == '\\';
+                        word.push(*next);
+                        self.chars.next();
+                    }
+
+                    Token::Word(word)
+                },
+                _ => return Err(self.invalid(position, "unexpected character"))
+            };
+
+            tokens.push((position, lexed));
+        }
+
+        Ok(tokens)
+    }
+
+    fn lex_symbol(&mut self, position: usize, first: char) -> Result<Token, SchemaError> {
+        let equals = matches!(self.chars.peek(), Some((_, '=')));
+        if equals {
+            self.chars.next();
+        }
+
+        Ok(Token::Symbol(match (first, equals) {
+            ('=', true) => Comparator::Eq,
+            ('!', true) => Comparator::Neq,
+            ('<', false) => Comparator::Lt,
+            ('>', false) => Comparator::Gt,
+            ('<', true) => Comparator::Lte,
+            ('>', true) => Comparator::Gte,
+            _ => return Err(self.invalid(position, "invalid comparator"))
+        }))
+    }
+
+    fn lex_str(&mut self, position: usize) -> Result<String, SchemaError> {
+        let mut string = String::new();
+
+        loop {
+            let (escape_position, token) = self.chars.next()
+                .ok_or_else(|| self.invalid(position, "unterminated string"))?;
+
+            match token {
+                '"' => return Ok(string),
+                '\\' => {
+                    let escaped = match self.chars.next() {
+                        Some((_, '"')) => '"',
+                        Some((_, '\\')) => '\\',
+                        Some((_, 'n')) => '\n',
+                        Some((_, 't')) => '\t',
+                        Some((_, 'r')) => '\r',
+                        Some((_, 'u')) => {
+                            let mut hex = String::new();
+                            for _ in 0..4 {
+                                if let Some((_, digit)) = self.chars.next() {
+                                    hex.push(digit);
+                                }
+                            }
+
+                            u32::from_str_radix(&hex, 16).ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.invalid(escape_position, "invalid unicode escape"))?
+                        },
+                        _ => return Err(self.invalid(escape_position, "invalid escape"))
+                    };
+
+                    string.push(escaped);
+                },
+                other => string.push(other)
+            }
+        }
+    }
+
+    // Numbers are read like JSON numbers are; see JsonParser.
+    fn lex_number(&mut self, position: usize, first: char) -> Result<Token, SchemaError> {
+        let mut number = String::from(first);
+        while let Some((_, next)) = self.chars.peek() {
+            let exponent_sign = matches!(*next, '-' | '+') && number.ends_with(['e', 'E']);
+            if !(next.is_ascii_digit() || matches!(*next, '.' | 'e' | 'E') || exponent_sign) {
+                break;
+            }
+
+            number.push(*next);
+            self.chars.next();
+        }
+
+        let invalid = || self.invalid(position, "invalid number");
+
+        let value = match number.contains(['.', 'e', 'E']) {
+            true => Value::Float64(number.parse().map_err(|_| invalid())?),
+            false if first == '-' => {
+                let signed: i64 = number.parse().map_err(|_| invalid())?;
+
+                i32::try_from(signed).map(Value::Int32).unwrap_or(Value::Int64(signed))
+            },
+            false => {
+                let whole: u64 = number.parse().map_err(|_| invalid())?;
+
+                u32::try_from(whole).map(Value::Uint32).unwrap_or(Value::Uint64(whole))
+            }
+        };
+
+        Ok(Token::Number(value))
+    }
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    end: usize,
+    depth: usize
+}
+
+impl Parser {
+    fn position(&self) -> usize {
+        self.tokens.get(self.next).map(|(position, _)| *position).unwrap_or(self.end)
+    }
+
+    fn invalid(&self, message: &str) -> SchemaError {
+        SchemaError::InvalidFilter(message.into(), self.position())
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(_, token)| token)
+    }
+
+    fn peek_word(&self, word: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(next)) if next == word)
+    }
+
+    fn take(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.next).map(|(_, token)| token.clone());
+        self.next += 1;
+
+        token
+    }
+
+    fn take_word(&mut self, word: &str) -> bool {
+        let matched = self.peek_word(word);
+        if matched {
+            self.next += 1;
+        }
+
+        matched
+    }
+
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T, SchemaError>) -> Result<T, SchemaError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(self.invalid("nested too deeply"));
+        }
+
+        let parsed = parse(self);
+        self.depth -= 1;
+
+        parsed
+    }
+
+    fn expect(&mut self, expected: Token, message: &str) -> Result<(), SchemaError> {
+        match self.peek() == Some(&expected) {
+            true => {
+                self.next += 1;
+                Ok(())
+            },
+            false => Err(self.invalid(message))
+        }
+    }
+
+    fn parse_conjunctive(&mut self, op: Conjunctive) -> Result<Condition, SchemaError> {
+        let word = match op {
+            Conjunctive::Or => "or",
+            Conjunctive::And => "and"
+        };
+
+        let mut parts = Vec::from([self.parse_part(&op)?]);
+        while self.take_word(word) {
+            parts.push(self.parse_part(&op)?);
+        }
+
+        Ok(match parts.len() {
+            1 => parts.remove(0),
+            _ => Condition::Conjunctive(op, parts)
+        })
+    }
+
+    // Parts of ors are ands, parts of ands are (possibly negated) primaries.
+    fn parse_part(&mut self, op: &Conjunctive) -> Result<Condition, SchemaError> {
+        match op {
+            Conjunctive::Or => self.parse_conjunctive(Conjunctive::And),
+            Conjunctive::And => self.parse_not()
+        }
+    }
+
+    fn parse_not(&mut self) -> Result<Condition, SchemaError> {
+        match self.take_word("not") {
+            true => Ok(Condition::Not(Box::new(self.nested(Self::parse_not)?))),
+            false => self.parse_primary()
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Condition, SchemaError> {
+        if self.peek() == Some(&Token::Open('(')) {
+            self.next += 1;
+            let inner = self.nested(|parser| parser.parse_conjunctive(Conjunctive::Or))?;
+            self.expect(Token::Close(')'), "expected )")?;
+
+            return Ok(inner);
+        }
+
+        let left = self.parse_operand()?;
+
+        if self.take_word("is") {
+            let negated = self.take_word("not");
+            if !self.take_word("null") {
+                return Err(self.invalid("expected null"));
+            }
+
+            let is_null = Condition::Comparison(Comparator::IsNull, left, ValueReference::Value(Value::Null));
+            return Ok(match negated {
+                true => Condition::Not(Box::new(is_null)),
+                false => is_null
+            });
+        }
+        if self.take_word("exists") {
+            return Ok(Condition::Comparison(Comparator::Exists, left, ValueReference::Value(Value::Null)));
+        }
+
+        let comparator = match self.peek() {
+            Some(Token::Symbol(comparator)) => Some(comparator.clone()),
+            Some(Token::Word(word)) if word == "not" => Some(Comparator::NotIn),
+            Some(Token::Word(word)) => match word.as_str() {
+                "in" => Some(Comparator::In),
+                "contains" => Some(Comparator::Contains),
+                "starts_with" => Some(Comparator::StartsWith),
+                "ends_with" => Some(Comparator::EndsWith),
+                "eq_ignore_case" => Some(Comparator::EqIgnoreCase),
+                "like" => Some(Comparator::Like),
+                "regex" => Some(Comparator::Regex),
+                _ => None
+            },
+            _ => None
+        };
+
+        match (comparator, left) {
+            (Some(comparator), left) => {
+                self.next += 1;
+                if comparator == Comparator::NotIn && !self.take_word("in") {
+                    return Err(self.invalid("expected in"));
+                }
+
+                Condition::comparison(comparator, left, self.parse_operand()?)
+            },
+            (None, ValueReference::Value(Value::Bool(constant))) => Ok(Condition::constant(constant)),
+            (None, reference @ ValueReference::Reference(_)) => {
+                Ok(Condition::Comparison(Comparator::Eq, reference, ValueReference::Value(Value::Bool(true))))
+            },
+            (None, _) => Err(self.invalid("expected a comparator"))
+        }
+    }
+
+    fn parse_operand(&mut self) -> Result<ValueReference, SchemaError> {
+        let position = self.position();
+
+        match self.peek() {
+            Some(Token::Word(word)) if !KEYWORDS.contains(&word.as_str()) || matches!(word.as_str(), "true" | "false" | "null") => {},
+            Some(Token::Word(_)) => return Err(self.invalid("expected an operand")),
+            _ => return Ok(ValueReference::Value(self.parse_literal()?))
+        }
+
+        let word = match self.take() {
+            Some(Token::Word(word)) => word,
+            _ => return Err(self.invalid("expected an operand"))
+        };
+
+        match word.as_str() {
+            "true" => return Ok(ValueReference::Value(Value::Bool(true))),
+            "false" => return Ok(ValueReference::Value(Value::Bool(false))),
+            "null" => return Ok(ValueReference::Value(Value::Null)),
+            _ => {}
+        }
+
+        if let Some((_, typ)) = TYPED_LITERALS.iter().find(|(name, _)| *name == word) {
+            if self.peek() == Some(&Token::Open('(')) {
+                self.next += 1;
+                let encoded = match self.take() {
+                    Some(Token::Str(encoded)) => encoded,
+                    _ => return Err(SchemaError::InvalidFilter("expected a string".into(), position))
+                };
+                self.expect(Token::Close(')'), "expected )")?;
+
+                return Ok(ValueReference::Value(typ.decode_str(&encoded)?));
+            }
+        }
+
+        match Path::parse(&word) {
+            Ok(path) => Ok(ValueReference::Reference(path)),
+            Err(SchemaError::InvalidPath(message)) => Err(SchemaError::InvalidFilter(message, position)),
+            Err(err) => Err(err)
+        }
+    }
+
+    fn parse_literal(&mut self) -> Result<Value, SchemaError> {
+        match self.take() {
+            Some(Token::Str(string)) => Ok(Value::Str(string)),
+            Some(Token::Number(number)) => Ok(number),
+            Some(Token::Word(word)) if word == "true" || word == "false" => Ok(Value::Bool(word == "true")),
+            Some(Token::Word(word)) if word == "null" => Ok(Value::Null),
+            Some(Token::Open('[')) => {
+                let mut elements = Vec::new();
+                while self.peek() != Some(&Token::Close(']')) {
+                    elements.push(self.nested(Self::parse_literal)?);
+
+                    if !matches!(self.peek(), Some(Token::Close(']'))) {
+                        self.expect(Token::Comma, "expected , or ]")?;
+                    }
+                }
+                self.next += 1;
+
+                Ok(Value::List(elements))
+            },
+            Some(Token::Open('{')) => {
+                let mut members = Vec::new();
+                while self.peek() != Some(&Token::Close('}')) {
+                    let key = match self.take() {
+                        Some(Token::Str(key)) => key,
+                        _ => {
+                            self.next -= 1;
+                            return Err(self.invalid("expected a string key"));
+                        }
+                    };
+                    self.expect(Token::Colon, "expected :")?;
+                    members.push((key, self.nested(Self::parse_literal)?));
+
+                    if !matches!(self.peek(), Some(Token::Close('}'))) {
+                        self.expect(Token::Comma, "expected , or }")?;
+                    }
+                }
+                self.next += 1;
+
+                Ok(Value::map_from(members.into_iter().collect::<std::collections::HashMap<_, _>>()))
+            },
+            Some(Token::Word(word)) => match TYPED_LITERALS.iter().any(|(name, _)| *name == word) {
+                true => {
+                    self.next -= 1;
+                    match self.parse_operand()? {
+                        ValueReference::Value(value) => Ok(value),
+                        _ => Err(self.invalid("expected a literal"))
+                    }
+                },
+                false => {
+                    self.next -= 1;
+                    Err(self.invalid("expected a literal"))
+                }
+            },
+            _ => {
+                self.next = self.next.saturating_sub(1);
+                Err(self.invalid("expected an operand"))
+            }
+        }
+    }
+}
+
+impl Condition {
+    // Parse the text syntax described above.
+    pub fn parse_filter(src: &str) -> Result<Self, SchemaError> {
+        let lexer = Lexer { chars: src.char_indices().peekable() };
+        let mut parser = Parser { tokens: lexer.lex()?, next: 0, end: src.len(), depth: 0 };
+
+        let condition = parser.parse_conjunctive(Conjunctive::Or)?;
+
+        match parser.peek() {
+            None => Ok(condition),
+            Some(_) => Err(parser.invalid("unexpected trailing input"))
+        }
+    }
+
+    // The text syntax for the condition, or an error at the point where it can't be
+    // expressed (see above).
+    pub fn to_filter(&self) -> Result<String, SchemaError> {
+        let mut written = String::new();
+
+        match std::fmt::write(&mut written, format_args!("{}", FilterSyntax(self))) {
+            Ok(()) => Ok(written),
+            Err(_) => Err(SchemaError::InvalidFilter("can't be written as a filter".into(), written.len()))
+        }
+    }
+}
+
+fn comparator_syntax(comparator: &Comparator) -> &'static str {
+    match comparator {
+        Comparator::Eq => "==",
+        Comparator::Neq => "!=",
+        Comparator::Lt => "<",
+        Comparator::Gt => ">",
+        Comparator::Lte => "<=",
+        Comparator::Gte => ">=",
+        Comparator::In => "in",
+        Comparator::NotIn => "not in",
+        Comparator::Contains => "contains",
+        Comparator::StartsWith => "starts_with",
+        Comparator::EndsWith => "ends_with",
+        Comparator::EqIgnoreCase => "eq_ignore_case",
+        Comparator::Like => "like",
+        Comparator::Regex => "regex",
+        Comparator::IsNull => "is null",
+        Comparator::Exists => "exists"
+    }
+}
+
+fn write_str(f: &mut Formatter<'_>, string: &str) -> std::fmt::Result {
+    write!(f, "\"")?;
+    for token in string.chars() {
+        match token {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\t' => write!(f, "\\t")?,
+            '\r' => write!(f, "\\r")?,
+            control if control.is_control() => write!(f, "\\u{:04x}", control as u32)?,
+            other => write!(f, "{}", other)?
+        }
+    }
+    write!(f, "\"")
+}
+
+fn write_literal(f: &mut Formatter<'_>, value: &Value) -> std::fmt::Result {
+    match value {
+        Value::Null => write!(f, "null"),
+        Value::Bool(value) => write!(f, "{}", value),
+        Value::Int32(value) => write!(f, "{}", value),
+        Value::Uint32(value) => write!(f, "{}", value),
+        Value::Int64(value) => write!(f, "{}", value),
+        Value::Uint64(value) => write!(f, "{}", value),
+        // The syntax has no non-finite numbers.
+        Value::Float64(value) if !value.is_finite() => Err(std::fmt::Error),
+        // Debug formatting keeps a fraction part on whole numbers.
+        Value::Float64(value) => write!(f, "{:?}", value),
+        Value::Str(value) => write_str(f, value),
+        Value::Decimal(value) => write!(f, "decimal(\"{}\")", value),
+        Value::Timestamp(value) => write!(f, "timestamp(\"{}\")", value),
+        Value::Uuid(value) => write!(f, "uuid(\"{}\")", value),
+        Value::Bytes(value) => write!(f, "bytes(\"{}\")", encode_base64(value)),
+        Value::List(elements) => {
+            write!(f, "[")?;
+            for (i, element) in elements.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write_literal(f, element)?;
+            }
+            write!(f, "]")
+        },
+        Value::Map(members) => {
+            let mut keys: Vec<&String> = members.keys().collect();
+            keys.sort();
+
+            write!(f, "{{")?;
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write_str(f, key)?;
+                write!(f, ": ")?;
+                write_literal(f, &members[key])?;
+            }
+            write!(f, "}}")
+        }
+    }
+}
+
+// References are words, so anything in a member name that can't be part of one is
+// escaped, as is a first member that would otherwise read as a keyword or a literal.
+fn write_reference(f: &mut Formatter<'_>, path: &Path) -> std::fmt::Result {
+    match path.segments().first() {
+        Some(Segment::Key(key)) => {
+            let ambiguous = KEYWORDS.contains(&key.as_str())
+                || TYPED_LITERALS.iter().any(|(name, _)| name == key)
+                || !key.starts_with(is_word_start);
+            if ambiguous && key.starts_with(|token: char| token.is_alphanumeric() || token == '_') {
+                write!(f, "\\")?;
+            }
+        },
+        Some(Segment::Any) => {},
+        // Nothing reads as a reference to the root or to a list index.
+        Some(Segment::Index(_)) | None => return Err(std::fmt::Error)
+    }
+
+    for (i, segment) in path.segments().iter().enumerate() {
+        match segment {
+            Segment::Key(key) => {
+                if i > 0 {
+                    write!(f, ".")?;
+                }
+
+                for token in key.chars() {
+                    if !(token.is_alphanumeric() || token == '_') {
+                        write!(f, "\\")?;
+                    }
+                    write!(f, "{}", token)?;
+                }
+            },
+            Segment::Index(index) => write!(f, "[{}]", index)?,
+            Segment::Any if i == 0 => write!(f, "*")?,
+            Segment::Any => write!(f, "[*]")?
+        }
+    }
+
+    Ok(())
+}
+
+// Writing fails where the syntax can't express a condition, so these are only written
+// through to_filter, and the types themselves aren't Display.
+struct FilterSyntax<'fs>(&'fs Condition);
+struct OperandSyntax<'fs>(&'fs ValueReference);
+
+impl Display for OperandSyntax<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            ValueReference::Value(value) => write_literal(f, value),
+            ValueReference::Pattern(literal, _) => write_str(f, literal),
+            ValueReference::Reference(path) => write_reference(f, path)
+        }
+    }
+}
+
+impl Condition {
+    // Write with parentheses only where precedence requires them; or is 0, and is 1, and
+    // everything else binds tighter.
+    fn write(&self, f: &mut Formatter<'_>, precedence: u8) -> std::fmt::Result {
+        match self {
+            Self::True => write!(f, "true"),
+            Self::False => write!(f, "false"),
+            Self::Not(inner) => {
+                write!(f, "not ")?;
+                inner.write(f, 2)
+            },
+            Self::Comparison(op, left, right) => match op.is_unary() {
+                true => write!(f, "{} {}", OperandSyntax(left), comparator_syntax(op)),
+                false => write!(f, "{} {} {}", OperandSyntax(left), comparator_syntax(op), OperandSyntax(right))
+            },
+            Self::Conjunctive(op, parts) => {
+                let (word, own) = match op {
+                    Conjunctive::Or => ("or", 0),
+                    Conjunctive::And => ("and", 1)
+                };
+
+                match parts.len() {
+                    0 => return Self::constant(own == 1).write(f, precedence),
+                    1 => return parts[0].write(f, precedence),
+                    _ => {}
+                }
+
+                if precedence > own {
+                    write!(f, "(")?;
+                }
+                for (i, part) in parts.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " {} ", word)?;
+                    }
+                    // Nested conjunctives of the same kind keep their grouping.
+                    part.write(f, own + 1)?;
+                }
+                if precedence > own {
+                    write!(f, ")")?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Display for FilterSyntax<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.0.write(f, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::primitives::Type;
+
+    fn reference(path: &str) -> ValueReference {
+        ValueReference::Reference(Path::parse(path).unwrap())
+    }
+
+    #[test]
+    fn parse_filters() {
+        let parsed = Condition::parse_filter("name == \"x\" and (age > 3 or vip)").unwrap();
+        assert_eq!(parsed, Condition::Conjunctive(Conjunctive::And, Vec::from([
+            Condition::Comparison(Comparator::Eq, reference("name"), Value::str_from("x").into()),
+            Condition::Conjunctive(Conjunctive::Or, Vec::from([
+                Condition::Comparison(Comparator::Gt, reference("age"), Value::Uint32(3).into()),
+                Condition::Comparison(Comparator::Eq, reference("vip"), Value::Bool(true).into())
+            ]))
+        ])));
+
+        let parsed = Condition::parse_filter(
+            "not deleted and items[*].n not in [1, -2.5] and note is not null or \\and exists"
+        ).unwrap();
+        assert_eq!(parsed, Condition::Conjunctive(Conjunctive::Or, Vec::from([
+            Condition::Conjunctive(Conjunctive::And, Vec::from([
+                Condition::Not(Box::new(
+                    Condition::Comparison(Comparator::Eq, reference("deleted"), Value::Bool(true).into())
+                )),
+                Condition::Comparison(Comparator::NotIn, reference("items[*].n"), Value::List(Vec::from([
+                    Value::Uint32(1), Value::Float64(-2.5)
+                ])).into()),
+                Condition::Not(Box::new(
+                    Condition::Comparison(Comparator::IsNull, reference("note"), Value::Null.into())
+                ))
+            ])),
+            Condition::Comparison(Comparator::Exists, reference("and"), Value::Null.into())
+        ])));
+
+        assert_eq!(Condition::parse_filter("true").unwrap(), Condition::True);
+        assert_eq!(
+            Condition::parse_filter("at >= timestamp(\"2022-11-03T18:04:05Z\")").unwrap().to_filter().unwrap(),
+            "at >= timestamp(\"2022-11-03T18:04:05Z\")"
+        );
+
+        for (invalid, position) in [("a ==", 4), ("(a == 1", 7), ("a == 1 b", 7), ("a # 1", 2), ("and == 1", 0)] {
+            match Condition::parse_filter(invalid) {
+                Err(SchemaError::InvalidFilter(_, at)) => assert_eq!(at, position, "{:?}", invalid),
+                other => panic!("{:?} parsed as {:?}", invalid, other)
+            }
+        }
+    }
+
+    #[test]
+    fn print_filters() {
+        for src in [
+            "name == \"a \\\"quoted\\\"\\n string\" and (age > 3 or vip == true)",
+            "not (a == 1 and b != 2) or c is null",
+            "tags contains \"x\" and name like \"A%\" and \\in regex \"^a\" and d not in [1, 2]",
+            "not x exists and m == {\"k\": [1.0, null]}"
+        ] {
+            let parsed = Condition::parse_filter(src).unwrap();
+
+            assert_eq!(parsed.to_filter().unwrap(), src);
+            assert_eq!(Condition::parse_filter(&parsed.to_filter().unwrap()).unwrap(), parsed);
+        }
+
+        let weird = Condition::Comparison(Comparator::Eq, ValueReference::Reference(
+            Path::root().child(Segment::Key("a b-\"c".into())).child(Segment::Key("2".into()))
+        ), Value::Bool(true).into());
+        assert_eq!(weird.to_filter().unwrap(), "a\\ b\\-\\\"c.2 == true");
+
+        let unwritable = [
+            Condition::Comparison(Comparator::Eq, reference("a"), Value::Float64(f64::NAN).into()),
+            Condition::Comparison(Comparator::Eq, ValueReference::Reference(Path::root()), Value::Null.into()),
+            Condition::Comparison(Comparator::Eq, reference("[0]"), Value::Null.into())
+        ];
+        for condition in unwritable {
+            assert!(matches!(condition.to_filter(), Err(SchemaError::InvalidFilter(_, _))), "{:?}", condition);
+        }
+    }
+
+    #[test]
+    fn nesting_limit() {
+        let nested = |depth: usize, open: &str, close: &str| {
+            format!("a == 1 and {}b{}", open.repeat(depth), close.repeat(depth))
+        };
+
+        assert!(Condition::parse_filter(&nested(MAX_DEPTH, "(", ")")).is_ok());
+        assert!(Condition::parse_filter(&nested(MAX_DEPTH + 1, "(", ")")).is_err());
+        assert!(Condition::parse_filter(&nested(100_000, "not ", "")).is_err());
+        assert!(Condition::parse_filter(&format!("a == {}1{}", "[".repeat(100_000), "]".repeat(100_000))).is_err());
+    }
+
+    // A small deterministic generator, so failures reproduce.
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, bound: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+
+            self.0 % bound
+        }
+
+        fn pick<'a>(&mut self, options: &[&'a str]) -> &'a str {
+            options[self.below(options.len() as u64) as usize]
+        }
+    }
+
+    fn random_path(rng: &mut Rng) -> Path {
+        let keys = ["a", "and", "x y", "a-b", "q\"", "1st", "a.b", "[0]", "*", "\\", "é", "decimal", "_"];
+
+        let mut path = Path::root().child(match rng.below(6) {
+            0 => Segment::Any,
+            _ => Segment::Key(rng.pick(&keys).into())
+        });
+        for _ in 0..rng.below(3) {
+            path = path.child(match rng.below(4) {
+                0 => Segment::Index(rng.below(10) as usize),
+                1 => Segment::Any,
+                _ => Segment::Key(rng.pick(&keys).into())
+            });
+        }
+
+        path
+    }
+
+    // Literals as the parser reads them back, e.g. whole numbers in their narrowest variant.
+    fn random_literal(rng: &mut Rng, depth: u32) -> Value {
+        match (depth, rng.below(12)) {
+            (_, 0) => Value::Null,
+            (_, 1) => Value::Bool(rng.below(2) == 0),
+            (_, 2) => Value::Uint32(rng.below(1000) as u32),
+            (_, 3) => Value::Int32(-(rng.below(1000) as i32) - 1),
+            (_, 4) => Value::Uint64(u64::MAX - rng.below(1000)),
+            (_, 5) => Value::Int64(i64::MIN + rng.below(1000) as i64),
+            (_, 6) => Value::Float64([0.5, -2.25, 1e300, 3.0, -0.0, 1.0e-7][rng.below(6) as usize]),
+            (_, 7) => Value::str_from(rng.pick(&["", "plain", "q\"uote", "back\\slash", "line\nbreak", "\u{1}", "ünï"])),
+            (_, 8) => {
+                let (typ, encoded) = [
+                    (Type::Decimal, "-12.50"), (Type::Timestamp, "2022-11-03T18:04:05Z"),
+                    (Type::Uuid, "67e55044-10b1-426f-9247-bb680e5fe0c8"), (Type::Bytes, "aGk=")
+                ][rng.below(4) as usize].clone();
+
+                typ.decode_str(encoded).unwrap()
+            },
+            (0, _) => Value::Null,
+            (_, 9) | (_, 10) => Value::List((0..rng.below(3)).map(|_| random_literal(rng, depth - 1)).collect()),
+            _ => Value::map_from((0..rng.below(3))
+                .map(|_| (rng.pick(&["k", "a b", "\""]).to_owned(), random_literal(rng, depth - 1)))
+                .collect::<std::collections::HashMap<_, _>>())
+        }
+    }
+
+    // Conditions as the parser builds them, e.g. without single part conjunctives.
+    fn random_condition(rng: &mut Rng, depth: u32) -> Condition {
+        let operand = |rng: &mut Rng| match rng.below(3) {
+            0 => ValueReference::Reference(random_path(rng)),
+            _ => ValueReference::Value(random_literal(rng, 2))
+        };
+
+        match (depth, rng.below(6)) {
+            (_, 0) => Condition::constant(rng.below(2) == 0),
+            (_, 1) => {
+                let comparator = [Comparator::IsNull, Comparator::Exists][rng.below(2) as usize].clone();
+                Condition::Comparison(comparator, ValueReference::Reference(random_path(rng)), Value::Null.into())
+            },
+            (_, 2) => {
+                let comparator = [Comparator::Like, Comparator::Regex][rng.below(2) as usize].clone();
+                let pattern = Value::str_from(rng.pick(&["a%_", "^x\\.y$", "\"q\""]));
+
+                Condition::comparison(comparator, ValueReference::Reference(random_path(rng)), pattern.into()).unwrap()
+            },
+            (0, _) | (_, 3) => {
+                let comparator = [
+                    Comparator::Eq, Comparator::Neq, Comparator::Lt, Comparator::Gte, Comparator::In,
+                    Comparator::NotIn, Comparator::Contains, Comparator::StartsWith, Comparator::EqIgnoreCase
+                ][rng.below(9) as usize].clone();
+
+                Condition::Comparison(comparator, ValueReference::Reference(random_path(rng)), operand(rng))
+            },
+            (_, 4) => Condition::Not(Box::new(random_condition(rng, depth - 1))),
+            _ => {
+                let op = [Conjunctive::And, Conjunctive::Or][rng.below(2) as usize].clone();
+                Condition::Conjunctive(op, (0..2 + rng.below(2)).map(|_| random_condition(rng, depth - 1)).collect())
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip_random_filters() {
+        let mut rng = Rng(0x9e3779b97f4a7c15);
+
+        for _ in 0..2000 {
+            let condition = random_condition(&mut rng, 4);
+            let written = condition.to_filter().unwrap();
+
+            assert_eq!(Condition::parse_filter(&written).unwrap(), condition, "{}", written);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+// Functions callable from expressions: the standard library (see stdlib), plus native
+// functions registered with the Registry.
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+
+use super::errors::SchemaError;
+use super::primitives::{Type, Value};
+use super::stdlib;
+
+// The result type for arguments of the given types, or InvalidArguments.
+pub type SignatureFn = fn(&[Type]) -> Result<Type, SchemaError>;
+pub type CallFn = fn(&[Value]) -> Result<Value, SchemaError>;
+
+#[derive(Clone)]
+pub struct NativeFunction {
+    signature: SignatureFn,
+    call: CallFn,
+    propagates_null: bool
+}
+
+impl NativeFunction {
+    // A function that is null when any argument is, so its signature and implementation
+    // only ever see non-null arguments.
+    pub fn new(signature: SignatureFn, call: CallFn) -> Self {
+        Self { signature, call, propagates_null: true }
+    }
+
+    // A function that handles null arguments itself, e.g. a type check.
+    pub fn accepting_null(signature: SignatureFn, call: CallFn) -> Self {
+        Self { signature, call, propagates_null: false }
+    }
+
+    pub fn result_type(&self, args: &[Type]) -> Result<Type, SchemaError> {
+        if !self.propagates_null {
+            return (self.signature)(args);
+        }
+
+        let required: Vec<Type> = args.iter().map(|arg| arg.required().clone()).collect();
+        let result = (self.signature)(&required)?;
+
+        match args.iter().any(Type::is_optional) && !result.is_optional() {
+            true => Ok(Type::Optional(Box::new(result))),
+            false => Ok(result)
+        }
+    }
+
+    // Arguments are checked against the signature first, so implementations can rely on
+    // its arity and types.
+    pub fn call(&self, args: &[Value]) -> Result<Value, SchemaError> {
+        if self.propagates_null && args.contains(&Value::Null) {
+            return Ok(Value::Null);
+        }
+
+        let arg_types: Vec<Type> = args.iter().map(|arg| Type::try_from(arg).unwrap_or(Type::Any)).collect();
+        (self.signature)(&arg_types)?;
+
+        (self.call)(args)
+    }
+}
+
+#[derive(Clone)]
+pub struct Functions {
+    natives: HashMap<String, NativeFunction>
+}
+
+impl Functions {
+    // Just the standard library.
+    pub fn standard() -> Self {
+        let mut natives = HashMap::new();
+        for (name, function) in stdlib::functions() {
+            natives.insert(name.to_owned(), function);
+        }
+
+        Self { natives }
+    }
+
+    // Add (or replace) a function.
+    pub fn register(&mut self, name: impl Into<String>, function: NativeFunction) {
+        self.natives.insert(name.into(), function);
+    }
+
+    pub fn get(&self, name: &str) -> Result<&NativeFunction, SchemaError> {
+        self.natives.get(name).ok_or_else(|| SchemaError::UnknownFunction(name.to_owned()))
+    }
+
+    pub fn result_type(&self, name: &str, args: &[Type]) -> Result<Type, SchemaError> {
+        self.get(name)?.result_type(args).map_err(|err| SchemaError::Call(name.to_owned(), Box::new(err)))
+    }
+
+    pub fn call(&self, name: &str, args: &[Value]) -> Result<Value, SchemaError> {
+        self.get(name)?.call(args).map_err(|err| SchemaError::Call(name.to_owned(), Box::new(err)))
+    }
+}
+
+impl Default for Functions {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+impl Debug for Functions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut names: Vec<&String> = self.natives.keys().collect();
+        names.sort();
+
+        write!(f, "Functions({:?})", names)
+    }
+}
@@ -0,0 +1,50 @@
+// Values produced by the schema rather than supplied by clients; see Type::Default and
+// Type::Computed.
+use super::errors::SchemaError;
+use super::primitives::{Type, Value};
+use super::scalars::Timestamp;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Generator {
+    Constant(Value),
+    // The current time, e.g. for a creation timestamp.
+    Now
+}
+
+impl Generator {
+    // Either "now" or {"constant": <value>}.
+    pub fn parse_from_value(value: Value) -> Result<Self, SchemaError> {
+        match value {
+            Value::Str(name) if name == "now" => Ok(Self::Now),
+            Value::Map(_) => Ok(Self::Constant(value.lookup("constant")?)),
+            other => Err(SchemaError::InvalidDefinition(format!("invalid generator {:?}", other)))
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        match self {
+            Self::Constant(constant) => Value::map_from([("constant".into(), constant.clone())]),
+            Self::Now => Value::str_from("now")
+        }
+    }
+
+    pub fn generate(&self) -> Value {
+        match self {
+            Self::Constant(constant) => constant.clone(),
+            Self::Now => Value::Timestamp(Timestamp::now())
+        }
+    }
+
+    // Whether generated values will always be valid for the given type.
+    pub fn check_produces(&self, typ: &Type) -> Result<(), SchemaError> {
+        let valid = match self {
+            Self::Constant(constant) => typ.validate(constant).is_ok(),
+            Self::Now => matches!(typ.base(), Type::Timestamp | Type::Any)
+        };
+
+        match valid {
+            true => Ok(()),
+            false => Err(SchemaError::InvalidDefinition(format!("{:?} doesn't produce {:?}", self, typ)))
+        }
+    }
+}
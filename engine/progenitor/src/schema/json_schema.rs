@@ -0,0 +1,661 @@
+// Conversion between types and JSON Schema (draft 2020-12) documents.
+//
+// Exported schemas describe values as the JSON format writes them, so for example
+// decimals and timestamps are strings. Imports support the subset of JSON Schema that has
+// a counterpart here; other assertions are rejected rather than silently dropped, while
+// annotations (titles, descriptions, and so on) are ignored.
+//
+// Computed values export as readOnly, which doesn't say how they're computed, so they
+// import as their plain type.
+use std::collections::HashMap;
+
+use super::constraint::{Constraint, Format, Pattern};
+use super::errors::SchemaError;
+use super::generated::Generator;
+use super::named::TypeRef;
+use super::primitives::{Type, Value};
+use super::scalars::Decimal;
+use super::validation::ValidationMode;
+
+const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+const DEFS_PREFIX: &str = "#/$defs/";
+
+const ANNOTATIONS: [&str; 10] = [
+    "$schema", "$id", "$comment", "$anchor", "title", "description", "examples",
+    "deprecated", "readOnly", "writeOnly"
+];
+
+const ASSERTIONS: [&str; 24] = [
+    "$defs", "$ref", "type", "format", "contentEncoding", "enum", "const", "items",
+    "properties", "required", "additionalProperties", "anyOf", "oneOf", "minimum", "maximum",
+    "minLength", "maxLength", "minItems", "maxItems", "minProperties", "maxProperties",
+    "pattern", "uniqueItems", "default"
+];
+
+fn invalid(message: impl Into<String>) -> SchemaError {
+    SchemaError::InvalidDefinition(message.into())
+}
+
+fn keywords<const N: usize>(pairs: [(&str, Value); N]) -> HashMap<String, Value> {
+    pairs.into_iter()
+        .map(|(key, value)| (key.to_owned(), value))
+        .collect()
+}
+
+fn typed(name: &str) -> HashMap<String, Value> {
+    keywords([("type", Value::str_from(name))])
+}
+
+fn string_list<'a>(strings: impl Iterator<Item = &'a String>) -> Value {
+    let mut strings: Vec<&String> = strings.collect();
+    strings.sort();
+
+    Value::List(strings.into_iter().map(Value::str_from).collect())
+}
+
+// Bounds are JSON numbers, even on types written as strings. Decimals are written as the
+// float that reads back as the same number, if there is one.
+fn export_bound(bound: &Value) -> Result<Value, SchemaError> {
+    let decimal = match bound {
+        Value::Decimal(decimal) => decimal.normalized(),
+        other => return Ok(other.clone())
+    };
+
+    if let (0, Ok(integer)) = (decimal.scale(), i64::try_from(decimal.mantissa())) {
+        return Ok(Value::Int64(integer));
+    }
+
+    let float = decimal.to_f64();
+    match Decimal::parse(&float.to_string()) {
+        Ok(read) if float.is_finite() && read == decimal => Ok(Value::Float64(float)),
+        _ => Err(invalid(format!("the bound {} has no exact JSON number", decimal)))
+    }
+}
+
+fn export_constraint(constraint: &Constraint, base: &Type) -> Result<(&'static str, Value), SchemaError> {
+    let length_keys = match base {
+        Type::List(_) => ("minItems", "maxItems"),
+        Type::Map(_) => ("minProperties", "maxProperties"),
+        _ => ("minLength", "maxLength")
+    };
+
+    Ok(match constraint {
+        Constraint::Min(bound) => ("minimum", export_bound(bound)?),
+        Constraint::Max(bound) => ("maximum", export_bound(bound)?),
+        Constraint::MinLength(length) => (length_keys.0, Value::Uint64(*length as u64)),
+        Constraint::MaxLength(length) => (length_keys.1, Value::Uint64(*length as u64)),
+        Constraint::Pattern(pattern) => ("pattern", Value::str_from(pattern.source())),
+        Constraint::Format(Format::Email) => ("format", Value::str_from("email")),
+        Constraint::Format(Format::Url) => ("format", Value::str_from("uri")),
+        Constraint::Unique => ("uniqueItems", Value::Bool(true))
+    })
+}
+
+fn export(typ: &Type, defs: &mut HashMap<String, Value>) -> Result<HashMap<String, Value>, SchemaError> {
+    Ok(match typ {
+        Type::Any => HashMap::new(),
+        Type::Bool => typed("boolean"),
+        Type::Int32 => keywords([("type", Value::str_from("integer")), ("format", Value::str_from("int32"))]),
+        Type::Uint32 => keywords([("type", Value::str_from("integer")), ("format", Value::str_from("uint32"))]),
+        Type::Int64 => keywords([("type", Value::str_from("integer")), ("format", Value::str_from("int64"))]),
+        Type::Uint64 => keywords([("type", Value::str_from("integer")), ("format", Value::str_from("uint64"))]),
+        Type::Float64 => keywords([("type", Value::str_from("number")), ("format", Value::str_from("double"))]),
+        Type::Decimal => keywords([("type", Value::str_from("string")), ("format", Value::str_from("decimal"))]),
+        Type::String => typed("string"),
+        Type::Bytes => keywords([("type", Value::str_from("string")), ("contentEncoding", Value::str_from("base64"))]),
+        Type::Timestamp => keywords([("type", Value::str_from("string")), ("format", Value::str_from("date-time"))]),
+        Type::Uuid => keywords([("type", Value::str_from("string")), ("format", Value::str_from("uuid"))]),
+        Type::List(inner) => keywords([
+            ("type", Value::str_from("array")),
+            ("items", Value::Map(export(inner, defs)?))
+        ]),
+        Type::Map(members) => {
+            let properties = members.iter()
+                .map(|(key, member)| Ok((key.clone(), Value::Map(export(member, defs)?))))
+                .collect::<Result<_, SchemaError>>()?;
+            let required = members.iter()
+                .filter(|(_, member)| !member.is_optional())
+                .map(|(key, _)| key);
+
+            keywords([
+                ("type", Value::str_from("object")),
+                ("properties", Value::Map(properties)),
+                ("required", string_list(required))
+            ])
+        },
+        Type::Enum(options) => keywords([
+            ("type", Value::str_from("string")),
+            ("enum", Value::List(options.iter().map(Value::str_from).collect()))
+        ]),
+        Type::Union(discriminator, variants) => {
+            let mut tags: Vec<&String> = variants.keys().collect();
+            tags.sort();
+
+            let mut branches = Vec::with_capacity(tags.len());
+            for tag in tags {
+                let tag_schema = Value::map_from([("const".into(), Value::str_from(tag))]);
+
+                let mut branch = export(&variants[tag], defs)?;
+                let is_object = matches!(
+                    (branch.get("properties"), branch.get("required")),
+                    (Some(Value::Map(_)), Some(Value::List(_)))
+                );
+
+                if is_object {
+                    if let Some(Value::Map(properties)) = branch.get_mut("properties") {
+                        properties.insert(discriminator.clone(), tag_schema);
+                    }
+                    if let Some(Value::List(required)) = branch.get_mut("required") {
+                        required.insert(0, Value::str_from(discriminator));
+                    }
+                }
+                else {
+                    branch = keywords([("allOf", Value::List(Vec::from([
+                        Value::Map(branch),
+                        Value::Map(keywords([
+                            ("type", Value::str_from("object")),
+                            ("properties", Value::map_from([(discriminator.clone(), tag_schema)])),
+                            ("required", Value::List(Vec::from([Value::str_from(discriminator)])))
+                        ]))
+                    ])))]);
+                }
+
+                branches.push(Value::Map(branch));
+            }
+
+            keywords([("oneOf", Value::List(branches))])
+        },
+        Type::Optional(inner) => keywords([("anyOf", Value::List(Vec::from([
+            Value::Map(export(inner, defs)?),
+            Value::Map(typed("null"))
+        ])))]),
+        Type::Constrained(inner, constraints) => {
+            let mut schema = export(inner, defs)?;
+            for constraint in constraints.iter() {
+                let (key, value) = export_constraint(constraint, inner.base())?;
+                schema.insert(key.to_owned(), value);
+            }
+
+            schema
+        },
+        Type::Ref(reference) => {
+            if let (false, Some(target)) = (defs.contains_key(reference.name()), reference.target()) {
+                // Reserve the name first so recursive references stop here.
+                defs.insert(reference.name().to_owned(), Value::Null);
+
+                let target = Value::Map(export(target, defs)?);
+                defs.insert(reference.name().to_owned(), target);
+            }
+
+            keywords([("$ref", Value::str_from(format!("{}{}", DEFS_PREFIX, reference.name())))])
+        },
+        Type::Default(inner, generator) => {
+            let mut schema = export(inner, defs)?;
+            if let Generator::Constant(constant) = generator {
+                schema.insert("default".into(), constant.clone());
+            }
+
+            schema
+        },
+        // JSON Schema can't say how a value is computed, only that clients can't set it.
+        Type::Computed(inner, _) => {
+            let mut schema = export(inner, defs)?;
+            schema.insert("readOnly".into(), Value::Bool(true));
+
+            schema
+        }
+    })
+}
+
+fn import_list(schema: &HashMap<String, Value>, key: &str) -> Result<Option<Vec<Value>>, SchemaError> {
+    match schema.get(key) {
+        Some(Value::List(members)) => Ok(Some(members.clone())),
+        Some(_) => Err(invalid(format!("{} must be a list", key))),
+        None => Ok(None)
+    }
+}
+
+fn import_strings(values: Vec<Value>) -> Result<Vec<String>, SchemaError> {
+    values.into_iter().map(String::try_from).collect()
+}
+
+// A oneOf whose branches are all objects with a distinct constant for the same property.
+fn import_union(branches: Vec<Value>) -> Result<Type, SchemaError> {
+    let not_union = || invalid("oneOf is only supported for discriminated unions");
+
+    let properties = |branch: &Value| match branch.lookup("properties") {
+        Ok(Value::Map(properties)) => Ok(properties),
+        _ => Err(not_union())
+    };
+
+    let first = properties(branches.first().ok_or_else(not_union)?)?;
+    let mut candidates: Vec<&String> = first.iter()
+        .filter(|(_, property)| matches!(property.lookup("const"), Ok(Value::Str(_))))
+        .map(|(key, _)| key)
+        .collect();
+    candidates.sort();
+
+    let discriminator = candidates.into_iter()
+        .find(|key| branches.iter().all(|branch| {
+            properties(branch).is_ok_and(|properties| {
+                properties.get(*key).is_some_and(|property| matches!(property.lookup("const"), Ok(Value::Str(_))))
+            })
+        }))
+        .ok_or_else(not_union)?
+        .clone();
+
+    let mut variants = HashMap::with_capacity(branches.len());
+    for branch in branches {
+        let mut branch = match branch {
+            Value::Map(branch) => branch,
+            _ => return Err(not_union())
+        };
+
+        let tag: String = match branch.get_mut("properties") {
+            Some(Value::Map(properties)) => properties.remove(&discriminator)
+                .ok_or_else(not_union)?
+                .lookup("const")?
+                .try_into()?,
+            _ => return Err(not_union())
+        };
+
+        if let Some(Value::List(required)) = branch.get_mut("required") {
+            required.retain(|key| *key != Value::str_from(discriminator.as_str()));
+        }
+
+        variants.insert(tag, import(&Value::Map(branch))?);
+    }
+
+    Ok(Type::Union(discriminator, variants))
+}
+
+// Reads the format of a schema with the given type name (empty when untyped), rejecting
+// those that are neither imported as a type nor as a constraint.
+fn import_format(name: &str, schema: &HashMap<String, Value>) -> Result<Option<String>, SchemaError> {
+    let supported: &[&str] = match name {
+        "integer" => &["int32", "uint32", "int64", "uint64"],
+        "number" => &["double"],
+        "string" => &["decimal", "date-time", "uuid", "email", "uri"],
+        "" => &["email", "uri"],
+        _ => &[]
+    };
+
+    match schema.get("format") {
+        Some(format) => {
+            let format = String::try_from(format.clone())?;
+            match supported.contains(&format.as_str()) {
+                true => Ok(Some(format)),
+                false => Err(invalid(format!("unsupported format {} for {}", format, name)))
+            }
+        },
+        None => Ok(None)
+    }
+}
+
+fn import_typed(name: &str, schema: &HashMap<String, Value>) -> Result<Type, SchemaError> {
+    let format = import_format(name, schema)?;
+
+    if name != "string" && schema.contains_key("enum") {
+        return Err(invalid("enum is only supported for strings"));
+    }
+
+    Ok(match (name, format.as_deref()) {
+        ("boolean", _) => Type::Bool,
+        ("integer", Some("int32")) => Type::Int32,
+        ("integer", Some("uint32")) => Type::Uint32,
+        ("integer", Some("uint64")) => Type::Uint64,
+        ("integer", _) => Type::Int64,
+        ("number", _) => Type::Float64,
+        ("string", _) if schema.contains_key("enum") => {
+            Type::Enum(import_strings(import_list(schema, "enum")?.unwrap_or_default())?)
+        },
+        ("string", _) if schema.get("contentEncoding") == Some(&Value::str_from("base64")) => Type::Bytes,
+        ("string", Some("decimal")) => Type::Decimal,
+        ("string", Some("date-time")) => Type::Timestamp,
+        ("string", Some("uuid")) => Type::Uuid,
+        ("string", _) => Type::String,
+        ("array", _) => Type::List(Box::new(match schema.get("items") {
+            Some(items) => import(items)?,
+            None => Type::Any
+        })),
+        ("object", _) => {
+            if schema.get("additionalProperties").is_some_and(|allowed| *allowed != Value::Bool(true)) {
+                return Err(invalid("additionalProperties is only supported as true, see ValidationMode"));
+            }
+
+            let required = import_strings(import_list(schema, "required")?.unwrap_or_default())?;
+
+            let mut members = HashMap::new();
+            if let Some(properties) = schema.get("properties") {
+                let properties = match properties {
+                    Value::Map(properties) => properties,
+                    _ => return Err(invalid("properties must be a map"))
+                };
+
+                for (key, property) in properties.iter() {
+                    let member = import(property)?;
+
+                    let member = match required.contains(key) || member.is_optional() {
+                        true => member,
+                        false => Type::Optional(Box::new(member))
+                    };
+
+                    members.insert(key.clone(), member);
+                }
+            }
+
+            if let Some(missing) = required.iter().find(|key| !members.contains_key(*key)) {
+                return Err(invalid(format!("required property {} isn't in properties", missing)));
+            }
+
+            Type::Map(members)
+        },
+        ("null", _) => Type::Optional(Box::new(Type::Any)),
+        (other, _) => return Err(invalid(format!("unsupported type {}", other)))
+    })
+}
+
+// Numeric bounds on decimals are read as decimals, the inverse of export_bound.
+fn import_bound(bound: &Value, typ: &Type) -> Value {
+    let numeric = matches!(bound, Value::Float64(_)) || bound.as_integer().is_some();
+
+    match (typ.base(), numeric) {
+        (Type::Decimal, true) => Type::Decimal.normalize(bound, ValidationMode::Coerce).unwrap_or_else(|_| bound.clone()),
+        _ => bound.clone()
+    }
+}
+
+fn import_constraints(schema: &HashMap<String, Value>, typ: &Type) -> Result<Vec<Constraint>, SchemaError> {
+    let mut constraints = Vec::new();
+
+    let length = |value: &Value| -> Result<usize, SchemaError> {
+        let length: u64 = value.clone().try_into()?;
+
+        usize::try_from(length).map_err(|_| invalid("length out of range"))
+    };
+
+    // In the same order as the definition notation, so that round trips are exact.
+    let keys = [
+        "minimum", "maximum", "minLength", "minItems", "minProperties", "maxLength", "maxItems",
+        "maxProperties", "pattern", "format", "uniqueItems"
+    ];
+
+    for key in keys {
+        let value = match schema.get(key) {
+            Some(value) => value,
+            None => continue
+        };
+
+        let constraint = match key {
+            "minimum" => Constraint::parse_from_value("min", import_bound(value, typ))?,
+            "maximum" => Constraint::parse_from_value("max", import_bound(value, typ))?,
+            "minLength" | "minItems" | "minProperties" => Constraint::MinLength(length(value)?),
+            "maxLength" | "maxItems" | "maxProperties" => Constraint::MaxLength(length(value)?),
+            "pattern" => Constraint::Pattern(Pattern::new(&String::try_from(value.clone())?)?),
+            "format" if *value == Value::str_from("email") => Constraint::Format(Format::Email),
+            "format" if *value == Value::str_from("uri") => Constraint::Format(Format::Url),
+            "uniqueItems" if *value == Value::Bool(true) => Constraint::Unique,
+            _ => continue
+        };
+
+        if !constraint.applies_to(typ) {
+            return Err(invalid(format!("{} doesn't apply to {:?}", key, typ)));
+        }
+
+        constraints.push(constraint);
+    }
+
+    Ok(constraints)
+}
+
+fn import(schema: &Value) -> Result<Type, SchemaError> {
+    let schema = match schema {
+        Value::Bool(true) => return Ok(Type::Any),
+        Value::Map(schema) => schema,
+        _ => return Err(invalid("a schema must be a map or true"))
+    };
+
+    for key in schema.keys() {
+        if !ANNOTATIONS.contains(&key.as_str()) && !ASSERTIONS.contains(&key.as_str()) {
+            return Err(invalid(format!("unsupported keyword {}", key)));
+        }
+    }
+
+    if let Some(reference) = schema.get("$ref") {
+        // A reference stands for its target alone, so other assertions next to it would be
+        // dropped; $defs only holds the targets.
+        if let Some(key) = schema.keys().find(|key| ASSERTIONS.contains(&key.as_str()) && *key != "$ref" && *key != "$defs") {
+            return Err(invalid(format!("{} is unsupported next to $ref", key)));
+        }
+
+        let reference: String = reference.clone().try_into()?;
+
+        return match reference.strip_prefix(DEFS_PREFIX) {
+            Some(name) => Ok(Type::Ref(TypeRef::new(name))),
+            None => Err(invalid(format!("unsupported reference {}", reference)))
+        };
+    }
+
+    let mut nullable = false;
+    let mut typ = if let Some(branches) = import_list(schema, "anyOf")? {
+        let null = Value::Map(typed("null"));
+
+        match &branches[..] {
+            [other, nullish] | [nullish, other] if *nullish == null => Type::Optional(Box::new(import(other)?)),
+            _ => return Err(invalid("anyOf is only supported for nullable types"))
+        }
+    }
+    else if let Some(branches) = import_list(schema, "oneOf")? {
+        import_union(branches)?
+    }
+    else {
+        let names = match schema.get("type") {
+            Some(Value::Str(name)) => Vec::from([name.clone()]),
+            Some(Value::List(names)) => import_strings(names.clone())?,
+            Some(_) => return Err(invalid("type must be a string or list")),
+            None => Vec::new()
+        };
+
+        nullable = names.iter().any(|name| name == "null");
+        let names: Vec<&String> = names.iter().filter(|name| *name != "null").collect();
+
+        match (&names[..], schema.get("const")) {
+            ([], Some(Value::Str(constant))) => Type::Enum(Vec::from([constant.clone()])),
+            ([name], Some(Value::Str(constant))) if *name == "string" => Type::Enum(Vec::from([constant.clone()])),
+            (_, Some(_)) => return Err(invalid("const is only supported for strings")),
+            ([name], _) => import_typed(name, schema)?,
+            ([], _) if schema.contains_key("enum") => import_typed("string", schema)?,
+            ([], _) if schema.contains_key("properties") => import_typed("object", schema)?,
+            ([], _) => {
+                import_format("", schema)?;
+                Type::Any
+            },
+            _ => return Err(invalid("multiple types are only supported with null"))
+        }
+    };
+
+    let constraints = import_constraints(schema, &typ)?;
+    if !constraints.is_empty() {
+        typ = Type::Constrained(Box::new(typ), constraints);
+    }
+
+    if nullable {
+        typ = Type::Optional(Box::new(typ));
+    }
+
+    if let Some(default) = schema.get("default") {
+        // Defaults come from JSON, so numbers may need widening to the declared type.
+        let default = typ.normalize(default, ValidationMode::Coerce)
+            .map_err(|_| invalid("default doesn't match its type"))?;
+
+        typ = Type::Default(Box::new(typ), Generator::Constant(default));
+    }
+
+    Ok(typ)
+}
+
+impl Type {
+    // Fails for bounds that JSON numbers can't express exactly.
+    pub fn to_json_schema(&self) -> Result<Value, SchemaError> {
+        let mut defs = HashMap::new();
+        let mut schema = export(self, &mut defs)?;
+
+        schema.insert("$schema".into(), Value::str_from(DIALECT));
+        if !defs.is_empty() {
+            schema.insert("$defs".into(), Value::Map(defs));
+        }
+
+        Ok(Value::Map(schema))
+    }
+
+    // Import a JSON Schema document, along with the types named in its $defs. References
+    // between them are linked; the named types can also be given to the Registry.
+    pub fn parse_json_schema(document: Value) -> Result<(Type, HashMap<String, Type>), SchemaError> {
+        let mut named = HashMap::new();
+        if let Ok(defs) = document.lookup("$defs") {
+            let defs = match defs {
+                Value::Map(defs) => defs,
+                _ => return Err(invalid("$defs must be a map"))
+            };
+
+            for (name, def) in defs.iter() {
+                named.insert(name.clone(), import(def)?);
+            }
+        }
+
+        let typ = import(&document)?;
+
+        let resolve = |name: &str| named.get(name).cloned();
+        typ.link(&resolve)?;
+        for def in named.values() {
+            def.link(&resolve)?;
+        }
+
+        Ok((typ, named))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_schema_round_trip() {
+        let typ = Type::Map(HashMap::from([
+            ("id".into(), Type::Uuid),
+            ("email".into(), Type::Constrained(Box::new(Type::String), Vec::from([
+                Constraint::MaxLength(64),
+                Constraint::Format(Format::Email)
+            ]))),
+            ("age".into(), Type::Optional(Box::new(Type::Constrained(Box::new(Type::Uint32), Vec::from([
+                Constraint::Max(Value::Uint32(150))
+            ]))))),
+            ("tags".into(), Type::List(Box::new(Type::Enum(Vec::from(["a".into(), "b".into()]))))),
+            ("visits".into(), Type::Default(Box::new(Type::Int64), Generator::Constant(Value::Int64(0)))),
+            ("payment".into(), Type::Union("kind".into(), HashMap::from([
+                ("card".into(), Type::Map(HashMap::from([("number".into(), Type::String)]))),
+                ("cash".into(), Type::Map(HashMap::new()))
+            ])))
+        ]));
+
+        let (imported, named) = Type::parse_json_schema(typ.to_json_schema().unwrap()).unwrap();
+
+        assert_eq!(imported, typ);
+        assert!(named.is_empty());
+    }
+
+    #[test]
+    fn json_schema_decimal_bounds() {
+        let typ = Type::Constrained(Box::new(Type::Decimal), Vec::from([
+            Constraint::Min(Value::Decimal(Decimal::new(150, 2))),
+            Constraint::Max(Value::Decimal(Decimal::new(1000, 1)))
+        ]));
+
+        let schema = typ.to_json_schema().unwrap();
+        let Value::Map(keywords) = &schema else { panic!("{:?}", schema) };
+        assert_eq!(keywords.get("minimum"), Some(&Value::Float64(1.5)));
+        assert_eq!(keywords.get("maximum"), Some(&Value::Int64(100)));
+
+        let (imported, _) = Type::parse_json_schema(schema).unwrap();
+        assert_eq!(imported, typ);
+
+        // No float reads back as this one.
+        let inexact = Type::Constrained(Box::new(Type::Decimal), Vec::from([
+            Constraint::Min(Value::Decimal(Decimal::parse("0.1000000000000000000001").unwrap()))
+        ]));
+        assert!(inexact.to_json_schema().is_err());
+    }
+
+    #[test]
+    fn json_schema_refs() {
+        let comment = Type::Map(HashMap::from([
+            ("text".into(), Type::String),
+            ("replies".into(), Type::List(Box::new(Type::Ref(TypeRef::new("comment")))))
+        ]));
+        let linked = comment.clone();
+        comment.link(&|_| Some(linked.clone())).unwrap();
+
+        let schema = Type::Ref(TypeRef::new("comment"));
+        schema.link(&|_| Some(comment.clone())).unwrap();
+
+        let document = schema.to_json_schema().unwrap();
+        assert_eq!(document.lookup("$ref").unwrap(), Value::str_from("#/$defs/comment"));
+
+        let (imported, named) = Type::parse_json_schema(document).unwrap();
+        assert_eq!(imported, schema);
+        assert_eq!(named["comment"], comment);
+        assert!(imported.validate(&Value::map_from([
+            ("text".into(), Value::str_from("hi")),
+            ("replies".into(), Value::List(Vec::new()))
+        ])).is_ok());
+    }
+
+    #[test]
+    fn json_schema_import() {
+        let document = Value::map_from([
+            ("type".into(), Value::List(Vec::from([Value::str_from("string"), Value::str_from("null")]))),
+            ("format".into(), Value::str_from("date-time")),
+            ("description".into(), Value::str_from("ignored"))
+        ]);
+        assert_eq!(Type::parse_json_schema(document).unwrap().0, Type::Optional(Box::new(Type::Timestamp)));
+
+        assert!(Type::parse_json_schema(Value::map_from([
+            ("type".into(), Value::str_from("number")),
+            ("multipleOf".into(), Value::Uint32(2))
+        ])).is_err());
+
+        // Keywords that can't be imported faithfully are rejected.
+        let rejected = [
+            Value::map_from([("type".into(), Value::str_from("integer")), ("const".into(), Value::Int64(1))]),
+            Value::map_from([("type".into(), Value::str_from("string")), ("format".into(), Value::str_from("hostname"))]),
+            Value::map_from([("type".into(), Value::str_from("number")), ("format".into(), Value::str_from("int32"))]),
+            Value::map_from([("format".into(), Value::str_from("ipv4"))]),
+            Value::map_from([
+                ("type".into(), Value::str_from("integer")),
+                ("enum".into(), Value::List(Vec::from([Value::Int64(1)])))
+            ]),
+            Value::map_from([
+                ("type".into(), Value::str_from("object")),
+                ("properties".into(), Value::map_from([("name".into(), Value::map_from([("type".into(), Value::str_from("string"))]))])),
+                ("required".into(), Value::List(Vec::from([Value::str_from("id")])))
+            ]),
+            Value::map_from([("type".into(), Value::str_from("number")), ("minimum".into(), Value::str_from("1"))]),
+            Value::map_from([
+                ("$defs".into(), Value::map_from([("n".into(), Value::map_from([("type".into(), Value::str_from("integer"))]))])),
+                ("$ref".into(), Value::str_from("#/$defs/n")),
+                ("minimum".into(), Value::Int64(3))
+            ]),
+            Value::map_from([
+                ("$defs".into(), Value::map_from([("n".into(), Value::map_from([("type".into(), Value::str_from("integer"))]))])),
+                ("$ref".into(), Value::str_from("#/$defs/n")),
+                ("type".into(), Value::str_from("integer"))
+            ])
+        ];
+        for document in rejected {
+            assert!(Type::parse_json_schema(document.clone()).is_err(), "{:?}", document);
+        }
+
+        let document = Value::map_from([("type".into(), Value::str_from("string")), ("const".into(), Value::str_from("a"))]);
+        assert_eq!(Type::parse_json_schema(document).unwrap().0, Type::Enum(Vec::from(["a".into()])));
+    }
+}
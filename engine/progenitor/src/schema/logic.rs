@@ -0,0 +1,766 @@
+// Indirectly represented logic: expressions over the variables in a scope, and trees of
+// statements that assign them.
+//
+// Arithmetic is checked (overflow and division by zero are errors) and follows the
+// comparison semantics of Comparator where it can: numbers mix across variants, and
+// null is unknown, so arithmetic on it is null and and/or use three-valued logic.
+// Mixed integer variants produce the narrowest variant holding both ranges, e.g.
+// Int32 and Uint32 produce Int64, anything with a Float64 produces a Float64, and
+// decimals mix with integers but not floats.
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::errors::SchemaError;
+use super::primitives::{Type, Value};
+use super::scalars::Decimal;
+use super::path::{Path, Segment};
+use super::expr::{Condition, ValueReference};
+use super::functions::Functions;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    And,
+    Or
+}
+
+// Whether mixed integer operands produce a signed and/or 64 bit result.
+fn integer_result(left: &Type, right: &Type) -> (bool, bool) {
+    let signed = matches!(left, Type::Int32 | Type::Int64) || matches!(right, Type::Int32 | Type::Int64);
+    let wide = matches!(left, Type::Int64 | Type::Uint64) || matches!(right, Type::Int64 | Type::Uint64)
+        || (signed && (*left == Type::Uint32 || *right == Type::Uint32));
+
+    (signed, wide)
+}
+
+fn sample_type(value: &Value) -> Type {
+    Type::try_from(value).unwrap_or(Type::Any)
+}
+
+impl Operator {
+    pub fn parse_from_value(value: Value) -> Result<Self, SchemaError> {
+        let which: String = value.try_into()?;
+
+        match which.as_str() {
+            "add" => Ok(Self::Add),
+            "sub" => Ok(Self::Sub),
+            "mul" => Ok(Self::Mul),
+            "div" => Ok(Self::Div),
+            "and" => Ok(Self::And),
+            "or" => Ok(Self::Or),
+            _ => Err(SchemaError::NotImplemented("invalid operator".into()))
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        Value::str_from(match self {
+            Self::Add => "add",
+            Self::Sub => "sub",
+            Self::Mul => "mul",
+            Self::Div => "div",
+            Self::And => "and",
+            Self::Or => "or"
+        })
+    }
+
+    fn invalid(&self, left: Type, right: Type) -> SchemaError {
+        SchemaError::InvalidOperation(self.clone(), Box::new(left), Box::new(right))
+    }
+
+    // The type of the result for operands of the given types.
+    pub fn result_type(&self, left: &Type, right: &Type) -> Result<Type, SchemaError> {
+        let result = match (self, left.base(), right.base()) {
+            (_, Type::Any, _) | (_, _, Type::Any) => Type::Any,
+            (Self::And | Self::Or, Type::Bool, Type::Bool) => Type::Bool,
+            (Self::Add, Type::String | Type::Enum(_), Type::String | Type::Enum(_)) => Type::String,
+            (Self::Add, Type::List(inner), _) if inner.comparable_with(right) => left.base().clone(),
+            (Self::And | Self::Or, _, _) => return Err(self.invalid(left.clone(), right.clone())),
+            (_, a, b) if a.is_numeric() && b.is_numeric() => match (a, b) {
+                (Type::Float64, _) | (_, Type::Float64) => Type::Float64,
+                (Type::Decimal, _) | (_, Type::Decimal) if *self == Self::Div => {
+                    return Err(self.invalid(left.clone(), right.clone()));
+                },
+                (Type::Decimal, _) | (_, Type::Decimal) => Type::Decimal,
+                (a, b) => match integer_result(a, b) {
+                    (true, false) => Type::Int32,
+                    (true, true) => Type::Int64,
+                    (false, false) => Type::Uint32,
+                    (false, true) => Type::Uint64
+                }
+            },
+            _ => return Err(self.invalid(left.clone(), right.clone()))
+        };
+
+        match left.is_optional() || right.is_optional() {
+            true => Ok(Type::Optional(Box::new(result))),
+            false => Ok(result)
+        }
+    }
+
+    pub fn evaluate(&self, left: &Value, right: &Value) -> Result<Value, SchemaError> {
+        let invalid = || self.invalid(sample_type(left), sample_type(right));
+
+        if let Self::And | Self::Or = self {
+            let truth = |value: &Value| match value {
+                Value::Bool(truth) => Ok(Some(*truth)),
+                Value::Null => Ok(None),
+                _ => Err(invalid())
+            };
+
+            // The value that decides the result when either side is it.
+            let decisive = *self == Self::Or;
+            return Ok(match (truth(left)?, truth(right)?) {
+                (Some(a), Some(b)) if a == decisive || b == decisive => Value::Bool(decisive),
+                (Some(a), _) | (_, Some(a)) if a == decisive => Value::Bool(decisive),
+                (Some(_), Some(_)) => Value::Bool(!decisive),
+                _ => Value::Null
+            });
+        }
+
+        let overflow = || SchemaError::OutOfRange(format!("{:?} overflowed or divided by zero", self));
+
+        Ok(match (left, right) {
+            (Value::Null, _) | (_, Value::Null) => Value::Null,
+            (Value::List(elements), element) if *self == Self::Add => {
+                let mut appended = elements.clone();
+                appended.push(element.clone());
+
+                Value::List(appended)
+            },
+            (Value::Str(a), Value::Str(b)) if *self == Self::Add => Value::Str(a.to_owned() + b),
+            (Value::Float64(_), _) | (_, Value::Float64(_)) => {
+                let (a, b) = (left.as_f64().ok_or_else(invalid)?, right.as_f64().ok_or_else(invalid)?);
+
+                let result = match self {
+                    Self::Add => a + b,
+                    Self::Sub => a - b,
+                    Self::Mul => a * b,
+                    _ if b == 0.0 => return Err(overflow()),
+                    _ => a / b
+                };
+
+                // Finite operands overflowing to infinity.
+                match result.is_finite() || !(a.is_finite() && b.is_finite()) {
+                    true => Value::Float64(result),
+                    false => return Err(overflow())
+                }
+            },
+            (Value::Decimal(_), _) | (_, Value::Decimal(_)) => {
+                let as_decimal = |value: &Value| match value {
+                    Value::Decimal(decimal) => Some(*decimal),
+                    other => other.as_integer().map(|integer| Decimal::new(integer, 0))
+                };
+                let (a, b) = (as_decimal(left).ok_or_else(invalid)?, as_decimal(right).ok_or_else(invalid)?);
+
+                Value::Decimal(match self {
+                    Self::Add => a.checked_add(&b),
+                    Self::Sub => a.checked_sub(&b),
+                    Self::Mul => a.checked_mul(&b),
+                    _ => return Err(invalid())
+                }.ok_or_else(overflow)?)
+            },
+            _ => {
+                let (a, b) = (left.as_integer().ok_or_else(invalid)?, right.as_integer().ok_or_else(invalid)?);
+
+                let result = match self {
+                    Self::Add => a.checked_add(b),
+                    Self::Sub => a.checked_sub(b),
+                    Self::Mul => a.checked_mul(b),
+                    _ => a.checked_div(b)
+                }.ok_or_else(overflow)?;
+
+                let narrowed = match integer_result(&sample_type(left), &sample_type(right)) {
+                    (true, false) => i32::try_from(result).ok().map(Value::Int32),
+                    (true, true) => i64::try_from(result).ok().map(Value::Int64),
+                    (false, false) => u32::try_from(result).ok().map(Value::Uint32),
+                    (false, true) => u64::try_from(result).ok().map(Value::Uint64)
+                };
+
+                narrowed.ok_or_else(overflow)?
+            }
+        })
+    }
+}
+
+// Named values for logic to read and assign, and the functions it may call.
+#[derive(Debug, Clone, Default)]
+pub struct LogicScope {
+    values: HashMap<String, Value>,
+    functions: Arc<Functions>
+}
+
+impl LogicScope {
+    // A scope with just the standard library.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_functions(functions: Arc<Functions>) -> Self {
+        Self {
+            values: HashMap::new(),
+            functions
+        }
+    }
+
+    pub fn functions(&self) -> &Functions {
+        &self.functions
+    }
+
+    pub fn bind(&mut self, name: impl Into<String>, value: Value) {
+        self.values.insert(name.into(), value);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.values.get(name)
+    }
+
+    // The types of the bound variables, as a map type, for validating logic against.
+    pub fn types(&self) -> Type {
+        Type::Map(self.values.iter().map(|(name, value)| (name.clone(), sample_type(value))).collect())
+    }
+
+    // The first segment of a reference names a variable and the rest are looked up within
+    // it, as conditions look up references (so absent members are null).
+    pub fn evaluate(&self, reference: &ValueReference) -> Result<Value, SchemaError> {
+        match reference {
+            ValueReference::Value(val) => Ok(val.clone()),
+            ValueReference::Pattern(literal, _) => Ok(Value::str_from(literal.as_str())),
+            ValueReference::Reference(path) => {
+                let (name, rest) = match path.split_first() {
+                    Some((Segment::Key(name), rest)) => (name, rest),
+                    _ => return Err(SchemaError::NotImplemented("invalid variable reference".into()))
+                };
+
+                let value = self.values.get(name)
+                    .ok_or_else(|| SchemaError::NotImplemented(format!("no variable {} in scope", name)))?;
+
+                let mut found = rest.select(value, true)?;
+                match rest.has_wildcard() {
+                    true => Ok(Value::List(found)),
+                    false => Ok(found.pop().unwrap_or(Value::Null))
+                }
+            }
+        }
+    }
+
+    pub fn holds(&self, condition: &Condition) -> Result<bool, SchemaError> {
+        Ok(self.truth(condition)? == Some(true))
+    }
+
+    // TODO: Avoid the copy.
+    pub fn truth(&self, condition: &Condition) -> Result<Option<bool>, SchemaError> {
+        condition.truth(&Value::Map(self.values.clone()))
+    }
+
+    // Assign a variable, or a location within one.
+    pub fn assign(&mut self, path: &Path, value: Value) -> Result<(), SchemaError> {
+        let (name, rest) = match path.split_first() {
+            Some((Segment::Key(name), rest)) => (name.clone(), rest),
+            _ => return Err(SchemaError::NotImplemented("invalid variable reference".into()))
+        };
+
+        let assigned = match self.values.get(&name) {
+            Some(current) => rest.update(current, &mut |_| Ok(value.clone()))?,
+            None if rest.segments().is_empty() => value,
+            None => return Err(SchemaError::NotImplemented(format!("no variable {} in scope", name)))
+        };
+
+        self.values.insert(name, assigned);
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Op(Operator, Box<Expression>, Box<Expression>),
+    Ref(ValueReference),
+    // Whether a condition holds for the variables in scope, or null if that's unknown.
+    Condition(Condition),
+    Call(String, Vec<Expression>),
+    // An expression of a variable bound to each element of a list; only an argument of
+    // the map and filter calls.
+    Lambda(String, Box<Expression>)
+}
+
+// Calls to these take a list and a lambda rather than values; see Functions for the rest.
+fn is_list_form(name: &str) -> bool {
+    name == "map" || name == "filter"
+}
+
+impl Expression {
+    // {"op": [<operator>, <expression>, <expression>]}, {"condition": <condition>},
+    // {"call": [<name>, <expression>, ...]}, {"lambda": [<variable>, <expression>]}, or a
+    // value reference.
+    pub fn parse_from_value(value: Value) -> Result<Self, SchemaError> {
+        if let Ok(op_value) = value.lookup("op") {
+            Ok(Self::Op(
+                Operator::parse_from_value(op_value.index(0)?)?,
+                Box::new(Expression::parse_from_value(op_value.index(1)?)?),
+                Box::new(Expression::parse_from_value(op_value.index(2)?)?)
+            ))
+        }
+        else if let Ok(condition_value) = value.lookup("condition") {
+            Ok(Self::Condition(Condition::parse_from_value(condition_value)?))
+        }
+        else if let Ok(call_value) = value.lookup("call") {
+            let parts = call_value.elements()?;
+            let name: String = call_value.index(0)?.try_into()?;
+
+            let mut args = Vec::with_capacity(parts.len());
+            for part in parts.iter().skip(1) {
+                args.push(Expression::parse_from_value(part.clone())?);
+            }
+
+            Ok(Self::Call(name, args))
+        }
+        else if let Ok(lambda_value) = value.lookup("lambda") {
+            Ok(Self::Lambda(
+                lambda_value.index(0)?.try_into()?,
+                Box::new(Expression::parse_from_value(lambda_value.index(1)?)?)
+            ))
+        }
+        else {
+            Ok(Self::Ref(ValueReference::parse_from_value(value)?))
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        match self {
+            Self::Op(op, left, right) => Value::map_from([
+                ("op".into(), Value::List(Vec::from([op.to_value(), left.to_value(), right.to_value()])))
+            ]),
+            Self::Ref(reference) => reference.to_value(),
+            Self::Condition(condition) => Value::map_from([("condition".into(), condition.to_value())]),
+            Self::Call(name, args) => {
+                let mut parts = Vec::from([Value::Str(name.clone())]);
+                parts.extend(args.iter().map(Self::to_value));
+
+                Value::map_from([("call".into(), Value::List(parts))])
+            },
+            Self::Lambda(variable, body) => Value::map_from([
+                ("lambda".into(), Value::List(Vec::from([Value::Str(variable.clone()), body.to_value()])))
+            ])
+        }
+    }
+
+    // The type of the expression's value given the types of the variables in scope (a map
+    // type).
+    pub fn type_of(&self, scope: &Type, functions: &Functions) -> Result<Type, SchemaError> {
+        match self {
+            Self::Op(op, left, right) => {
+                op.result_type(&left.type_of(scope, functions)?, &right.type_of(scope, functions)?)
+            },
+            Self::Ref(ValueReference::Value(value)) => Ok(sample_type(value)),
+            Self::Ref(reference) => reference.lookup_type(scope),
+            Self::Condition(condition) => {
+                condition.validate(scope)?;
+
+                Ok(Type::Optional(Box::new(Type::Bool)))
+            },
+            Self::Call(name, args) if is_list_form(name) => {
+                let (list, variable, body) = match args.as_slice() {
+                    [list, Self::Lambda(variable, body)] => (list, variable, body),
+                    _ => return Err(SchemaError::InvalidArguments(Vec::new()))
+                };
+
+                let list_type = list.type_of(scope, functions)?;
+                let element = match list_type.base() {
+                    Type::List(inner) => inner.as_ref().clone(),
+                    Type::Any => Type::Any,
+                    other => return Err(SchemaError::InvalidArguments(Vec::from([other.clone()])))
+                };
+
+                let mut inner_scope = match scope.base() {
+                    Type::Map(members) => members.clone(),
+                    _ => HashMap::new()
+                };
+                inner_scope.insert(variable.clone(), element.clone());
+                let body_type = body.type_of(&Type::Map(inner_scope), functions)?;
+
+                let result = match name.as_str() {
+                    "map" => Type::List(Box::new(body_type)),
+                    _ if matches!(body_type.base(), Type::Bool | Type::Any) => Type::List(Box::new(element)),
+                    _ => return Err(SchemaError::InvalidArguments(Vec::from([list_type, body_type])))
+                };
+
+                match list_type.is_optional() {
+                    true => Ok(Type::Optional(Box::new(result))),
+                    false => Ok(result)
+                }
+            },
+            Self::Call(name, args) => {
+                let mut arg_types = Vec::with_capacity(args.len());
+                for arg in args {
+                    arg_types.push(arg.type_of(scope, functions)?);
+                }
+
+                functions.result_type(name, &arg_types)
+            },
+            Self::Lambda(_, _) => Err(SchemaError::NotImplemented("lambda outside map or filter".into()))
+        }
+    }
+
+    pub fn evaluate(&self, scope: &LogicScope) -> Result<Value, SchemaError> {
+        match self {
+            Self::Op(op, left, right) => op.evaluate(&left.evaluate(scope)?, &right.evaluate(scope)?),
+            Self::Ref(reference) => scope.evaluate(reference),
+            Self::Condition(condition) => Ok(scope.truth(condition)?.map(Value::Bool).unwrap_or(Value::Null)),
+            Self::Call(name, args) if is_list_form(name) => {
+                let (list, variable, body) = match args.as_slice() {
+                    [list, Self::Lambda(variable, body)] => (list, variable, body),
+                    _ => return Err(SchemaError::InvalidArguments(Vec::new()))
+                };
+
+                let elements = match list.evaluate(scope)? {
+                    Value::Null => return Ok(Value::Null),
+                    Value::List(elements) => elements,
+                    other => return Err(SchemaError::InvalidArguments(Vec::from([sample_type(&other)])))
+                };
+
+                let mut inner_scope = scope.clone();
+                let mut results = Vec::with_capacity(elements.len());
+                for element in elements {
+                    inner_scope.bind(variable.clone(), element.clone());
+
+                    match (name.as_str(), body.evaluate(&inner_scope)?) {
+                        ("map", result) => results.push(result),
+                        (_, Value::Bool(true)) => results.push(element),
+                        (_, Value::Bool(false) | Value::Null) => {},
+                        (_, other) => return Err(SchemaError::InvalidArguments(Vec::from([sample_type(&other)])))
+                    }
+                }
+
+                Ok(Value::List(results))
+            },
+            Self::Call(name, args) => {
+                let mut values = Vec::with_capacity(args.len());
+                for arg in args {
+                    values.push(arg.evaluate(scope)?);
+                }
+
+                scope.functions().call(name, &values)
+            },
+            Self::Lambda(_, _) => Err(SchemaError::NotImplemented("lambda outside map or filter".into()))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    // Bind the value of an expression to a variable, or to a location within one.
+    Assignment(Path, Expression),
+    Branch(LogicTree)
+}
+
+impl Statement {
+    // {"assign": [<path>, <expression>]} or a logic tree.
+    pub fn parse_from_value(value: Value) -> Result<Self, SchemaError> {
+        match value.lookup("assign") {
+            Ok(assign_value) => {
+                let path: String = assign_value.index(0)?.try_into()?;
+
+                Ok(Self::Assignment(Path::parse(&path)?, Expression::parse_from_value(assign_value.index(1)?)?))
+            },
+            Err(_) => Ok(Self::Branch(LogicTree::parse_from_value(value)?))
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        match self {
+            Self::Assignment(path, expression) => Value::map_from([
+                ("assign".into(), Value::List(Vec::from([Value::Str(path.to_string()), expression.to_value()])))
+            ]),
+            Self::Branch(tree) => tree.to_value()
+        }
+    }
+
+    // Check the statement against the types of the variables in scope, which it may add to.
+    fn validate(&self, scope: &mut HashMap<String, Type>, functions: &Functions) -> Result<(), SchemaError> {
+        match self {
+            Self::Assignment(path, expression) => {
+                let assigned = expression.type_of(&Type::Map(scope.clone()), functions)?;
+
+                match path.split_first() {
+                    Some((Segment::Key(name), rest)) if rest.segments().is_empty() => {
+                        if let Some(current) = scope.get(name) {
+                            if !current.comparable_with(&assigned) {
+                                return Err(SchemaError::InvalidType(Box::new(current.clone()), Box::new(assigned)));
+                            }
+                        }
+
+                        scope.insert(name.clone(), assigned);
+                    },
+                    Some((Segment::Key(_), _)) => {
+                        let current = Type::Map(scope.clone()).lookup(path)?;
+
+                        if !current.comparable_with(&assigned) {
+                            return Err(SchemaError::InvalidType(Box::new(current), Box::new(assigned)));
+                        }
+                    },
+                    _ => return Err(SchemaError::NotImplemented("invalid variable reference".into()))
+                }
+
+                Ok(())
+            },
+            Self::Branch(tree) => tree.validate_in(scope, functions)
+        }
+    }
+
+    fn execute(&self, scope: &mut LogicScope) -> Result<(), SchemaError> {
+        match self {
+            Self::Assignment(path, expression) => {
+                let value = expression.evaluate(scope)?;
+
+                scope.assign(path, value)
+            },
+            Self::Branch(tree) => tree.execute(scope)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LogicTree {
+    // The first tree if the condition holds, otherwise the second if there is one.
+    ConditionalBranch(Condition, Box<LogicTree>, Option<Box<LogicTree>>),
+    Block(Vec<Statement>)
+}
+
+impl LogicTree {
+    // {"if": [<condition>, <tree>, <tree>?]} or {"block": [<statement>, ...]}.
+    pub fn parse_from_value(value: Value) -> Result<Self, SchemaError> {
+        if let Ok(if_value) = value.lookup("if") {
+            let otherwise = match if_value.index(2) {
+                Ok(else_value) => Some(Box::new(LogicTree::parse_from_value(else_value)?)),
+                Err(_) => None
+            };
+
+            Ok(Self::ConditionalBranch(
+                Condition::parse_from_value(if_value.index(0)?)?,
+                Box::new(LogicTree::parse_from_value(if_value.index(1)?)?),
+                otherwise
+            ))
+        }
+        else if let Ok(block_value) = value.lookup("block") {
+            let mut statements = Vec::new();
+            for element in block_value.elements()? {
+                statements.push(Statement::parse_from_value(element.clone())?);
+            }
+
+            Ok(Self::Block(statements))
+        }
+        else {
+            Err(SchemaError::NotImplemented("invalid logic tree".into()))
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        match self {
+            Self::ConditionalBranch(condition, then, otherwise) => {
+                let mut parts = Vec::from([condition.to_value(), then.to_value()]);
+                if let Some(otherwise) = otherwise {
+                    parts.push(otherwise.to_value());
+                }
+
+                Value::map_from([("if".into(), Value::List(parts))])
+            },
+            Self::Block(statements) => Value::map_from([
+                ("block".into(), Value::List(statements.iter().map(Statement::to_value).collect()))
+            ])
+        }
+    }
+
+    // Check the tree against the types of the variables in scope (a map type) and the
+    // functions it may call.
+    pub fn validate(&self, scope: &Type, functions: &Functions) -> Result<(), SchemaError> {
+        let mut variables = match scope.base() {
+            Type::Map(members) => members.clone(),
+            other => return Err(SchemaError::InvalidType(Box::new(Type::Map(HashMap::new())), Box::new(other.clone())))
+        };
+
+        self.validate_in(&mut variables, functions)
+    }
+
+    fn validate_in(&self, scope: &mut HashMap<String, Type>, functions: &Functions) -> Result<(), SchemaError> {
+        match self {
+            Self::ConditionalBranch(condition, then, otherwise) => {
+                condition.validate(&Type::Map(scope.clone()))?;
+
+                let mut then_scope = scope.clone();
+                then.validate_in(&mut then_scope, functions)?;
+
+                let mut else_scope = scope.clone();
+                if let Some(otherwise) = otherwise {
+                    otherwise.validate_in(&mut else_scope, functions)?;
+                }
+
+                // Variables introduced by only one branch, or with different types, may be
+                // absent afterwards.
+                for (name, typ) in then_scope.iter().chain(else_scope.iter()) {
+                    if scope.contains_key(name) {
+                        continue;
+                    }
+
+                    let agreed = then_scope.get(name) == else_scope.get(name);
+                    scope.insert(name.clone(), match agreed {
+                        true => typ.clone(),
+                        false if then_scope.get(name).zip(else_scope.get(name)).is_some() => Type::Any,
+                        false => Type::Optional(Box::new(typ.clone()))
+                    });
+                }
+
+                Ok(())
+            },
+            Self::Block(statements) => {
+                for statement in statements {
+                    statement.validate(scope, functions)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    pub fn execute(&self, scope: &mut LogicScope) -> Result<(), SchemaError> {
+        match self {
+            Self::ConditionalBranch(condition, then, otherwise) => {
+                match (scope.holds(condition)?, otherwise) {
+                    (true, _) => then.execute(scope),
+                    (false, Some(otherwise)) => otherwise.execute(scope),
+                    (false, None) => Ok(())
+                }
+            },
+            Self::Block(statements) => {
+                for statement in statements {
+                    statement.execute(scope)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operators() {
+        assert_eq!(Operator::Sub.evaluate(&Value::Int32(1), &Value::Uint32(3)).unwrap(), Value::Int64(-2));
+        assert_eq!(Operator::Mul.evaluate(&Value::Uint32(4), &Value::Uint32(3)).unwrap(), Value::Uint32(12));
+        assert_eq!(Operator::Div.evaluate(&Value::Int64(7), &Value::Float64(2.0)).unwrap(), Value::Float64(3.5));
+        assert_eq!(
+            Operator::Add.evaluate(&Value::Decimal(Decimal::new(15, 1)), &Value::Uint32(1)).unwrap(),
+            Value::Decimal(Decimal::new(25, 1))
+        );
+        assert!(Operator::Add.evaluate(&Value::Uint32(u32::MAX), &Value::Uint32(1)).is_err());
+        assert!(Operator::Div.evaluate(&Value::Int32(1), &Value::Int32(0)).is_err());
+        assert!(Operator::Div.evaluate(&Value::Float64(1.0), &Value::Uint32(0)).is_err());
+        assert!(Operator::Div.evaluate(&Value::Float64(0.0), &Value::Float64(-0.0)).is_err());
+        assert!(Operator::Mul.evaluate(&Value::Float64(f64::MAX), &Value::Uint32(2)).is_err());
+        assert!(Operator::Add.evaluate(&Value::Bool(true), &Value::Uint32(1)).is_err());
+        assert_eq!(Operator::Add.evaluate(&Value::Null, &Value::Uint32(1)).unwrap(), Value::Null);
+
+        assert_eq!(Operator::And.evaluate(&Value::Null, &Value::Bool(false)).unwrap(), Value::Bool(false));
+        assert_eq!(Operator::And.evaluate(&Value::Null, &Value::Bool(true)).unwrap(), Value::Null);
+        assert_eq!(Operator::Or.evaluate(&Value::Bool(true), &Value::Null).unwrap(), Value::Bool(true));
+        assert_eq!(Operator::Or.evaluate(&Value::Bool(false), &Value::Bool(false)).unwrap(), Value::Bool(false));
+
+        assert_eq!(Operator::Sub.result_type(&Type::Int32, &Type::Uint32).unwrap(), Type::Int64);
+        assert!(Operator::And.result_type(&Type::Bool, &Type::String).is_err());
+    }
+
+    #[test]
+    fn execute_tree() {
+        let tree = LogicTree::parse_from_value(Value::map_from([
+            ("block".into(), Value::List(Vec::from([
+                Value::map_from([("assign".into(), Value::List(Vec::from([
+                    Value::str_from("total"),
+                    Value::map_from([("op".into(), Value::List(Vec::from([
+                        Value::str_from("mul"),
+                        Value::map_from([("ref".into(), Value::str_from("order.count"))]),
+                        Value::map_from([("ref".into(), Value::str_from("order.price"))])
+                    ])))])
+                ])))]),
+                Value::map_from([("if".into(), Value::List(Vec::from([
+                    Condition::parse_filter("total > 100").unwrap().to_value(),
+                    Value::map_from([("block".into(), Value::List(Vec::from([
+                        Value::map_from([("assign".into(), Value::List(Vec::from([
+                            Value::str_from("order.bulk"),
+                            Value::map_from([("value".into(), Value::Bool(true))])
+                        ])))])
+                    ])))]),
+                    Value::map_from([("block".into(), Value::List(Vec::new()))])
+                ])))])
+            ])))
+        ])).unwrap();
+
+        assert_eq!(LogicTree::parse_from_value(tree.to_value()).unwrap(), tree);
+
+        let mut scope = LogicScope::new();
+        scope.bind("order", Value::map_from([
+            ("count".into(), Value::Uint32(30)),
+            ("price".into(), Value::Uint32(4)),
+            ("bulk".into(), Value::Bool(false))
+        ]));
+
+        tree.validate(&scope.types(), scope.functions()).unwrap();
+        tree.execute(&mut scope).unwrap();
+
+        assert_eq!(scope.get("total"), Some(&Value::Uint32(120)));
+        assert_eq!(scope.get("order").unwrap().lookup("bulk").unwrap(), Value::Bool(true));
+
+        let mut strings = LogicScope::new();
+        strings.bind("order", Value::map_from([
+            ("count".into(), Value::str_from("30")),
+            ("price".into(), Value::Uint32(4)),
+            ("bulk".into(), Value::Bool(false))
+        ]));
+        assert!(tree.validate(&strings.types(), strings.functions()).is_err());
+
+        let names = Expression::parse_from_value(Value::map_from([("call".into(), Value::List(Vec::from([
+            Value::str_from("map"),
+            Value::map_from([("call".into(), Value::List(Vec::from([
+                Value::str_from("filter"),
+                Value::map_from([("ref".into(), Value::str_from("people"))]),
+                Value::map_from([("lambda".into(), Value::List(Vec::from([
+                    Value::str_from("person"),
+                    Value::map_from([("condition".into(), Condition::parse_filter("person.age >= 18").unwrap().to_value())])
+                ])))])
+            ])))]),
+            Value::map_from([("lambda".into(), Value::List(Vec::from([
+                Value::str_from("person"),
+                Value::map_from([("call".into(), Value::List(Vec::from([
+                    Value::str_from("upper"),
+                    Value::map_from([("ref".into(), Value::str_from("person.name"))])
+                ])))])
+            ])))])
+        ])))])).unwrap();
+
+        let person = |name: &str, age: u32| Value::map_from([
+            ("name".into(), Value::str_from(name)), ("age".into(), Value::Uint32(age))
+        ]);
+        let mut people = LogicScope::new();
+        people.bind("people", Value::List(Vec::from([person("ada", 36), person("tim", 9)])));
+
+        assert_eq!(
+            names.type_of(&people.types(), people.functions()).unwrap(),
+            Type::List(Box::new(Type::String))
+        );
+        assert_eq!(names.evaluate(&people).unwrap(), Value::List(Vec::from([Value::str_from("ADA")])));
+        assert_eq!(Expression::parse_from_value(names.to_value()).unwrap(), names);
+
+        // Conditions on nulls are unknown rather than false.
+        let adult = Expression::Condition(Condition::parse_filter("age >= 18").unwrap());
+        let mut unknown = LogicScope::new();
+        unknown.bind("age", Value::Null);
+        assert_eq!(adult.evaluate(&unknown).unwrap(), Value::Null);
+        assert_eq!(adult.type_of(&unknown.types(), unknown.functions()).unwrap(), Type::Optional(Box::new(Type::Bool)));
+        unknown.bind("age", Value::Uint32(9));
+        assert_eq!(adult.evaluate(&unknown).unwrap(), Value::Bool(false));
+    }
+}
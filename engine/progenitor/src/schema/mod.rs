@@ -4,10 +4,37 @@
 // TODO: Support masks when validating indirects; e.g. references / comparators supported by a database.
 mod errors;
 mod primitives;
+mod scalars;
+mod definition;
+mod constraint;
+mod named;
+mod generated;
+mod json_schema;
+mod validation;
+mod path;
 mod expr;
+mod filter;
+mod logic;
+mod functions;
+mod stdlib;
+mod convert;
 mod mutation;
+mod patch;
+mod diff;
+mod canonical;
 
 pub use errors::SchemaError;
 pub use primitives::{Type, Value};
+pub use scalars::{Timestamp, Uuid, Decimal};
+pub use constraint::{Constraint, Format, Pattern};
+pub use named::TypeRef;
+pub use generated::Generator;
+pub use validation::{ValidationReport, Violation, ValidationMode};
+pub use path::{Path, Segment};
+pub(crate) use scalars::encode_base64;
 pub use expr::{Comparator, Conjunctive, Condition, ValueReference};
+pub use logic::{Operator, Expression, Statement, LogicTree, LogicScope};
+pub use functions::{Functions, NativeFunction, SignatureFn, CallFn};
+pub use convert::{FromValue, IntoValue, Typed};
 pub use mutation::Mutation;
+pub use canonical::ContentHash;
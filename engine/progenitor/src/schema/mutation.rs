@@ -1,77 +1,421 @@
 // Indirect representation of mutations to indirectly represented data.
+//
+// Every mutation is applied at each location its path selects. Unset and Insert act on
+// the parent of the last segment, a member of a map or an index in a list, so the last
+// segment of their path can't be a wildcard. Copy and Move take a single value, so
+// their paths can't have wildcards at all.
 use super::errors::SchemaError;
 use super::primitives::{Type, Value};
+use super::path::{Path, Segment};
+use super::expr::{Condition, tag_literal, untag_literal};
+use super::logic::Operator;
+use super::validation::ValidationMode;
 
-// TODO: Needs more variants.
+#[derive(Debug, Clone, PartialEq)]
 pub enum Mutation {
-    Set(String, Value),
+    // Set every value at the path, adding it if the last segment names an absent member.
+    Set(Path, Value),
+    // Remove a member of a map (doing nothing if it's absent) or an element of a list.
+    Unset(Path),
+    // Add to or subtract from a number, keeping its variant where the result fits.
+    Increment(Path, Value),
+    Decrement(Path, Value),
+    // Add an element to the end or start of a list, which may be absent.
+    Append(Path, Value),
+    Prepend(Path, Value),
+    // Insert an element into a list before the index the path ends in, or at the end if
+    // that's its length or -, or add (or replace) a member of a map.
+    Insert(Path, Value),
+    // Insert the value at the first path at the second, as Insert does.
+    Copy(Path, Path),
+    // Unset the value at the first path, then insert it at the second.
+    Move(Path, Path),
+    // Remove the elements of a list for which the condition holds.
+    RemoveWhere(Path, Condition),
+    // Merge a map into the map at the path, member by member: nested maps are merged and
+    // anything else replaces what was there.
+    Merge(Path, Value),
+    // Fail unless the condition holds for the value, e.g. to guard the rest of a bulk.
+    Require(Condition),
     Bulk(Vec<Mutation>)
 }
 
-// TODO: Avoid clone!
-fn execute_mutation_via_lookup(target: &Value, lookup: &str, to: Value) -> Result<Value, SchemaError> {
-    // TODO: Rusty way to do this is probably with an iterator.
-    let (this_lookup, next_lookup) = match lookup.split_once(".") {
-        Some((a, b)) => (a, Some(b)),
-        None => (lookup, None)
-    };
-
-    match target {
-        Value::List(elements) => {
-            match this_lookup.parse::<usize>() {
-                Ok(i) => {
-                    if i >= elements.len() {
-                        return Err(SchemaError::InvalidIndex(Type::try_from(target).ok(), Some(i)));
-                    }
+// The list index a segment ends with for Insert, where - is the end.
+fn insert_index(segment: &Segment, elements: &[Value]) -> Option<usize> {
+    match segment {
+        Segment::Key(key) if key == "-" => Some(elements.len()),
+        _ => segment.as_index()
+    }
+}
 
-                    match next_lookup {
-                        Some(next) => { 
-                            let mut updated = elements.clone();
-                            updated[i] = execute_mutation_via_lookup(target, next, to)?;
+fn parse_path(value: Value) -> Result<Path, SchemaError> {
+    Path::parse(&String::try_from(value)?)
+}
 
-                            Ok(Value::List(updated))
-                        },
-                        None => {
-                            Err(SchemaError::NotImplemented("Object key mutation of array element".into()))
-                        }
-                    }
-                },
-                Err(_) => Err(SchemaError::InvalidIndex(Type::try_from(target).ok(), None))
+fn invalid(message: &str, path: &Path) -> SchemaError {
+    SchemaError::InvalidMutation(format!("{} at {:?}", message, path.to_string()))
+}
+
+fn merge(target: &Value, patch: &Value) -> Value {
+    match (target, patch) {
+        (Value::Map(members), Value::Map(patch_members)) => {
+            let mut merged = members.clone();
+            for (key, patch_member) in patch_members.iter() {
+                let member = merged.get(key).map_or_else(|| patch_member.clone(), |member| merge(member, patch_member));
+
+                merged.insert(key.clone(), member);
             }
+
+            Value::Map(merged)
         },
-        Value::Map(members) => {
-            match members.get(this_lookup) {
-                Some(inner) => {
-                    let mut updated = members.clone();
-
-                    updated.insert(
-                        this_lookup.to_string(),
-                        match next_lookup {
-                            Some(next) => {
-                                execute_mutation_via_lookup(inner, next, to)?
-                            },
-                            None => to,
-                        }
-                    );
+        _ => patch.clone()
+    }
+}
 
-                    Ok(Value::Map(updated))
-                },
-                None => {
-                    Err(SchemaError::InvalidLookup(Type::try_from(target).ok(), this_lookup.into()))
-                }
+// The type of a value that may be inserted at a path.
+fn insert_type(typ: &Type, path: &Path) -> Result<Type, SchemaError> {
+    let (parent, last) = path.split_last().ok_or_else(|| invalid("nowhere to insert", path))?;
+
+    match (typ.lookup(&parent)?.base(), last) {
+        (Type::Any, _) => Ok(Type::Any),
+        (Type::List(_), Segment::Key(key)) if key == "-" => element_type(typ, &parent),
+        (Type::List(_), _) if last.as_index().is_some() => element_type(typ, &parent),
+        (Type::Map(_) | Type::Union(_, _), Segment::Key(_)) => typ.lookup(path),
+        _ => Err(invalid("nowhere to insert", path))
+    }
+}
+
+// The single value at a path.
+fn lookup_one(target: &Value, path: &Path) -> Result<Value, SchemaError> {
+    match path.has_wildcard() {
+        true => Err(invalid("can't take a value at a wildcard", path)),
+        false => target.lookup(path)
+    }
+}
+
+fn validate_merge(typ: &Type, patch: &Value) -> Result<(), SchemaError> {
+    match (typ.base(), patch) {
+        (Type::Any, _) => Ok(()),
+        (Type::Map(_) | Type::Union(_, _), Value::Map(patch_members)) => {
+            for (key, patch_member) in patch_members.iter() {
+                validate_merge(&typ.member(key)?, patch_member)?;
             }
+
+            Ok(())
         },
-        _ => Ok(to)
+        _ => typ.validate(patch)
+    }
+}
+
+// The element type of the list type at a path.
+fn element_type(typ: &Type, path: &Path) -> Result<Type, SchemaError> {
+    let list_t = typ.lookup(path)?;
+
+    match list_t.base() {
+        Type::List(inner) => Ok(inner.as_ref().clone()),
+        Type::Any => Ok(Type::Any),
+        _ => Err(invalid("not a list", path))
     }
 }
 
 impl Mutation {
+    // {"set": [<path>, <value>]}, {"unset": <path>}, {"increment": [<path>, <number>]},
+    // {"decrement": [<path>, <number>]}, {"append": [<path>, <value>]},
+    // {"prepend": [<path>, <value>]}, {"insert": [<path>, <value>]},
+    // {"remove_where": [<path>, <condition>]}, {"merge": [<path>, <map>]},
+    // {"copy": [<path>, <path>]}, {"move": [<path>, <path>]}, {"require": <condition>}, or
+    // {"bulk": [<mutation>, ...]}. Values without a JSON form are tagged with their type as
+    // in conditions, e.g. {"set": [<path>, "1.50", "decimal"]}.
+    pub fn parse_from_value(value: Value) -> Result<Self, SchemaError> {
+        let pair = |key: &str| -> Result<Option<(Path, Value)>, SchemaError> {
+            let pair_value = match value.lookup(key) {
+                Ok(pair_value) => pair_value,
+                Err(_) => return Ok(None)
+            };
+
+            let second = match pair_value.index(2) {
+                Ok(name) => untag_literal(pair_value.index(1)?, name)?,
+                Err(_) => pair_value.index(1)?
+            };
+
+            Ok(Some((parse_path(pair_value.index(0)?)?, second)))
+        };
+
+        if let Some((path, value)) = pair("set")? {
+            Ok(Self::Set(path, value))
+        }
+        else if let Ok(path_value) = value.lookup("unset") {
+            Ok(Self::Unset(parse_path(path_value)?))
+        }
+        else if let Some((path, by)) = pair("increment")? {
+            Ok(Self::Increment(path, by))
+        }
+        else if let Some((path, by)) = pair("decrement")? {
+            Ok(Self::Decrement(path, by))
+        }
+        else if let Some((path, element)) = pair("append")? {
+            Ok(Self::Append(path, element))
+        }
+        else if let Some((path, element)) = pair("prepend")? {
+            Ok(Self::Prepend(path, element))
+        }
+        else if let Some((path, element)) = pair("insert")? {
+            Ok(Self::Insert(path, element))
+        }
+        else if let Some((path, condition_value)) = pair("remove_where")? {
+            Ok(Self::RemoveWhere(path, Condition::parse_from_value(condition_value)?))
+        }
+        else if let Some((path, patch)) = pair("merge")? {
+            Ok(Self::Merge(path, patch))
+        }
+        else if let Some((from, to)) = pair("copy")? {
+            Ok(Self::Copy(from, parse_path(to)?))
+        }
+        else if let Some((from, to)) = pair("move")? {
+            Ok(Self::Move(from, parse_path(to)?))
+        }
+        else if let Ok(condition_value) = value.lookup("require") {
+            Ok(Self::Require(Condition::parse_from_value(condition_value)?))
+        }
+        else if let Ok(bulk_value) = value.lookup("bulk") {
+            let mut parts = Vec::new();
+            for element in bulk_value.elements()? {
+                parts.push(Mutation::parse_from_value(element.clone())?);
+            }
+
+            Ok(Self::Bulk(parts))
+        }
+        else {
+            Err(SchemaError::NotImplemented("invalid mutation".into()))
+        }
+    }
+
+    // The inverse of parse_from_value.
+    pub fn to_value(&self) -> Value {
+        let pair = |key: &str, path: &Path, value: Value| {
+            let elements = match tag_literal(&value) {
+                (encoded, Some(name)) => Vec::from([Value::Str(path.to_string()), encoded, name]),
+                (value, None) => Vec::from([Value::Str(path.to_string()), value])
+            };
+
+            Value::map_from([(key.into(), Value::List(elements))])
+        };
+
+        match self {
+            Self::Set(path, value) => pair("set", path, value.clone()),
+            Self::Unset(path) => Value::map_from([("unset".into(), Value::Str(path.to_string()))]),
+            Self::Increment(path, by) => pair("increment", path, by.clone()),
+            Self::Decrement(path, by) => pair("decrement", path, by.clone()),
+            Self::Append(path, element) => pair("append", path, element.clone()),
+            Self::Prepend(path, element) => pair("prepend", path, element.clone()),
+            Self::Insert(path, element) => pair("insert", path, element.clone()),
+            Self::RemoveWhere(path, condition) => pair("remove_where", path, condition.to_value()),
+            Self::Merge(path, patch) => pair("merge", path, patch.clone()),
+            Self::Copy(from, to) => pair("copy", from, Value::Str(to.to_string())),
+            Self::Move(from, to) => pair("move", from, Value::Str(to.to_string())),
+            Self::Require(condition) => Value::map_from([("require".into(), condition.to_value())]),
+            Self::Bulk(parts) => Value::map_from([
+                ("bulk".into(), Value::List(parts.iter().map(Self::to_value).collect()))
+            ])
+        }
+    }
+
+    // Check the mutation against the type of the values it will be applied to, so it can't
+    // produce one that isn't of that type. Constraints on numbers that are incremented or
+    // decremented can only be checked once the result is known.
+    pub fn validate(&self, typ: &Type) -> Result<(), SchemaError> {
+        match self {
+            Self::Set(path, value) => typ.lookup(path)?.validate(value),
+            Self::Unset(path) => {
+                let (parent, last) = path.split_last().ok_or_else(|| invalid("can't unset the whole value", path))?;
+                if *last == Segment::Any {
+                    return Err(invalid("can't unset a wildcard", path));
+                }
+
+                match typ.lookup(&parent)?.base() {
+                    Type::List(_) | Type::Any => Ok(()),
+                    _ if typ.lookup(path)?.is_optional() => Ok(()),
+                    _ => Err(invalid("can't unset a required member", path))
+                }
+            },
+            Self::Increment(path, by) | Self::Decrement(path, by) => {
+                let target_t = typ.lookup(path)?;
+                let by_t = Type::try_from(by)?;
+
+                // A fraction would leave an integer target holding a float.
+                let fractional = matches!(by_t.base(), Type::Float64 | Type::Decimal);
+
+                match (target_t.base(), by_t.is_numeric()) {
+                    (Type::Any, true) => Ok(()),
+                    (Type::Float64 | Type::Decimal, true) => Ok(()),
+                    (target_base, true) if target_base.is_numeric() && !fractional => Ok(()),
+                    (target_base, true) if target_base.is_numeric() => Err(invalid("not an integer", path)),
+                    _ => Err(invalid("not a number", path))
+                }
+            },
+            Self::Append(path, element) | Self::Prepend(path, element) => element_type(typ, path)?.validate(element),
+            Self::Insert(path, element) => insert_type(typ, path)?.validate(element),
+            Self::Copy(from, to) | Self::Move(from, to) => {
+                if let Self::Move(_, _) = self {
+                    Self::Unset(from.clone()).validate(typ)?;
+                }
+
+                let (from_t, to_t) = (typ.lookup(from)?, insert_type(typ, to)?);
+                match (from_t.base(), to_t.base()) {
+                    (_, Type::Any) => Ok(()),
+                    (a, b) if a == b && (to_t.is_optional() || !from_t.is_optional()) => Ok(()),
+                    _ => Err(SchemaError::InvalidType(Box::new(to_t), Box::new(from_t)))
+                }
+            },
+            Self::Require(condition) => condition.validate(typ),
+            Self::RemoveWhere(path, condition) => condition.validate(&element_type(typ, path)?),
+            Self::Merge(path, patch) => validate_merge(&typ.lookup(path)?, patch),
+            Self::Bulk(parts) => {
+                for part in parts {
+                    part.validate(typ)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+
     pub fn execute(&self, target: &Value) -> Result<Value, SchemaError> {
         match self {
-            Mutation::Set(lookup, value) => {
-                execute_mutation_via_lookup(target, lookup, value.clone())
+            Self::Set(path, value) => {
+                path.update(target, &mut |_| Ok(value.clone()))
+            },
+            Self::Unset(path) => {
+                let (parent, last) = path.split_last().ok_or_else(|| invalid("can't unset the whole value", path))?;
+
+                // Nothing to do if the parent is absent, which update would add as null.
+                if parent.select(target, false).is_err() {
+                    return Ok(target.clone());
+                }
+
+                parent.update(target, &mut |parent_value| match (parent_value, last) {
+                    (Value::Map(members), Segment::Key(key)) => {
+                        let mut updated = members.clone();
+                        updated.remove(key);
+
+                        Ok(Value::Map(updated))
+                    },
+                    (Value::List(elements), _) if last.as_index().is_some_and(|i| i < elements.len()) => {
+                        let mut updated = elements.clone();
+                        updated.remove(last.as_index().unwrap_or_default());
+
+                        Ok(Value::List(updated))
+                    },
+                    _ => Err(invalid("nothing to unset", path))
+                })
+            },
+            Self::Increment(path, by) | Self::Decrement(path, by) => {
+                let op = match self {
+                    Self::Increment(_, _) => Operator::Add,
+                    _ => Operator::Sub
+                };
+
+                path.update(target, &mut |current| {
+                    if current.as_integer().is_none() && !matches!(current, Value::Float64(_) | Value::Decimal(_)) {
+                        return Err(invalid("not a number", path));
+                    }
+
+                    let result = op.evaluate(current, by)?;
+                    let current_t = Type::try_from(current)?;
+
+                    // Keep the variant, e.g. a Uint32 incremented by an Int32 stays a Uint32.
+                    current_t.normalize(&result, ValidationMode::Coerce)
+                        .map_err(|_| invalid(&format!("{:?} doesn't fit {:?}", result, current_t), path))
+                })
+            },
+            Self::Append(path, element) | Self::Prepend(path, element) => {
+                path.update(target, &mut |current| {
+                    let mut elements = match current {
+                        Value::List(elements) => elements.clone(),
+                        Value::Null => Vec::new(),
+                        _ => return Err(invalid("not a list", path))
+                    };
+
+                    match self {
+                        Self::Append(_, _) => elements.push(element.clone()),
+                        _ => elements.insert(0, element.clone())
+                    }
+
+                    Ok(Value::List(elements))
+                })
+            },
+            Self::Insert(path, element) => {
+                let (parent, last) = path.split_last().ok_or_else(|| invalid("nowhere to insert", path))?;
+
+                parent.update(target, &mut |current| match (current, last) {
+                    (Value::List(elements), _) => {
+                        let index = insert_index(last, elements)
+                            .filter(|i| *i <= elements.len())
+                            .ok_or_else(|| invalid("no index to insert at", path))?;
+
+                        let mut updated = elements.clone();
+                        updated.insert(index, element.clone());
+
+                        Ok(Value::List(updated))
+                    },
+                    (Value::Map(members), Segment::Key(key)) => {
+                        let mut updated = members.clone();
+                        updated.insert(key.clone(), element.clone());
+
+                        Ok(Value::Map(updated))
+                    },
+                    _ => Err(invalid("nowhere to insert", path))
+                })
+            },
+            Self::Copy(from, to) => {
+                Self::Insert(to.clone(), lookup_one(target, from)?).execute(target)
+            },
+            Self::Move(from, to) => {
+                // A value can't be moved into itself.
+                if to.segments().len() > from.segments().len() && to.segments().starts_with(from.segments()) {
+                    return Err(invalid("can't move a value into itself", to));
+                }
+
+                let value = lookup_one(target, from)?;
+
+                Self::Insert(to.clone(), value).execute(&Self::Unset(from.clone()).execute(target)?)
+            },
+            Self::Require(condition) => match condition.evaluate(target)? {
+                true => Ok(target.clone()),
+                // Not every condition can be written as a filter, e.g. one on the root.
+                false => Err(SchemaError::InvalidMutation(format!(
+                    "{} doesn't hold", condition.to_filter().unwrap_or_else(|_| format!("{:?}", condition.to_value()))
+                )))
+            },
+            Self::RemoveWhere(path, condition) => {
+                path.update(target, &mut |current| {
+                    let elements = match current {
+                        Value::List(elements) => elements,
+                        Value::Null => return Ok(Value::Null),
+                        _ => return Err(invalid("not a list", path))
+                    };
+
+                    let mut kept = Vec::with_capacity(elements.len());
+                    for element in elements.iter() {
+                        if !condition.evaluate(element)? {
+                            kept.push(element.clone());
+                        }
+                    }
+
+                    Ok(Value::List(kept))
+                })
             },
-            Mutation::Bulk(parts) => {
+            Self::Merge(path, patch) => {
+                if !matches!(patch, Value::Map(_)) {
+                    return Err(invalid("can only merge a map", path));
+                }
+
+                path.update(target, &mut |current| match current {
+                    Value::Map(_) | Value::Null => Ok(merge(current, patch)),
+                    _ => Err(invalid("not a map", path))
+                })
+            },
+            Self::Bulk(parts) => {
                 let mut updated = target.clone();
 
                 for mutation in parts {
@@ -84,4 +428,183 @@ impl Mutation {
     }
 }
 
-// TODO: High-prio for tests.
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use super::super::expr::{Comparator, ValueReference};
+
+    fn path(src: &str) -> Path {
+        Path::parse(src).unwrap()
+    }
+
+    #[test]
+    fn set() {
+        let target = Value::map_from([
+            ("name".into(), Value::str_from("a")),
+            ("grid".into(), Value::List(Vec::from([
+                Value::List(Vec::from([Value::Uint32(1), Value::Uint32(2)])),
+                Value::List(Vec::from([Value::Uint32(3)]))
+            ])))
+        ]);
+
+        let updated = Mutation::Bulk(Vec::from([
+            Mutation::Set(path("name"), Value::str_from("b")),
+            Mutation::Set(path("grid[0][1]"), Value::Uint32(20)),
+            Mutation::Set(path("grid.1.0"), Value::Uint32(30)),
+            Mutation::Set(path("added"), Value::Bool(true))
+        ])).execute(&target).unwrap();
+
+        assert_eq!(updated, Value::map_from([
+            ("name".into(), Value::str_from("b")),
+            ("grid".into(), Value::List(Vec::from([
+                Value::List(Vec::from([Value::Uint32(1), Value::Uint32(20)])),
+                Value::List(Vec::from([Value::Uint32(30)]))
+            ]))),
+            ("added".into(), Value::Bool(true))
+        ]));
+
+        let every = Mutation::Set(path("grid[*][0]"), Value::Uint32(0)).execute(&target).unwrap();
+        assert_eq!(every.lookup(path("grid[*][0]")).unwrap(), Value::List(Vec::from([
+            Value::Uint32(0), Value::Uint32(0)
+        ])));
+
+        assert!(Mutation::Set(path("grid[2][0]"), Value::Null).execute(&target).is_err());
+        assert!(Mutation::Set(path("missing.inner"), Value::Null).execute(&target).is_err());
+        assert!(Mutation::Set(path("name.inner"), Value::Null).execute(&target).is_err());
+    }
+
+    // A condition on a list element itself.
+    fn element(comparator: Comparator, right: Value) -> Condition {
+        Condition::Comparison(comparator, ValueReference::Reference(Path::root()), right.into())
+    }
+
+    fn counts() -> Value {
+        Value::map_from([
+            ("visits".into(), Value::Uint32(3)),
+            ("tags".into(), Value::List(Vec::from([Value::str_from("a"), Value::str_from("b")]))),
+            ("meta".into(), Value::map_from([
+                ("owner".into(), Value::str_from("ada")),
+                ("limits".into(), Value::map_from([("daily".into(), Value::Uint32(5))]))
+            ]))
+        ])
+    }
+
+    fn counts_type() -> Type {
+        Type::Map(HashMap::from([
+            ("visits".into(), Type::Uint32),
+            ("note".into(), Type::Optional(Box::new(Type::String))),
+            ("tags".into(), Type::List(Box::new(Type::String))),
+            ("meta".into(), Type::Map(HashMap::from([
+                ("owner".into(), Type::String),
+                ("limits".into(), Type::Map(HashMap::from([("daily".into(), Type::Uint32)])))
+            ])))
+        ]))
+    }
+
+    #[test]
+    fn edit() {
+        let strs = |strs: &[&str]| Value::List(strs.iter().map(|s| Value::str_from(*s)).collect());
+
+        let updated = Mutation::Bulk(Vec::from([
+            Mutation::Increment(path("visits"), Value::Int32(2)),
+            Mutation::Append(path("tags"), Value::str_from("c")),
+            Mutation::Prepend(path("tags"), Value::str_from("z")),
+            Mutation::Insert(path("tags[1]"), Value::str_from("y")),
+            Mutation::Unset(path("tags.2")),
+            Mutation::Merge(path("meta"), Value::map_from([
+                ("limits".into(), Value::map_from([("monthly".into(), Value::Uint32(50))]))
+            ])),
+            Mutation::Unset(path("meta.owner")),
+            Mutation::Unset(path("absent"))
+        ])).execute(&counts()).unwrap();
+
+        assert_eq!(updated, Value::map_from([
+            ("visits".into(), Value::Uint32(5)),
+            ("tags".into(), strs(&["z", "y", "b", "c"])),
+            ("meta".into(), Value::map_from([
+                ("limits".into(), Value::map_from([
+                    ("daily".into(), Value::Uint32(5)), ("monthly".into(), Value::Uint32(50))
+                ]))
+            ]))
+        ]));
+
+        let removed = Mutation::RemoveWhere(path("tags"), element(Comparator::In, strs(&["a", "c"])))
+            .execute(&Mutation::Append(path("tags"), Value::str_from("c")).execute(&counts()).unwrap())
+            .unwrap();
+        assert_eq!(removed.lookup("tags").unwrap(), strs(&["b"]));
+
+        assert_eq!(
+            Mutation::Append(path("new"), Value::Bool(true)).execute(&counts()).unwrap().lookup("new").unwrap(),
+            Value::List(Vec::from([Value::Bool(true)]))
+        );
+
+        assert!(Mutation::Decrement(path("visits"), Value::Uint32(4)).execute(&counts()).is_err());
+        assert!(Mutation::Increment(path("tags"), Value::Uint32(1)).execute(&counts()).is_err());
+        assert!(Mutation::Increment(path("visits"), Value::Float64(0.5)).execute(&counts()).is_err());
+        assert!(Mutation::Increment(path("visits"), Value::Uint32(u32::MAX)).execute(&counts()).is_err());
+        assert!(Mutation::Insert(path("tags[3]"), Value::str_from("x")).execute(&counts()).is_err());
+        assert!(Mutation::Unset(path("tags[2]")).execute(&counts()).is_err());
+        assert!(Mutation::Merge(path("visits"), Value::map_from([])).execute(&counts()).is_err());
+    }
+
+    #[test]
+    fn validate() {
+        let typ = counts_type();
+
+        assert!(Mutation::Bulk(Vec::from([
+            Mutation::Set(path("note"), Value::str_from("hi")),
+            Mutation::Unset(path("note")),
+            Mutation::Unset(path("tags[0]")),
+            Mutation::Increment(path("visits"), Value::Int64(-1)),
+            Mutation::Append(path("tags"), Value::str_from("c")),
+            Mutation::Insert(path("tags.0"), Value::str_from("c")),
+            Mutation::RemoveWhere(path("tags"), element(Comparator::StartsWith, Value::str_from("a"))),
+            Mutation::Merge(path("meta"), Value::map_from([
+                ("limits".into(), Value::map_from([("daily".into(), Value::Uint32(1))]))
+            ]))
+        ])).validate(&typ).is_ok());
+
+        assert!(Mutation::Set(path("visits"), Value::str_from("many")).validate(&typ).is_err());
+        assert!(Mutation::Unset(path("visits")).validate(&typ).is_err());
+        assert!(Mutation::Unset(Path::root()).validate(&typ).is_err());
+        assert!(Mutation::Increment(path("meta.owner"), Value::Uint32(1)).validate(&typ).is_err());
+        assert!(Mutation::Increment(path("visits"), Value::Float64(1.0)).validate(&typ).is_err());
+        assert!(Mutation::Decrement(path("visits"), Type::Decimal.decode_str("1.50").unwrap()).validate(&typ).is_err());
+        assert!(Mutation::Append(path("tags"), Value::Uint32(1)).validate(&typ).is_err());
+        assert!(Mutation::Insert(path("tags"), Value::str_from("c")).validate(&typ).is_err());
+        assert!(Mutation::RemoveWhere(path("tags"), element(Comparator::Gt, Value::Uint32(3))).validate(&typ).is_err());
+        assert!(Mutation::Merge(path("meta"), Value::map_from([
+            ("limits".into(), Value::map_from([("daily".into(), Value::Bool(false))]))
+        ])).validate(&typ).is_err());
+    }
+
+    #[test]
+    fn value_round_trip() {
+        let mutation = Mutation::Bulk(Vec::from([
+            Mutation::Set(path("a\\.b[*]"), Value::List(Vec::from([Value::Null, Value::Uint32(1)]))),
+            Mutation::Set(Path::root(), Value::map_from([("x".into(), Value::str_from("y"))])),
+            Mutation::Unset(path("a[2]")),
+            Mutation::Increment(path("n"), Value::Int32(1)),
+            Mutation::Decrement(path("n"), Value::Float64(0.5)),
+            Mutation::Append(path("l"), Value::Null),
+            Mutation::Prepend(path("l"), Value::Bool(true)),
+            Mutation::Insert(path("l[0]"), Value::Uint32(0)),
+            Mutation::RemoveWhere(path("l"), element(Comparator::IsNull, Value::Null)),
+            Mutation::Merge(path("m"), Value::map_from([("k".into(), Value::Null)])),
+            Mutation::Bulk(Vec::new()),
+            Mutation::Set(path("at"), Type::Timestamp.decode_str("2022-11-03T18:04:05Z").unwrap()),
+            Mutation::Increment(path("total"), Type::Decimal.decode_str("1.50").unwrap()),
+            Mutation::Append(path("blobs"), Value::List(Vec::from([Value::Bytes(Vec::from(*b"hi"))])))
+        ]));
+
+        assert_eq!(Mutation::parse_from_value(mutation.to_value()).unwrap(), mutation);
+        assert_eq!(
+            Mutation::Increment(path("total"), Type::Decimal.decode_str("1.50").unwrap()).to_value(),
+            Value::map_from([("increment".into(), Value::List(Vec::from([
+                Value::str_from("total"), Value::str_from("1.50"), Value::str_from("decimal")
+            ])))])
+        );
+    }
+}
@@ -0,0 +1,118 @@
+// Named type references, resolved against the types in the Registry. References are
+// shared rather than copied, so a type may (indirectly) refer to itself.
+use std::sync::{Arc, OnceLock};
+use std::fmt::{Debug, Formatter};
+
+use super::errors::SchemaError;
+use super::primitives::Type;
+
+#[derive(Clone)]
+pub struct TypeRef {
+    name: String,
+    target: Arc<OnceLock<Type>>
+}
+
+impl TypeRef {
+    // An unresolved reference; see Type::link.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            target: Arc::new(OnceLock::new())
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn target(&self) -> Option<&Type> {
+        self.target.get()
+    }
+
+    pub fn resolve(&self) -> Result<&Type, SchemaError> {
+        self.target().ok_or_else(|| SchemaError::UnresolvedType(self.name.clone()))
+    }
+}
+
+// References are identified by name; comparing or printing targets could recurse forever.
+impl PartialEq for TypeRef {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Debug for TypeRef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "TypeRef({:?})", self.name)
+    }
+}
+
+impl Type {
+    // Resolve every reference reachable from this type (without following references that
+    // are already resolved) using the given lookup.
+    pub fn link(&self, resolve: &impl Fn(&str) -> Option<Type>) -> Result<(), SchemaError> {
+        match self {
+            Self::Ref(reference) if reference.target().is_none() => {
+                let target = resolve(reference.name())
+                    .ok_or_else(|| SchemaError::UnresolvedType(reference.name().to_owned()))?;
+
+                // Losing a race here means someone else linked the same reference.
+                let _ = reference.target.set(target);
+            },
+            Self::List(inner) | Self::Optional(inner) | Self::Constrained(inner, _) => inner.link(resolve)?,
+            Self::Default(inner, _) | Self::Computed(inner, _) => inner.link(resolve)?,
+            Self::Map(members) | Self::Union(_, members) => {
+                for member in members.values() {
+                    member.link(resolve)?;
+                }
+            },
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::schema::Value;
+
+    #[test]
+    fn recursive_type() {
+        let comment = Type::Map(HashMap::from([
+            ("text".into(), Type::String),
+            ("replies".into(), Type::List(Box::new(Type::Ref(TypeRef::new("comment")))))
+        ]));
+
+        let lookup = comment.clone();
+        comment.link(&|name| match name {
+            "comment" => Some(lookup.clone()),
+            _ => None
+        }).unwrap();
+
+        let thread = Value::map_from([
+            ("text".into(), Value::str_from("first")),
+            ("replies".into(), Value::List(Vec::from([
+                Value::map_from([
+                    ("text".into(), Value::str_from("second")),
+                    ("replies".into(), Value::List(Vec::new()))
+                ])
+            ])))
+        ]);
+        assert!(comment.validate(&thread).is_ok());
+
+        let report = comment.report(&Value::map_from([
+            ("text".into(), Value::str_from("first")),
+            ("replies".into(), Value::List(Vec::from([
+                Value::map_from([("text".into(), Value::Uint32(2))])
+            ])))
+        ]));
+        let paths: Vec<&str> = report.violations().iter().map(|violation| violation.path()).collect();
+        assert_eq!(paths, ["/replies/0/replies", "/replies/0/text"]);
+
+        assert!(Type::Ref(TypeRef::new("missing")).link(&|_| None).is_err());
+    }
+}
@@ -0,0 +1,192 @@
+// Partial updates in the formats clients send them, as mutations: RFC 6902 JSON Patch
+// and RFC 7396 JSON Merge Patch.
+use std::collections::HashMap;
+
+use super::errors::SchemaError;
+use super::primitives::Value;
+use super::path::{Path, Segment};
+use super::expr::{Comparator, Condition, Conjunctive, ValueReference};
+use super::mutation::Mutation;
+
+fn exists(path: &Path) -> Condition {
+    Condition::Comparison(Comparator::Exists, ValueReference::Reference(path.clone()), Value::Null.into())
+}
+
+// A merge patch without its null members, at any depth, along with the paths of those
+// members, which are to be deleted.
+fn split_deletions(path: &Path, patch: &Value, deletions: &mut Vec<Path>) -> Value {
+    let members = match patch {
+        Value::Map(members) => members,
+        other => return other.clone()
+    };
+
+    let mut keys: Vec<&String> = members.keys().collect();
+    keys.sort();
+
+    let mut kept = HashMap::new();
+    for key in keys {
+        let member_path = path.clone().child(Segment::Key(key.clone()));
+
+        match &members[key] {
+            Value::Null => deletions.push(member_path),
+            member => {
+                kept.insert(key.clone(), split_deletions(&member_path, member, deletions));
+            }
+        }
+    }
+
+    Value::Map(kept)
+}
+
+impl Mutation {
+    // A JSON Patch: a list of operations applied in order, where a test that fails (or a
+    // remove or replace of an absent value) fails the whole patch.
+    pub fn parse_json_patch(value: Value) -> Result<Self, SchemaError> {
+        let mut parts = Vec::new();
+
+        for operation in value.elements()? {
+            let op: String = operation.lookup("op")?.try_into()?;
+            let path = Path::parse_pointer(&String::try_from(operation.lookup("path")?)?)?;
+            let from = || -> Result<Path, SchemaError> {
+                Path::parse_pointer(&String::try_from(operation.lookup("from")?)?)
+            };
+
+            parts.push(match op.as_str() {
+                // Adding at the root replaces the whole document.
+                "add" if path.segments().is_empty() => Self::Set(path, operation.lookup("value")?),
+                "add" => Self::Insert(path, operation.lookup("value")?),
+                "remove" => Self::Bulk(Vec::from([Self::Require(exists(&path)), Self::Unset(path)])),
+                "replace" => Self::Bulk(Vec::from([
+                    Self::Require(exists(&path)), Self::Set(path, operation.lookup("value")?)
+                ])),
+                "move" => Self::Move(from()?, path),
+                "copy" => Self::Copy(from()?, path),
+                "test" => Self::Require(match operation.lookup("value")? {
+                    // Null is unknown to Eq, and absent members look up as null.
+                    Value::Null => Condition::Conjunctive(Conjunctive::And, Vec::from([
+                        exists(&path),
+                        Condition::Comparison(Comparator::IsNull, ValueReference::Reference(path), Value::Null.into())
+                    ])),
+                    expected => Condition::Comparison(Comparator::Eq, ValueReference::Reference(path), expected.into())
+                }),
+                _ => return Err(SchemaError::NotImplemented(format!("invalid patch operation {:?}", op)))
+            });
+        }
+
+        Ok(Self::Bulk(parts))
+    }
+
+    // A JSON Merge Patch: maps are merged member by member, with null members deleted,
+    // and anything else replaces the target. The members that are kept are merged first,
+    // which also replaces whatever isn't a map below the target with one, and then the
+    // deleted ones are unset.
+    pub fn parse_merge_patch(value: Value) -> Self {
+        let mut deletions = Vec::new();
+        let kept = match value {
+            Value::Map(_) => split_deletions(&Path::root(), &value, &mut deletions),
+            other => return Self::Set(Path::root(), other)
+        };
+
+        let mut parts = Vec::from([Self::Merge(Path::root(), kept)]);
+        parts.extend(deletions.into_iter().map(Self::Unset));
+
+        Self::Bulk(parts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map<const N: usize>(members: [(&str, Value); N]) -> Value {
+        Value::Map(members.into_iter().map(|(key, member)| (key.to_owned(), member)).collect())
+    }
+
+    fn list<const N: usize>(elements: [Value; N]) -> Value {
+        Value::List(Vec::from(elements))
+    }
+
+    fn n(number: u32) -> Value {
+        Value::Uint32(number)
+    }
+
+    fn s(string: &str) -> Value {
+        Value::str_from(string)
+    }
+
+    fn op<const N: usize>(op: &str, path: &str, rest: [(&str, Value); N]) -> Value {
+        let mut operation = map(rest);
+        if let Value::Map(members) = &mut operation {
+            members.insert("op".into(), s(op));
+            members.insert("path".into(), s(path));
+        }
+
+        operation
+    }
+
+    #[test]
+    fn json_patch() {
+        let target = map([("a", map([("b", list([n(1), n(2), n(3)]))])), ("c/d", s("x")), ("e", Value::Null)]);
+        let apply = |patch: Value| Mutation::parse_json_patch(patch).unwrap().execute(&target);
+
+        assert_eq!(apply(list([
+            op("add", "/a/b/1", [("value", n(9))]),
+            op("add", "/a/b/-", [("value", n(4))]),
+            op("remove", "/a/b/0", []),
+            op("replace", "/c~1d", [("value", s("y"))]),
+            op("copy", "/f", [("from", s("/c~1d"))]),
+            op("move", "/g", [("from", s("/a/b/0"))]),
+            op("test", "/e", [("value", Value::Null)]),
+            op("test", "/a", [("value", map([("b", list([n(2), n(3), n(4)]))]))])
+        ])).unwrap(), map([
+            ("a", map([("b", list([n(2), n(3), n(4)]))])), ("c/d", s("y")), ("e", Value::Null), ("f", s("y")), ("g", n(9))
+        ]));
+
+        for failing in [
+            op("test", "/c~1d", [("value", s("z"))]),
+            op("test", "/absent", [("value", Value::Null)]),
+            op("test", "", [("value", n(1))]),
+            op("remove", "/absent", []),
+            op("replace", "/absent", [("value", n(1))]),
+            op("add", "/a/b/4", [("value", n(1))]),
+            op("move", "/a/b/0", [("from", s("/a"))])
+        ] {
+            assert!(apply(list([failing.clone()])).is_err(), "{:?} should fail", failing);
+        }
+
+        assert_eq!(apply(list([op("add", "", [("value", list([n(1)]))])])).unwrap(), list([n(1)]));
+
+        assert!(Mutation::parse_json_patch(list([op("frob", "/a", [])])).is_err());
+        assert!(Mutation::parse_json_patch(list([op("add", "a", [("value", n(1))])])).is_err());
+    }
+
+    #[test]
+    fn merge_patch() {
+        // The example from RFC 7396, plus nested deletions and a non-map replaced by one.
+        let target = map([
+            ("title", s("Goodbye!")),
+            ("author", map([("given", s("John")), ("family", s("Doe"))])),
+            ("tags", list([s("a")])),
+            ("n", n(1))
+        ]);
+        let patch = map([
+            ("title", s("Hello!")),
+            ("phone", s("555")),
+            ("author", map([("family", Value::Null)])),
+            ("tags", list([s("b")])),
+            ("n", map([("x", map([("y", Value::Null)]))]))
+        ]);
+
+        assert_eq!(Mutation::parse_merge_patch(patch).execute(&target).unwrap(), map([
+            ("title", s("Hello!")),
+            ("author", map([("given", s("John"))])),
+            ("tags", list([s("b")])),
+            ("phone", s("555")),
+            ("n", map([("x", map([]))]))
+        ]));
+        assert_eq!(Mutation::parse_merge_patch(list([n(1)])).execute(&target).unwrap(), list([n(1)]));
+        assert_eq!(Mutation::parse_merge_patch(map([("absent", map([("x", Value::Null)]))])).execute(&map([])).unwrap(), map([
+            ("absent", map([]))
+        ]));
+    }
+}
@@ -0,0 +1,509 @@
+// Compiled paths into indirectly represented data, e.g. for references and mutations.
+//
+// The grammar is dot-separated member names, with list indices and wildcards in brackets:
+//
+//  address.city
+//  items[0].name     (or items.0.name, numeric members also index lists)
+//  items[*].name     (or items.*.name, every element of a list or member of a map)
+//  a\.b              (the member "a.b"; \ escapes any of . [ ] * \)
+//
+// The empty path is the root, i.e. the value itself.
+use std::fmt::{Display, Formatter};
+
+use super::errors::SchemaError;
+use super::primitives::{Type, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Key(String),
+    Index(usize),
+    // Every element of a list or member of a map.
+    Any
+}
+
+impl Segment {
+    // The list index the segment names, if any; numeric members also index lists.
+    pub fn as_index(&self) -> Option<usize> {
+        match self {
+            Self::Index(i) => Some(*i),
+            Self::Key(key) => list_index(key),
+            Self::Any => None
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    segments: Vec<Segment>
+}
+
+const ESCAPED: [char; 5] = ['.', '[', ']', '*', '\\'];
+
+impl Path {
+    // The path to a value itself.
+    pub fn root() -> Self {
+        Self { segments: Vec::new() }
+    }
+
+    pub fn parse(src: &str) -> Result<Self, SchemaError> {
+        if src.is_empty() {
+            return Ok(Self::root());
+        }
+
+        let invalid = |position: usize, message: &str| {
+            SchemaError::InvalidPath(format!("{} at {} in {:?}", message, position, src))
+        };
+
+        let mut segments = Vec::new();
+        let mut key = String::new();
+        // Whether a member segment is being read (it may still be empty if escaped chars
+        // are yet to come), whether it's a wildcard, and whether a bracketed segment just
+        // closed, in which case only a separator or another bracket may follow.
+        let mut started = false;
+        let mut wildcard = false;
+        let mut closed = false;
+
+        let mut chars = src.char_indices();
+        while let Some((position, token)) = chars.next() {
+            match token {
+                '.' => {
+                    match (started, wildcard) {
+                        (true, true) => segments.push(Segment::Any),
+                        (true, false) => segments.push(Segment::Key(key.clone())),
+                        (false, _) if closed => {},
+                        (false, _) => return Err(invalid(position, "empty segment"))
+                    }
+
+                    key.clear();
+                    (started, wildcard, closed) = (false, false, false);
+                },
+                '[' => {
+                    match (started, wildcard) {
+                        (true, true) => segments.push(Segment::Any),
+                        (true, false) => segments.push(Segment::Key(key.clone())),
+                        _ => {}
+                    }
+
+                    let mut inner = String::new();
+                    loop {
+                        match chars.next() {
+                            Some((_, ']')) => break,
+                            Some((_, inner_token)) => inner.push(inner_token),
+                            None => return Err(invalid(position, "unclosed ["))
+                        }
+                    }
+
+                    segments.push(match inner.as_str() {
+                        "*" => Segment::Any,
+                        index => Segment::Index(index.parse().map_err(|_| invalid(position, "invalid index"))?)
+                    });
+
+                    key.clear();
+                    (started, wildcard, closed) = (false, false, true);
+                },
+                '*' if !started && !closed => (started, wildcard) = (true, true),
+                '*' => return Err(invalid(position, "unescaped * in a member name")),
+                _ if wildcard || closed => return Err(invalid(position, "expected . or [")),
+                '\\' => {
+                    let (_, escaped) = chars.next().ok_or_else(|| invalid(position, "dangling escape"))?;
+
+                    key.push(escaped);
+                    started = true;
+                },
+                other => {
+                    key.push(other);
+                    started = true;
+                }
+            }
+        }
+
+        match (started, wildcard) {
+            (true, true) => segments.push(Segment::Any),
+            (true, false) => segments.push(Segment::Key(key)),
+            (false, _) if closed => {},
+            (false, _) => return Err(invalid(src.len(), "empty segment"))
+        }
+
+        Ok(Self { segments })
+    }
+
+    // An RFC 6901 JSON Pointer, e.g. /items/0/a~1b for items[0] member "a/b". Pointers
+    // have no wildcards, so every token is a member (which also indexes lists).
+    pub fn parse_pointer(src: &str) -> Result<Self, SchemaError> {
+        if src.is_empty() {
+            return Ok(Self::root());
+        }
+
+        let tokens = src.strip_prefix('/')
+            .ok_or_else(|| SchemaError::InvalidPath(format!("pointer {:?} doesn't start with /", src)))?;
+
+        let mut segments = Vec::new();
+        for token in tokens.split('/') {
+            let mut key = String::with_capacity(token.len());
+
+            let mut chars = token.chars();
+            while let Some(token_char) = chars.next() {
+                match token_char {
+                    '~' => match chars.next() {
+                        Some('0') => key.push('~'),
+                        Some('1') => key.push('/'),
+                        _ => return Err(SchemaError::InvalidPath(format!("invalid escape in pointer {:?}", src)))
+                    },
+                    _ => key.push(token_char)
+                }
+            }
+
+            segments.push(Segment::Key(key));
+        }
+
+        Ok(Self { segments })
+    }
+
+    // The inverse of parse_pointer, for paths without wildcards.
+    pub fn to_pointer(&self) -> Result<String, SchemaError> {
+        let mut pointer = String::new();
+
+        for segment in self.segments.iter() {
+            pointer.push('/');
+
+            match segment {
+                Segment::Key(key) => pointer.push_str(&key.replace('~', "~0").replace('/', "~1")),
+                Segment::Index(i) => pointer.push_str(&i.to_string()),
+                Segment::Any => return Err(SchemaError::InvalidPath(format!("{} has a wildcard", self)))
+            }
+        }
+
+        Ok(pointer)
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    pub fn child(mut self, segment: Segment) -> Self {
+        self.segments.push(segment);
+
+        self
+    }
+
+    // The first segment and the path that follows it, unless this is the root.
+    pub fn split_first(&self) -> Option<(&Segment, Path)> {
+        let (first, rest) = self.segments.split_first()?;
+
+        Some((first, Self { segments: rest.to_vec() }))
+    }
+
+    // The path to the parent and the last segment, unless this is the root.
+    pub fn split_last(&self) -> Option<(Path, &Segment)> {
+        let (last, rest) = self.segments.split_last()?;
+
+        Some((Self { segments: rest.to_vec() }, last))
+    }
+
+    pub fn has_wildcard(&self) -> bool {
+        self.segments.contains(&Segment::Any)
+    }
+
+    // Every value the path selects; exactly one unless the path has wildcards. When
+    // lenient, absent members and elements are selected as null rather than being errors.
+    pub fn select(&self, value: &Value, lenient: bool) -> Result<Vec<Value>, SchemaError> {
+        let mut found = Vec::new();
+        select_into(&self.segments, value, lenient, &mut found)?;
+
+        Ok(found)
+    }
+
+    // Rebuild a value with every selected location replaced by the result of a function
+    // of its current value (null if absent). Only the last segment may name an absent
+    // member.
+    pub fn update(&self, value: &Value, f: &mut dyn FnMut(&Value) -> Result<Value, SchemaError>) -> Result<Value, SchemaError> {
+        update_at(&self.segments, value, f)
+    }
+}
+
+// A path of a single member, e.g. for lookups; the key isn't parsed.
+impl From<&str> for Path {
+    fn from(key: &str) -> Self {
+        Self { segments: Vec::from([Segment::Key(key.into())]) }
+    }
+}
+
+impl From<&Path> for Path {
+    fn from(path: &Path) -> Self {
+        path.clone()
+    }
+}
+
+fn list_index(key: &str) -> Option<usize> {
+    match key.chars().all(|c| c.is_ascii_digit()) {
+        true => key.parse().ok(),
+        false => None
+    }
+}
+
+fn select_into(segments: &[Segment], value: &Value, lenient: bool, found: &mut Vec<Value>) -> Result<(), SchemaError> {
+    let (segment, rest) = match segments.split_first() {
+        Some(split) => split,
+        None => {
+            found.push(value.clone());
+            return Ok(());
+        }
+    };
+
+    let missing = |found: &mut Vec<Value>, err: SchemaError| {
+        match lenient {
+            true => {
+                found.push(Value::Null);
+                Ok(())
+            },
+            false => Err(err)
+        }
+    };
+
+    match (segment, value) {
+        (Segment::Key(key), Value::Map(members)) => match members.get(key) {
+            Some(member) => select_into(rest, member, lenient, found),
+            None => missing(found, SchemaError::InvalidLookup(Type::try_from(value).ok(), key.clone()))
+        },
+        (Segment::Key(key), Value::List(_)) if list_index(key).is_some() => {
+            let index = Segment::Index(list_index(key).unwrap_or_default());
+
+            select_into(&[&[index], rest].concat(), value, lenient, found)
+        },
+        (Segment::Index(i), Value::List(elements)) => match elements.get(*i) {
+            Some(element) => select_into(rest, element, lenient, found),
+            None => missing(found, SchemaError::InvalidIndex(Type::try_from(value).ok(), Some(*i)))
+        },
+        (Segment::Any, Value::List(elements)) => {
+            for element in elements.iter() {
+                select_into(rest, element, lenient, found)?;
+            }
+
+            Ok(())
+        },
+        (Segment::Any, Value::Map(members)) => {
+            for member in members.values() {
+                select_into(rest, member, lenient, found)?;
+            }
+
+            Ok(())
+        },
+        (_, Value::Null) if lenient => missing(found, SchemaError::UnknownableType),
+        (Segment::Key(key), _) => Err(SchemaError::InvalidLookup(Type::try_from(value).ok(), key.clone())),
+        (Segment::Index(i), _) => Err(SchemaError::InvalidIndex(Type::try_from(value).ok(), Some(*i))),
+        (Segment::Any, _) => Err(SchemaError::InvalidLookup(Type::try_from(value).ok(), "*".into()))
+    }
+}
+
+fn update_at(
+    segments: &[Segment], value: &Value, f: &mut dyn FnMut(&Value) -> Result<Value, SchemaError>
+) -> Result<Value, SchemaError> {
+    let (segment, rest) = match segments.split_first() {
+        Some(split) => split,
+        None => return f(value)
+    };
+
+    match (segment, value) {
+        (Segment::Key(key), Value::Map(members)) => {
+            let updated_member = match (members.get(key), rest.is_empty()) {
+                (Some(member), _) => update_at(rest, member, f)?,
+                (None, true) => f(&Value::Null)?,
+                (None, false) => return Err(SchemaError::InvalidLookup(Type::try_from(value).ok(), key.clone()))
+            };
+
+            let mut updated = members.clone();
+            updated.insert(key.clone(), updated_member);
+
+            Ok(Value::Map(updated))
+        },
+        (Segment::Key(key), Value::List(_)) if list_index(key).is_some() => {
+            let index = Segment::Index(list_index(key).unwrap_or_default());
+
+            update_at(&[&[index], rest].concat(), value, f)
+        },
+        (Segment::Index(i), Value::List(elements)) => {
+            let element = elements.get(*i)
+                .ok_or_else(|| SchemaError::InvalidIndex(Type::try_from(value).ok(), Some(*i)))?;
+
+            let mut updated = elements.clone();
+            updated[*i] = update_at(rest, element, f)?;
+
+            Ok(Value::List(updated))
+        },
+        (Segment::Any, Value::List(elements)) => {
+            let mut updated = Vec::with_capacity(elements.len());
+            for element in elements.iter() {
+                updated.push(update_at(rest, element, f)?);
+            }
+
+            Ok(Value::List(updated))
+        },
+        (Segment::Any, Value::Map(members)) => {
+            let mut updated = members.clone();
+            for (key, member) in members.iter() {
+                updated.insert(key.clone(), update_at(rest, member, f)?);
+            }
+
+            Ok(Value::Map(updated))
+        },
+        (Segment::Key(key), _) => Err(SchemaError::InvalidLookup(Type::try_from(value).ok(), key.clone())),
+        (Segment::Index(i), _) => Err(SchemaError::InvalidIndex(Type::try_from(value).ok(), Some(*i))),
+        (Segment::Any, _) => Err(SchemaError::InvalidLookup(Type::try_from(value).ok(), "*".into()))
+    }
+}
+
+impl Display for Path {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                Segment::Key(key) => {
+                    if i > 0 {
+                        write!(f, ".")?;
+                    }
+
+                    for token in key.chars() {
+                        if ESCAPED.contains(&token) {
+                            write!(f, "\\")?;
+                        }
+                        write!(f, "{}", token)?;
+                    }
+                },
+                Segment::Index(index) => write!(f, "[{}]", index)?,
+                Segment::Any => write!(f, "[*]")?
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Value {
+    // The value at a path, or the member with a key; a list of every selected value if
+    // the path has wildcards.
+    pub fn lookup(&self, path: impl Into<Path>) -> Result<Value, SchemaError> {
+        let path = path.into();
+        let mut found = path.select(self, false)?;
+
+        match path.has_wildcard() {
+            true => Ok(Value::List(found)),
+            false => Ok(found.pop().unwrap_or(Value::Null))
+        }
+    }
+}
+
+impl Type {
+    fn element_type(&self, segment: &Segment) -> Result<Type, SchemaError> {
+        let element = match (self.base(), segment) {
+            (Self::Any, _) => Self::Any,
+            (Self::List(inner), _) => inner.as_ref().clone(),
+            (Self::Map(members), Segment::Any) => {
+                let mut member_types = members.values();
+                let first = member_types.next().cloned().unwrap_or(Self::Any);
+
+                match member_types.all(|member| *member == first) {
+                    true => first,
+                    false => return Err(SchemaError::InvalidLookup(Some(self.clone()), "*".into()))
+                }
+            },
+            _ => return Err(SchemaError::InvalidLookup(Some(self.clone()), format!("{}", Path::root().child(segment.clone()))))
+        };
+
+        match self.is_optional() && !element.is_optional() {
+            true => Ok(Self::Optional(Box::new(element))),
+            false => Ok(element)
+        }
+    }
+
+    // The type of the value(s) at a path, or of the member with a key. For paths with
+    // wildcards this is the type of each selected value.
+    pub fn lookup(&self, path: impl Into<Path>) -> Result<Type, SchemaError> {
+        let path = path.into();
+        let mut typ = self.clone();
+
+        for segment in path.segments() {
+            typ = match segment {
+                Segment::Key(key) if matches!(typ.base(), Self::List(_)) && list_index(key).is_some() => {
+                    typ.element_type(segment)?
+                },
+                Segment::Key(key) => typ.member(key)?,
+                Segment::Index(_) | Segment::Any => typ.element_type(segment)?
+            };
+        }
+
+        Ok(typ)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn parse_paths() {
+        assert_eq!(Path::parse("address.city").unwrap().segments(), [
+            Segment::Key("address".into()), Segment::Key("city".into())
+        ]);
+        assert_eq!(Path::parse("items[2][*].a\\.b").unwrap().segments(), [
+            Segment::Key("items".into()), Segment::Index(2), Segment::Any, Segment::Key("a.b".into())
+        ]);
+        assert_eq!(Path::parse("*.x").unwrap().segments(), [Segment::Any, Segment::Key("x".into())]);
+
+        for invalid in ["a.", ".a", "a..b", "a[x]", "a[1", "a*", "a[0]b", "a\\"] {
+            assert!(Path::parse(invalid).is_err(), "{:?} should be invalid", invalid);
+        }
+
+        for src in ["a.b", "items[2][*].a\\.b", "[0].x", "\\*", ""] {
+            assert_eq!(Path::parse(src).unwrap().to_string(), src);
+        }
+
+        assert_eq!(Path::parse_pointer("/a~1b/0/~0*").unwrap().segments(), [
+            Segment::Key("a/b".into()), Segment::Key("0".into()), Segment::Key("~*".into())
+        ]);
+        assert_eq!(Path::parse_pointer("/").unwrap().segments(), [Segment::Key("".into())]);
+        assert_eq!(Path::parse_pointer("").unwrap(), Path::root());
+        assert!(Path::parse_pointer("a").is_err());
+        assert!(Path::parse_pointer("/a~2").is_err());
+
+        for src in ["/a~1b/0/~0*", "/", ""] {
+            assert_eq!(Path::parse_pointer(src).unwrap().to_pointer().unwrap(), src);
+        }
+        assert!(Path::parse("a[*]").unwrap().to_pointer().is_err());
+    }
+
+    #[test]
+    fn lookup_paths() {
+        let value = Value::map_from([
+            ("address".into(), Value::map_from([("city".into(), Value::str_from("paris"))])),
+            ("items".into(), Value::List(Vec::from([
+                Value::map_from([("n".into(), Value::Uint32(1))]),
+                Value::map_from([("n".into(), Value::Uint32(2))])
+            ])))
+        ]);
+
+        assert_eq!(value.lookup(Path::parse("address.city").unwrap()).unwrap(), Value::str_from("paris"));
+        assert_eq!(value.lookup(Path::parse("items.1.n").unwrap()).unwrap(), Value::Uint32(2));
+        assert_eq!(
+            value.lookup(Path::parse("items[*].n").unwrap()).unwrap(),
+            Value::List(Vec::from([Value::Uint32(1), Value::Uint32(2)]))
+        );
+        assert!(value.lookup(Path::parse("address.zip").unwrap()).is_err());
+        assert_eq!(value.lookup(Path::root()).unwrap(), value);
+        assert_eq!(value.lookup("address").unwrap().lookup("city").unwrap(), Value::str_from("paris"));
+        assert!(value.lookup("address.city").is_err());
+        assert_eq!(Path::parse("address.zip").unwrap().select(&value, true).unwrap(), [Value::Null]);
+
+        let typ = Type::try_from(&value).unwrap();
+        assert_eq!(typ.lookup(Path::parse("items[*].n").unwrap()).unwrap(), Type::Uint32);
+        assert_eq!(typ.lookup(Path::parse("address.city").unwrap()).unwrap(), Type::String);
+
+        let optional = Type::Map(HashMap::from([
+            ("tags".into(), Type::Optional(Box::new(Type::List(Box::new(Type::String)))))
+        ]));
+        assert_eq!(
+            optional.lookup(Path::parse("tags[0]").unwrap()).unwrap(),
+            Type::Optional(Box::new(Type::String))
+        );
+    }
+}
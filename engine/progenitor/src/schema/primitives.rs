@@ -1,85 +1,196 @@
 // Indirect representations of types and data.
-use std::{mem, collections::HashMap};
+use std::{mem, cmp::Ordering, collections::HashMap};
 
+use super::constraint::Constraint;
 use super::errors::SchemaError;
+use super::generated::Generator;
+use super::named::TypeRef;
+use super::scalars::{Timestamp, Uuid, Decimal, decode_base64};
 
-// TODO: Needs more variants. Is null a type here? Any probably is.
-#[derive(Debug, Clone)]
+// TODO: Needs more variants.
+#[derive(Debug, Clone, PartialEq)]
 pub enum Type {
+    // Matches any value, including null. Mostly useful as the inner type of an Optional
+    // whose sample was null.
+    Any,
     Bool,
     Int32,
     Uint32,
+    Int64,
+    Uint64,
     Float64,
+    Decimal,
     String,
+    Bytes,
+    Timestamp,
+    Uuid,
     // TODO: Investigate whether this is the canonical way to prevent infinite size.
     // Forcing a heap allocation does seem weird but also maybe conceptually correct?
     List(Box<Type>),
-    Map(HashMap<String, Type>)
+    Map(HashMap<String, Type>),
+    // A string from a closed set.
+    Enum(Vec<String>),
+    // A map that is one of several variants, chosen by the string member named by the
+    // discriminator. Variant types describe the remaining members.
+    Union(String, HashMap<String, Type>),
+    // Either null or the inner type. As a Map member, the key may also be absent.
+    Optional(Box<Type>),
+    // The inner type, further restricted to values satisfying every constraint.
+    Constrained(Box<Type>, Vec<Constraint>),
+    // A named type from the Registry.
+    Ref(TypeRef),
+    // The inner type, generated when the value is absent or null.
+    Default(Box<Type>, Generator),
+    // The inner type, generated when the value is first stored regardless of what was
+    // supplied, and kept as it is by later updates.
+    Computed(Box<Type>, Generator)
 }
 
 impl Type {
-    pub fn lookup(&self, key: &str) -> Result<Type, SchemaError> {
-        if let Self::Map(inner) = self {
-            if let Some(value) = inner.get(key) {
-                return Ok(value.clone());
-            }
+    // The type of a single member; see lookup for paths.
+    pub(super) fn member(&self, key: &str) -> Result<Type, SchemaError> {
+        match self {
+            Self::Map(inner) => {
+                if let Some(value) = inner.get(key) {
+                    return Ok(value.clone());
+                }
+            },
+            // A member of an optional map is itself optional.
+            Self::Optional(inner) => {
+                return Ok(Self::Optional(Box::new(inner.member(key)?.required().clone())));
+            },
+            Self::Union(discriminator, variants) => {
+                if key == discriminator {
+                    let mut tags: Vec<String> = variants.keys().cloned().collect();
+                    tags.sort();
+
+                    return Ok(Self::Enum(tags));
+                }
+
+                return self.lookup_through_variants(key, variants);
+            },
+            Self::Constrained(inner, _) | Self::Default(inner, _) | Self::Computed(inner, _) => {
+                return inner.member(key);
+            },
+            Self::Ref(reference) => return reference.resolve()?.member(key),
+            Self::Any => return Ok(Self::Any),
+            _ => {}
         }
         
         Err(SchemaError::InvalidLookup(Some(self.clone()), key.into()))
     }
 
+    // A member of a union has a single type if every variant that declares it agrees,
+    // and is optional unless every variant requires it.
+    fn lookup_through_variants(&self, key: &str, variants: &HashMap<String, Type>) -> Result<Type, SchemaError> {
+        let mut found: Option<Type> = None;
+        let mut required = true;
+
+        for variant in variants.values() {
+            let member = match variant.member(key) {
+                Ok(member) => member,
+                Err(_) => {
+                    required = false;
+                    continue;
+                }
+            };
+
+            required = required && !member.is_optional();
+
+            match &found {
+                Some(existing) if existing != member.required() => {
+                    return Err(SchemaError::InvalidLookup(Some(self.clone()), key.into()));
+                },
+                _ => found = Some(member.required().clone())
+            }
+        }
+
+        match found {
+            Some(member) if required => Ok(member),
+            Some(member) => Ok(Self::Optional(Box::new(member))),
+            None => Err(SchemaError::InvalidLookup(Some(self.clone()), key.into()))
+        }
+    }
+
+    pub fn is_optional(&self) -> bool {
+        match self {
+            Self::Constrained(inner, _) => inner.is_optional(),
+            Self::Ref(reference) => reference.target().is_some_and(Type::is_optional),
+            // Generated values needn't be supplied.
+            Self::Default(_, _) | Self::Computed(_, _) => true,
+            rest => matches!(rest, Self::Optional(_) | Self::Any)
+        }
+    }
+
+    // Whether a value is generated when the member is absent.
+    pub fn is_generated(&self) -> bool {
+        matches!(self, Self::Default(_, _) | Self::Computed(_, _))
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self.base(),
+            Self::Int32 | Self::Uint32 | Self::Int64 | Self::Uint64 | Self::Float64 | Self::Decimal
+        )
+    }
+
+    // The type with any optionality removed.
+    pub fn required(&self) -> &Type {
+        match self {
+            Self::Optional(inner) | Self::Default(inner, _) | Self::Computed(inner, _) => inner.required(),
+            Self::Ref(reference) => match reference.target() {
+                Some(target) if target.is_optional() => target.required(),
+                _ => self
+            },
+            rest => rest
+        }
+    }
+
+    // The type with any optionality, constraints, generation, and naming removed.
+    pub fn base(&self) -> &Type {
+        match self {
+            Self::Optional(inner) | Self::Constrained(inner, _) => inner.base(),
+            Self::Default(inner, _) | Self::Computed(inner, _) => inner.base(),
+            Self::Ref(reference) => reference.target().map_or(self, Type::base),
+            rest => rest
+        }
+    }
+
     pub fn primitive_eq(&self, other: &Type) -> bool {
         match self {
             Self::List(_) => false,
             Self::Map(_) => false,
+            Self::Optional(_) => false,
+            Self::Constrained(_, _) => false,
+            Self::Ref(_) => false,
+            Self::Default(_, _) | Self::Computed(_, _) => false,
             rest => mem::discriminant(rest) == mem::discriminant(other)
         }
     }
 
-    // TODO: Strictness options.
-    pub fn validate(&self, value: &Value) -> Result<(), SchemaError> {
-        let value_t = value.try_into()?;
-        
-        match self {
-            Self::List(inner_t) => {
-                match value {
-                    Value::List(value_members) => {
-                        for member in value_members.iter() {
-                            inner_t.validate(member)?;
-                        }
-
-                        Ok(())
-                    },
-                    _ => Err(SchemaError::InvalidType(self.clone(), value_t))
-                }
-            },
-            Self::Map(inner_ts) => {
-                match value {
-                    Value::Map(value_members) => {
-                        for (key, inner_t) in inner_ts.iter() {
-                            let member = match value_members.get(key) {
-                                Some(member) => member,
-                                None => return Err(SchemaError::MissingKey(key.clone()))
-                            };
-
-                            inner_t.validate(member)?;
-                        }
-
-                        Ok(())
-                    },
-                    _ => Err(SchemaError::InvalidType(self.clone(), value_t))
-                }
-            },
-            _ => {
-                if self.primitive_eq(&value_t) {
-                    Ok(())
-                }
-                else {
-                    Err(SchemaError::InvalidType(self.clone(), value_t))
-                }
-            }
+    // Whether values of these types can be compared, ignoring optionality and constraints.
+    // Numbers are comparable across variants.
+    pub fn comparable_with(&self, other: &Type) -> bool {
+        match (self.base(), other.base()) {
+            (Self::Any, _) | (_, Self::Any) => true,
+            (Self::Enum(_), Self::Enum(_) | Self::String) | (Self::String, Self::Enum(_)) => true,
+            (a, b) if a.is_numeric() && b.is_numeric() => true,
+            (a, b) => a.primitive_eq(b)
         }
     }
+
+    // Recover a value of this type from its agreed string encoding, for data that came
+    // through a format without a native representation for it.
+    pub fn decode_str(&self, string: &str) -> Result<Value, SchemaError> {
+        Ok(match self.base() {
+            Self::String | Self::Any => Value::Str(string.into()),
+            Self::Decimal => Value::Decimal(Decimal::parse(string)?),
+            Self::Bytes => Value::Bytes(decode_base64(string)?),
+            Self::Timestamp => Value::Timestamp(Timestamp::parse(string)?),
+            Self::Uuid => Value::Uuid(Uuid::parse(string)?),
+            other => return Err(SchemaError::InvalidCast(other.clone()))
+        })
+    }
 }
 
 // Indirectly represented data, with (some) type encapsulation.
@@ -90,8 +201,14 @@ pub enum Value {
     Bool(bool),
     Int32(i32),
     Uint32(u32),
+    Int64(i64),
+    Uint64(u64),
     Float64(f64),
+    Decimal(Decimal),
     Str(String),
+    Bytes(Vec<u8>),
+    Timestamp(Timestamp),
+    Uuid(Uuid),
     List(Vec<Value>),
     Map(HashMap<String, Value>)
 }
@@ -105,16 +222,6 @@ impl Value {
         Self::Map(src.into())
     }
 
-    pub fn lookup(&self, key: &str) -> Result<Value, SchemaError> {
-        if let Self::Map(inner) = self {
-            if let Some(value) = inner.get(key) {
-                return Ok(value.clone());
-            }
-        }
-
-        Err(SchemaError::InvalidLookup(Type::try_from(self).ok(), key.into()))
-    }
-
     pub fn index(&self, i: usize) -> Result<Value, SchemaError> {
         if let Self::List(members) = self {
             if i >= members.len() {
@@ -128,6 +235,40 @@ impl Value {
         Err(SchemaError::InvalidLookup(None, "list elements".into()))
     }
 
+    pub(super) fn as_integer(&self) -> Option<i128> {
+        match self {
+            Self::Int32(value) => Some((*value).into()),
+            Self::Uint32(value) => Some((*value).into()),
+            Self::Int64(value) => Some((*value).into()),
+            Self::Uint64(value) => Some((*value).into()),
+            _ => None
+        }
+    }
+
+    pub(super) fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Float64(value) => Some(*value),
+            Self::Decimal(value) => Some(value.to_f64()),
+            rest => rest.as_integer().map(|value| value as f64)
+        }
+    }
+
+    // Order two values of any numeric variants by magnitude. Integers and decimals compare
+    // exactly, anything involving a float compares as floats. None if either side isn't
+    // numeric or is NaN.
+    pub fn compare_numeric(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Self::Decimal(a), Self::Decimal(b)) => Some(a.cmp(b)),
+            (Self::Decimal(a), b) => b.as_integer().map(|b| a.cmp(&Decimal::new(b, 0))),
+            (a, Self::Decimal(b)) => a.as_integer().map(|a| Decimal::new(a, 0).cmp(b)),
+            _ => None
+        }
+        .or_else(|| match (self.as_integer(), other.as_integer()) {
+            (Some(a), Some(b)) => Some(a.cmp(&b)),
+            _ => self.as_f64()?.partial_cmp(&other.as_f64()?)
+        })
+    }
+
     pub fn elements(&self) -> Result<&Vec<Value>, SchemaError> {
         if let Self::List(members) = self {
             return Ok(members);
@@ -151,12 +292,19 @@ impl TryFrom<Value> for Type {
 
     fn try_from(container: Value) -> Result<Self, Self::Error> {
         Ok(match container {
-            Value::Null => return Err(SchemaError::UnknownableType),
+            // A null sample only tells us the member is nullable.
+            Value::Null => Self::Optional(Box::new(Self::Any)),
             Value::Bool(_) => Self::Bool,
             Value::Int32(_) => Self::Int32,
             Value::Uint32(_) => Self::Uint32,
+            Value::Int64(_) => Self::Int64,
+            Value::Uint64(_) => Self::Uint64,
             Value::Float64(_) => Self::Float64,
+            Value::Decimal(_) => Self::Decimal,
             Value::Str(_) => Self::String,
+            Value::Bytes(_) => Self::Bytes,
+            Value::Timestamp(_) => Self::Timestamp,
+            Value::Uuid(_) => Self::Uuid,
             Value::List(members) => {
                 if members.len() == 0 {
                     return Err(Self::Error::UnknownableType);
@@ -231,7 +379,11 @@ macro_rules! primitive_to_indirect_implementation {
 primitive_from_indirect_implementation!(Value::Bool, bool);
 primitive_from_indirect_implementation!(Value::Int32, i32);
 primitive_from_indirect_implementation!(Value::Float64, f64);
+primitive_from_indirect_implementation!(Value::Decimal, Decimal);
 primitive_from_indirect_implementation!(Value::Str, String);
+primitive_from_indirect_implementation!(Value::Bytes, Vec<u8>);
+primitive_from_indirect_implementation!(Value::Timestamp, Timestamp);
+primitive_from_indirect_implementation!(Value::Uuid, Uuid);
 
 impl TryFrom<Value> for u32 {
     type Error = SchemaError;
@@ -242,7 +394,7 @@ impl TryFrom<Value> for u32 {
             Value::Int32(value) => {
                 match u32::try_from(value) {
                     Ok(unsigned) => Ok(unsigned),
-                    Err(_) => Err(SchemaError::NotImplemented("Cast of i32 -> u32 failed".into()))
+                    Err(_) => Err(SchemaError::OutOfRange(format!("{} doesn't fit in u32", value)))
                 }
             },
             case => Err(SchemaError::NotImplemented(format!("Indirect cast to u32 failed: {:?}", case)))
@@ -261,8 +413,110 @@ impl TryFrom<Value> for Option<u32> {
     }
 }
 
+// The 64-bit integers widen from any integer variant that fits.
+impl TryFrom<Value> for i64 {
+    type Error = SchemaError;
+
+    fn try_from(indirect: Value) -> Result<Self, Self::Error> {
+        match indirect {
+            Value::Int64(value) => Ok(value),
+            Value::Int32(value) => Ok(value.into()),
+            Value::Uint32(value) => Ok(value.into()),
+            Value::Uint64(value) => {
+                match i64::try_from(value) {
+                    Ok(signed) => Ok(signed),
+                    Err(_) => Err(SchemaError::OutOfRange(format!("{} doesn't fit in i64", value)))
+                }
+            },
+            case => Err(SchemaError::NotImplemented(format!("Indirect cast to i64 failed: {:?}", case)))
+        }
+    }
+}
+
+impl TryFrom<Value> for Option<i64> {
+    type Error = SchemaError;
+
+    fn try_from(indirect: Value) -> Result<Self, Self::Error> {
+        Ok(match indirect {
+            Value::Null => None,
+            _ => Some(indirect.try_into()?)
+        })
+    }
+}
+
+impl TryFrom<Value> for u64 {
+    type Error = SchemaError;
+
+    fn try_from(indirect: Value) -> Result<Self, Self::Error> {
+        let cast_failed = |value: i64| SchemaError::OutOfRange(format!("{} doesn't fit in u64", value));
+
+        match indirect {
+            Value::Uint64(value) => Ok(value),
+            Value::Uint32(value) => Ok(value.into()),
+            Value::Int32(value) => u64::try_from(value).map_err(|_| cast_failed(value.into())),
+            Value::Int64(value) => u64::try_from(value).map_err(|_| cast_failed(value)),
+            case => Err(SchemaError::NotImplemented(format!("Indirect cast to u64 failed: {:?}", case)))
+        }
+    }
+}
+
+impl TryFrom<Value> for Option<u64> {
+    type Error = SchemaError;
+
+    fn try_from(indirect: Value) -> Result<Self, Self::Error> {
+        Ok(match indirect {
+            Value::Null => None,
+            _ => Some(indirect.try_into()?)
+        })
+    }
+}
+
 primitive_to_indirect_implementation!(Value::Bool, bool);
 primitive_to_indirect_implementation!(Value::Int32, i32);
 primitive_to_indirect_implementation!(Value::Uint32, u32);
+primitive_to_indirect_implementation!(Value::Int64, i64);
+primitive_to_indirect_implementation!(Value::Uint64, u64);
 primitive_to_indirect_implementation!(Value::Float64, f64);
+primitive_to_indirect_implementation!(Value::Decimal, Decimal);
 primitive_to_indirect_implementation!(Value::Str, String);
+primitive_to_indirect_implementation!(Value::Bytes, Vec<u8>);
+primitive_to_indirect_implementation!(Value::Timestamp, Timestamp);
+primitive_to_indirect_implementation!(Value::Uuid, Uuid);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payment_type() -> Type {
+        Type::Union("kind".into(), HashMap::from([
+            ("card".into(), Type::Map(HashMap::from([
+                ("number".into(), Type::String),
+                ("note".into(), Type::Optional(Box::new(Type::String)))
+            ]))),
+            ("bank".into(), Type::Map(HashMap::from([
+                ("iban".into(), Type::String),
+                ("note".into(), Type::String)
+            ])))
+        ]))
+    }
+
+    #[test]
+    fn lookup_union() {
+        let typ = payment_type();
+
+        assert_eq!(typ.lookup("kind").unwrap(), Type::Enum(Vec::from(["bank".into(), "card".into()])));
+        assert_eq!(typ.lookup("note").unwrap(), Type::Optional(Box::new(Type::String)));
+        assert_eq!(typ.lookup("iban").unwrap(), Type::Optional(Box::new(Type::String)));
+        assert!(typ.lookup("amount").is_err());
+    }
+
+    #[test]
+    fn integer_casts() {
+        assert_eq!(i64::try_from(Value::Uint32(7)).unwrap(), 7);
+        assert_eq!(u64::try_from(Value::Int64(7)).unwrap(), 7);
+
+        assert!(matches!(u32::try_from(Value::Int32(-1)), Err(SchemaError::OutOfRange(_))));
+        assert!(matches!(i64::try_from(Value::Uint64(u64::MAX)), Err(SchemaError::OutOfRange(_))));
+        assert!(matches!(u64::try_from(Value::Int64(-1)), Err(SchemaError::OutOfRange(_))));
+    }
+}
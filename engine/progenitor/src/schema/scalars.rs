@@ -0,0 +1,513 @@
+// Scalar primitives that don't have a std representation, along with their agreed
+// string encodings (used wherever a format can't represent them natively).
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::errors::SchemaError;
+use super::primitives::Type;
+
+const MICROS_PER_SECOND: i64 = 1_000_000;
+const SECONDS_PER_DAY: i64 = 86_400;
+
+// Days since the unix epoch for a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+// Inverse of days_from_civil.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = if days >= 0 { days } else { days - 146_096 } / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+
+    (year_of_era + era * 400 + if month <= 2 { 1 } else { 0 }, month, day)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31
+    }
+}
+
+// An instant, as microseconds since the unix epoch (UTC).
+//
+// Encoded as RFC 3339 in UTC, e.g. 2022-11-03T18:04:05.250000Z. The fraction is omitted
+// when it's zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp {
+    micros: i64
+}
+
+impl Timestamp {
+    pub fn from_micros(micros: i64) -> Self {
+        Self { micros }
+    }
+
+    pub fn now() -> Self {
+        let micros = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(elapsed) => elapsed.as_micros() as i64,
+            Err(before) => -(before.duration().as_micros() as i64)
+        };
+
+        Self { micros }
+    }
+
+    pub fn micros(&self) -> i64 {
+        self.micros
+    }
+
+    pub fn parse(string: &str) -> Result<Self, SchemaError> {
+        let invalid = || SchemaError::InvalidCast(Type::Timestamp);
+
+        let bytes = string.as_bytes();
+        let digits = |from: usize, to: usize| -> Result<i64, SchemaError> {
+            let part = string.get(from..to).ok_or_else(invalid)?;
+            if !part.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(invalid());
+            }
+
+            part.parse::<i64>().map_err(|_| invalid())
+        };
+        let expect = |at: usize, options: &[u8]| -> Result<(), SchemaError> {
+            match bytes.get(at) {
+                Some(token) if options.contains(token) => Ok(()),
+                _ => Err(invalid())
+            }
+        };
+
+        expect(4, b"-")?;
+        expect(7, b"-")?;
+        expect(10, b"Tt ")?;
+        expect(13, b":")?;
+        expect(16, b":")?;
+
+        let (year, month, day) = (digits(0, 4)?, digits(5, 7)?, digits(8, 10)?);
+        let (hour, minute, second) = (digits(11, 13)?, digits(14, 16)?, digits(17, 19)?);
+
+        // Leap seconds can't be represented, so second 60 is as invalid as February 30th.
+        if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day)
+            || hour > 23 || minute > 59 || second > 59
+        {
+            return Err(invalid());
+        }
+
+        let mut position = 19;
+        let mut fraction_micros = 0;
+        if bytes.get(position) == Some(&b'.') {
+            position += 1;
+
+            let start = position;
+            while bytes.get(position).is_some_and(|b| b.is_ascii_digit()) {
+                position += 1;
+            }
+            if position == start {
+                return Err(invalid());
+            }
+
+            // Anything beyond microsecond precision is truncated.
+            let kept = &string[start..position.min(start + 6)];
+            fraction_micros = kept.parse::<i64>().map_err(|_| invalid())? * 10_i64.pow(6 - kept.len() as u32);
+        }
+
+        let offset_seconds = match bytes.get(position) {
+            Some(b'Z') | Some(b'z') if position + 1 == bytes.len() => 0,
+            Some(sign @ (b'+' | b'-')) if position + 6 == bytes.len() => {
+                expect(position + 3, b":")?;
+
+                let (offset_hours, offset_minutes) = (digits(position + 1, position + 3)?, digits(position + 4, position + 6)?);
+                if offset_hours > 23 || offset_minutes > 59 {
+                    return Err(invalid());
+                }
+
+                let offset = offset_hours * 3600 + offset_minutes * 60;
+
+                if *sign == b'-' { -offset } else { offset }
+            },
+            _ => return Err(invalid())
+        };
+
+        let seconds = days_from_civil(year, month, day) * SECONDS_PER_DAY
+            + hour * 3600 + minute * 60 + second - offset_seconds;
+
+        Ok(Self::from_micros(seconds * MICROS_PER_SECOND + fraction_micros))
+    }
+}
+
+impl Display for Timestamp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let seconds = self.micros.div_euclid(MICROS_PER_SECOND);
+        let fraction = self.micros.rem_euclid(MICROS_PER_SECOND);
+
+        let (year, month, day) = civil_from_days(seconds.div_euclid(SECONDS_PER_DAY));
+        let in_day = seconds.rem_euclid(SECONDS_PER_DAY);
+
+        write!(
+            f, "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year, month, day, in_day / 3600, (in_day % 3600) / 60, in_day % 60
+        )?;
+
+        if fraction != 0 {
+            write!(f, ".{:06}", fraction)?;
+        }
+
+        write!(f, "Z")
+    }
+}
+
+// A 128-bit universally unique identifier.
+//
+// Encoded in the lowercase hyphenated form, e.g. 67e55044-10b1-426f-9247-bb680e5fe0c8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Uuid {
+    bytes: [u8; 16]
+}
+
+impl Uuid {
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        Self { bytes }
+    }
+
+    pub fn from_u128(value: u128) -> Self {
+        Self::from_bytes(value.to_be_bytes())
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.bytes
+    }
+
+    pub fn as_u128(&self) -> u128 {
+        u128::from_be_bytes(self.bytes)
+    }
+
+    pub fn parse(string: &str) -> Result<Self, SchemaError> {
+        let invalid = || SchemaError::InvalidCast(Type::Uuid);
+
+        if string.len() != 36 {
+            return Err(invalid());
+        }
+
+        let mut value: u128 = 0;
+        for (i, token) in string.chars().enumerate() {
+            if i == 8 || i == 13 || i == 18 || i == 23 {
+                if token != '-' {
+                    return Err(invalid());
+                }
+
+                continue;
+            }
+
+            value = (value << 4) | token.to_digit(16).ok_or_else(invalid)? as u128;
+        }
+
+        Ok(Self::from_u128(value))
+    }
+}
+
+impl Display for Uuid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, byte) in self.bytes.iter().enumerate() {
+            if i == 4 || i == 6 || i == 8 || i == 10 {
+                write!(f, "-")?;
+            }
+
+            write!(f, "{:02x}", byte)?;
+        }
+
+        Ok(())
+    }
+}
+
+// An exact base 10 number, represented as mantissa * 10^-scale.
+//
+// Equality and ordering are numeric, so 1.5 and 1.50 are equal. Encoded as a plain decimal
+// string, e.g. -1024.50, so that no precision is lost in formats with binary floats.
+#[derive(Debug, Clone, Copy)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u32
+}
+
+impl Decimal {
+    pub const MAX_SCALE: u32 = 28;
+
+    pub fn new(mantissa: i128, scale: u32) -> Self {
+        Self { mantissa, scale }
+    }
+
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    // The same number with trailing fractional zeros removed.
+    pub fn normalized(&self) -> Self {
+        let mut normal = *self;
+        while normal.scale > 0 && normal.mantissa % 10 == 0 {
+            normal.mantissa /= 10;
+            normal.scale -= 1;
+        }
+
+        normal
+    }
+
+    fn rescaled(&self, scale: u32) -> Option<i128> {
+        self.mantissa.checked_mul(10_i128.checked_pow(scale.checked_sub(self.scale)?)?)
+    }
+
+    fn aligned(&self, other: &Decimal) -> Option<(i128, i128, u32)> {
+        let scale = self.scale.max(other.scale);
+
+        Some((self.rescaled(scale)?, other.rescaled(scale)?, scale))
+    }
+
+    pub fn checked_add(&self, other: &Decimal) -> Option<Decimal> {
+        let (a, b, scale) = self.aligned(other)?;
+
+        Some(Self::new(a.checked_add(b)?, scale))
+    }
+
+    pub fn checked_sub(&self, other: &Decimal) -> Option<Decimal> {
+        let (a, b, scale) = self.aligned(other)?;
+
+        Some(Self::new(a.checked_sub(b)?, scale))
+    }
+
+    pub fn checked_mul(&self, other: &Decimal) -> Option<Decimal> {
+        let scale = self.scale + other.scale;
+        if scale > Self::MAX_SCALE {
+            return None;
+        }
+
+        Some(Self::new(self.mantissa.checked_mul(other.mantissa)?, scale))
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.mantissa as f64 / 10_f64.powi(self.scale as i32)
+    }
+
+    pub fn parse(string: &str) -> Result<Self, SchemaError> {
+        let invalid = || SchemaError::InvalidCast(Type::Decimal);
+
+        let (negative, unsigned) = match string.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, string.strip_prefix('+').unwrap_or(string))
+        };
+
+        let (whole, fraction) = match unsigned.split_once('.') {
+            Some((whole, fraction)) => (whole, fraction),
+            None => (unsigned, "")
+        };
+
+        if whole.is_empty() || fraction.len() as u32 > Self::MAX_SCALE
+            || !whole.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit())
+            || unsigned.ends_with('.')
+        {
+            return Err(invalid());
+        }
+
+        let mut mantissa: i128 = 0;
+        for digit in whole.bytes().chain(fraction.bytes()) {
+            mantissa = mantissa.checked_mul(10)
+                .and_then(|shifted| shifted.checked_add((digit - b'0') as i128))
+                .ok_or_else(invalid)?;
+        }
+
+        Ok(Self::new(if negative { -mantissa } else { mantissa }, fraction.len() as u32))
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(value: i64) -> Self {
+        Self::new(value as i128, 0)
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (a, b) = (self.normalized(), other.normalized());
+
+        match a.aligned(&b) {
+            Some((a, b, _)) => a.cmp(&b),
+            // Only reachable at the edges of i128, where f64 is precise enough to order.
+            None => a.to_f64().total_cmp(&b.to_f64())
+        }
+    }
+}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let normal = self.normalized();
+
+        normal.mantissa.hash(state);
+        normal.scale.hash(state);
+    }
+}
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let scale = self.scale as usize;
+
+        if self.mantissa < 0 {
+            write!(f, "-")?;
+        }
+
+        if scale == 0 {
+            return write!(f, "{}", digits);
+        }
+
+        let padded = format!("{:0>width$}", digits, width = scale + 1);
+        let (whole, fraction) = padded.split_at(padded.len() - scale);
+
+        write!(f, "{}.{}", whole, fraction)
+    }
+}
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// Standard, padded base64; the agreed string encoding for bytes.
+pub(crate) fn encode_base64(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let group = chunk.iter()
+            .enumerate()
+            .fold(0_u32, |group, (i, byte)| group | ((*byte as u32) << (16 - 8 * i)));
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[((group >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            }
+            else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+pub(crate) fn decode_base64(string: &str) -> Result<Vec<u8>, SchemaError> {
+    let invalid = || SchemaError::InvalidCast(Type::Bytes);
+
+    let bytes = string.as_bytes();
+    if !bytes.len().is_multiple_of(4) {
+        return Err(invalid());
+    }
+
+    let mut decoded = Vec::with_capacity(bytes.len() / 4 * 3);
+    for (chunk_index, chunk) in bytes.chunks(4).enumerate() {
+        let is_last = chunk_index == bytes.len() / 4 - 1;
+
+        let mut group = 0_u32;
+        let mut padding = 0;
+        for (i, token) in chunk.iter().enumerate() {
+            let sextet = match token {
+                b'=' if is_last && i >= 2 => {
+                    padding += 1;
+                    0
+                },
+                _ if padding > 0 => return Err(invalid()),
+                _ => BASE64_ALPHABET.iter().position(|a| a == token).ok_or_else(invalid)? as u32
+            };
+
+            group = (group << 6) | sextet;
+        }
+
+        for i in 0..(3 - padding) {
+            decoded.push((group >> (16 - 8 * i)) as u8);
+        }
+    }
+
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamp_encoding() {
+        let instant = Timestamp::parse("2022-11-03T18:04:05.25Z").unwrap();
+
+        assert_eq!(instant.micros(), 1_667_498_645_250_000);
+        assert_eq!(instant.to_string(), "2022-11-03T18:04:05.250000Z");
+        assert_eq!(Timestamp::parse("2022-11-03T20:04:05.250+02:00").unwrap(), instant);
+        assert_eq!(Timestamp::from_micros(-1).to_string(), "1969-12-31T23:59:59.999999Z");
+
+        assert!(Timestamp::parse("2022-13-03T18:04:05Z").is_err());
+        assert!(Timestamp::parse("2022-02-31T00:00:00Z").is_err());
+        assert!(Timestamp::parse("2022-04-31T00:00:00Z").is_err());
+        assert!(Timestamp::parse("2023-02-29T00:00:00Z").is_err());
+        assert!(Timestamp::parse("2024-02-29T00:00:00Z").is_ok());
+        assert!(Timestamp::parse("1900-02-29T00:00:00Z").is_err());
+        assert!(Timestamp::parse("2000-02-29T00:00:00Z").is_ok());
+        assert!(Timestamp::parse("2016-12-31T23:59:60Z").is_err());
+        assert!(Timestamp::parse("2022-11-03T18:04:05+24:00").is_err());
+        assert!(Timestamp::parse("2022-11-03T18:04:05").is_err());
+    }
+
+    #[test]
+    fn uuid_encoding() {
+        let id = Uuid::parse("67E55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+
+        assert_eq!(id.as_u128(), 0x67e5504410b1426f9247bb680e5fe0c8);
+        assert_eq!(id.to_string(), "67e55044-10b1-426f-9247-bb680e5fe0c8");
+
+        assert!(Uuid::parse("67e5504410b1426f9247bb680e5fe0c8").is_err());
+    }
+
+    #[test]
+    fn decimal_encoding() {
+        assert_eq!(Decimal::parse("-1024.50").unwrap().to_string(), "-1024.50");
+        assert_eq!(Decimal::parse("0.05").unwrap().to_string(), "0.05");
+        assert_eq!(Decimal::parse("1.50").unwrap(), Decimal::new(15, 1));
+        assert!(Decimal::parse("1.25").unwrap() > Decimal::parse("1.2").unwrap());
+
+        assert!(Decimal::parse("1.").is_err());
+        assert!(Decimal::parse("1e5").is_err());
+    }
+
+    #[test]
+    fn base64_encoding() {
+        for data in [&b""[..], b"h", b"he", b"hel", b"hello"] {
+            assert_eq!(decode_base64(&encode_base64(data)).unwrap(), data);
+        }
+
+        assert_eq!(encode_base64(b"hello"), "aGVsbG8=");
+        assert!(decode_base64("aGVsb=G8").is_err());
+    }
+}
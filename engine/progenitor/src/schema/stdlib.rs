@@ -0,0 +1,552 @@
+// The standard function library. map and filter take a lambda rather than a value, so
+// they're part of Expression instead.
+//
+//  strings: lower, upper, trim, concat(s, ...), split(s, separator), format(template, ...)
+//           where each {} in the template is replaced by the next argument
+//  math:    abs, round(n, digits?), min(n, ...), max(n, ...)
+//  lists:   len (also of strings and maps), sum, first
+//  maps:    keys, merge(m, ...) where later members win, pick(m, key, ...), omit(m, key, ...)
+//  types:   is_null, is_bool, is_number, is_string, is_list, is_map, and the casts
+//           to_string, to_int, to_float and to_decimal
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use super::errors::SchemaError;
+use super::primitives::{Type, Value};
+use super::scalars::{Decimal, encode_base64};
+use super::functions::NativeFunction;
+use super::logic::Operator;
+
+fn invalid(args: &[Type]) -> SchemaError {
+    SchemaError::InvalidArguments(args.to_vec())
+}
+
+fn invalid_values(args: &[Value]) -> SchemaError {
+    SchemaError::InvalidArguments(args.iter().map(|arg| Type::try_from(arg).unwrap_or(Type::Any)).collect())
+}
+
+fn is_text(typ: &Type) -> bool {
+    matches!(typ.base(), Type::String | Type::Enum(_) | Type::Any)
+}
+
+fn is_number(typ: &Type) -> bool {
+    typ.is_numeric() || *typ.base() == Type::Any
+}
+
+fn is_map(typ: &Type) -> bool {
+    matches!(typ.base(), Type::Map(_) | Type::Union(_, _) | Type::Any)
+}
+
+fn element_type(typ: &Type) -> Option<Type> {
+    match typ.base() {
+        Type::List(inner) => Some(inner.as_ref().clone()),
+        Type::Any => Some(Type::Any),
+        _ => None
+    }
+}
+
+// Check the arity, and that every argument is acceptable.
+fn accepts(args: &[Type], min: usize, max: Option<usize>, each: fn(usize, &Type) -> bool) -> Result<(), SchemaError> {
+    let arity = args.len() >= min && max.is_none_or(|max| args.len() <= max);
+
+    match arity && args.iter().enumerate().all(|(i, arg)| each(i, arg)) {
+        true => Ok(()),
+        false => Err(invalid(args))
+    }
+}
+
+fn text(value: &Value) -> Result<&str, SchemaError> {
+    match value {
+        Value::Str(string) => Ok(string),
+        other => Err(invalid_values(std::slice::from_ref(other)))
+    }
+}
+
+// The text form of a scalar, e.g. for format and to_string.
+fn display(value: &Value) -> Result<String, SchemaError> {
+    Ok(match value {
+        Value::Str(string) => string.clone(),
+        Value::Bool(value) => value.to_string(),
+        Value::Int32(value) => value.to_string(),
+        Value::Uint32(value) => value.to_string(),
+        Value::Int64(value) => value.to_string(),
+        Value::Uint64(value) => value.to_string(),
+        Value::Float64(value) => value.to_string(),
+        Value::Decimal(value) => value.to_string(),
+        Value::Timestamp(value) => value.to_string(),
+        Value::Uuid(value) => value.to_string(),
+        Value::Bytes(value) => encode_base64(value),
+        other => return Err(invalid_values(std::slice::from_ref(other)))
+    })
+}
+
+fn text_signature(args: &[Type]) -> Result<Type, SchemaError> {
+    accepts(args, 1, Some(1), |_, arg| is_text(arg))?;
+
+    Ok(Type::String)
+}
+
+fn lower(args: &[Value]) -> Result<Value, SchemaError> {
+    Ok(Value::Str(text(&args[0])?.to_lowercase()))
+}
+
+fn upper(args: &[Value]) -> Result<Value, SchemaError> {
+    Ok(Value::Str(text(&args[0])?.to_uppercase()))
+}
+
+fn trim(args: &[Value]) -> Result<Value, SchemaError> {
+    Ok(Value::Str(text(&args[0])?.trim().to_owned()))
+}
+
+fn concat_signature(args: &[Type]) -> Result<Type, SchemaError> {
+    accepts(args, 1, None, |_, arg| is_text(arg))?;
+
+    Ok(Type::String)
+}
+
+fn concat(args: &[Value]) -> Result<Value, SchemaError> {
+    let mut joined = String::new();
+    for arg in args {
+        joined.push_str(text(arg)?);
+    }
+
+    Ok(Value::Str(joined))
+}
+
+fn split_signature(args: &[Type]) -> Result<Type, SchemaError> {
+    accepts(args, 2, Some(2), |_, arg| is_text(arg))?;
+
+    Ok(Type::List(Box::new(Type::String)))
+}
+
+fn split(args: &[Value]) -> Result<Value, SchemaError> {
+    let parts = text(&args[0])?.split(text(&args[1])?);
+
+    Ok(Value::List(parts.map(Value::str_from).collect()))
+}
+
+fn format_signature(args: &[Type]) -> Result<Type, SchemaError> {
+    accepts(args, 1, None, |i, arg| match i {
+        0 => is_text(arg),
+        _ => !matches!(arg.base(), Type::List(_) | Type::Map(_) | Type::Union(_, _))
+    })?;
+
+    Ok(Type::String)
+}
+
+fn format(args: &[Value]) -> Result<Value, SchemaError> {
+    let template = text(&args[0])?;
+    let mut pieces = template.split("{}");
+    let mut formatted = String::from(pieces.next().unwrap_or_default());
+
+    let mut values = args[1..].iter();
+    for piece in pieces {
+        let value = values.next().ok_or_else(|| invalid_values(args))?;
+
+        formatted.push_str(&display(value)?);
+        formatted.push_str(piece);
+    }
+
+    match values.next() {
+        Some(_) => Err(invalid_values(args)),
+        None => Ok(Value::Str(formatted))
+    }
+}
+
+fn abs_signature(args: &[Type]) -> Result<Type, SchemaError> {
+    accepts(args, 1, Some(1), |_, arg| is_number(arg))?;
+
+    Ok(args[0].base().clone())
+}
+
+fn abs(args: &[Value]) -> Result<Value, SchemaError> {
+    let overflow = || invalid_values(args);
+
+    Ok(match &args[0] {
+        Value::Int32(value) => Value::Int32(value.checked_abs().ok_or_else(overflow)?),
+        Value::Int64(value) => Value::Int64(value.checked_abs().ok_or_else(overflow)?),
+        Value::Float64(value) => Value::Float64(value.abs()),
+        Value::Decimal(value) => Value::Decimal(Decimal::new(value.mantissa().abs(), value.scale())),
+        unsigned @ (Value::Uint32(_) | Value::Uint64(_)) => unsigned.clone(),
+        _ => return Err(invalid_values(args))
+    })
+}
+
+fn round_signature(args: &[Type]) -> Result<Type, SchemaError> {
+    accepts(args, 1, Some(2), |i, arg| match i {
+        0 => is_number(arg),
+        _ => matches!(arg.base(), Type::Int32 | Type::Uint32 | Type::Int64 | Type::Uint64 | Type::Any)
+    })?;
+
+    Ok(args[0].base().clone())
+}
+
+// Half away from zero, to a number of decimal places (0 by default).
+fn round(args: &[Value]) -> Result<Value, SchemaError> {
+    let digits = match args.get(1) {
+        Some(digits) => digits.as_integer().and_then(|digits| u32::try_from(digits).ok()).ok_or_else(|| invalid_values(args))?,
+        None => 0
+    };
+
+    Ok(match &args[0] {
+        Value::Float64(value) => {
+            let factor = 10f64.powi(digits.min(308) as i32);
+
+            Value::Float64((value * factor).round() / factor)
+        },
+        Value::Decimal(value) if value.scale() > digits => {
+            let factor = 10i128.checked_pow(value.scale() - digits).ok_or_else(|| invalid_values(args))?;
+            let (quotient, remainder) = (value.mantissa() / factor, value.mantissa() % factor);
+
+            let rounded = match remainder.abs() * 2 >= factor {
+                true => quotient + value.mantissa().signum(),
+                false => quotient
+            };
+
+            Value::Decimal(Decimal::new(rounded, digits))
+        },
+        number if number.as_f64().is_some() => number.clone(),
+        _ => return Err(invalid_values(args))
+    })
+}
+
+fn extremum_signature(args: &[Type]) -> Result<Type, SchemaError> {
+    accepts(args, 1, None, |_, arg| is_number(arg))?;
+
+    // The result is whichever argument is the extremum, as is.
+    match args.iter().all(|arg| arg.base() == args[0].base()) {
+        true => Ok(args[0].base().clone()),
+        false => Ok(Type::Any)
+    }
+}
+
+fn extremum(args: &[Value], keep: Ordering) -> Result<Value, SchemaError> {
+    let mut best = &args[0];
+
+    for arg in &args[1..] {
+        match arg.compare_numeric(best) {
+            Some(ordering) if ordering == keep => best = arg,
+            Some(_) => {},
+            None => return Err(invalid_values(args))
+        }
+    }
+
+    match best.as_f64() {
+        Some(_) => Ok(best.clone()),
+        None => Err(invalid_values(args))
+    }
+}
+
+fn min(args: &[Value]) -> Result<Value, SchemaError> {
+    extremum(args, Ordering::Less)
+}
+
+fn max(args: &[Value]) -> Result<Value, SchemaError> {
+    extremum(args, Ordering::Greater)
+}
+
+fn len_signature(args: &[Type]) -> Result<Type, SchemaError> {
+    accepts(args, 1, Some(1), |_, arg| is_text(arg) || is_map(arg) || element_type(arg).is_some())?;
+
+    Ok(Type::Uint64)
+}
+
+fn len(args: &[Value]) -> Result<Value, SchemaError> {
+    let length = match &args[0] {
+        Value::Str(string) => string.chars().count(),
+        Value::List(elements) => elements.len(),
+        Value::Map(members) => members.len(),
+        _ => return Err(invalid_values(args))
+    };
+
+    Ok(Value::Uint64(length as u64))
+}
+
+fn sum_signature(args: &[Type]) -> Result<Type, SchemaError> {
+    accepts(args, 1, Some(1), |_, arg| element_type(arg).is_some_and(|element| is_number(&element)))?;
+
+    // The sum of nothing is null, as in SQL.
+    Ok(Type::Optional(Box::new(element_type(&args[0]).unwrap_or(Type::Any))))
+}
+
+fn sum(args: &[Value]) -> Result<Value, SchemaError> {
+    let elements = args[0].elements()?;
+
+    let mut total: Option<Value> = None;
+    for element in elements {
+        if element.as_f64().is_none() {
+            return Err(invalid_values(args));
+        }
+
+        total = Some(match total {
+            Some(total) => Operator::Add.evaluate(&total, element)?,
+            None => element.clone()
+        });
+    }
+
+    Ok(total.unwrap_or(Value::Null))
+}
+
+fn first_signature(args: &[Type]) -> Result<Type, SchemaError> {
+    accepts(args, 1, Some(1), |_, arg| element_type(arg).is_some())?;
+
+    Ok(Type::Optional(Box::new(element_type(&args[0]).unwrap_or(Type::Any))))
+}
+
+fn first(args: &[Value]) -> Result<Value, SchemaError> {
+    Ok(args[0].elements()?.first().cloned().unwrap_or(Value::Null))
+}
+
+fn members(value: &Value) -> Result<&HashMap<String, Value>, SchemaError> {
+    match value {
+        Value::Map(members) => Ok(members),
+        other => Err(invalid_values(std::slice::from_ref(other)))
+    }
+}
+
+fn keys_signature(args: &[Type]) -> Result<Type, SchemaError> {
+    accepts(args, 1, Some(1), |_, arg| is_map(arg))?;
+
+    Ok(Type::List(Box::new(Type::String)))
+}
+
+// In sorted order.
+fn keys(args: &[Value]) -> Result<Value, SchemaError> {
+    let mut keys: Vec<&String> = members(&args[0])?.keys().collect();
+    keys.sort();
+
+    Ok(Value::List(keys.into_iter().map(|key| Value::Str(key.clone())).collect()))
+}
+
+fn merge_signature(args: &[Type]) -> Result<Type, SchemaError> {
+    accepts(args, 1, None, |_, arg| is_map(arg))?;
+
+    let mut merged = HashMap::new();
+    for arg in args {
+        match arg.base() {
+            Type::Map(members) => merged.extend(members.clone()),
+            _ => return Ok(Type::Any)
+        }
+    }
+
+    Ok(Type::Map(merged))
+}
+
+fn merge(args: &[Value]) -> Result<Value, SchemaError> {
+    let mut merged = HashMap::new();
+    for arg in args {
+        merged.extend(members(arg)?.clone());
+    }
+
+    Ok(Value::Map(merged))
+}
+
+// Which members remain isn't known until the keys are, so every member may be absent.
+fn selection_signature(args: &[Type]) -> Result<Type, SchemaError> {
+    accepts(args, 2, None, |i, arg| match i {
+        0 => is_map(arg),
+        _ => is_text(arg)
+    })?;
+
+    match args[0].base() {
+        Type::Map(members) => Ok(Type::Map(members.iter().map(|(key, member)| {
+            let optional = match member.is_optional() {
+                true => member.clone(),
+                false => Type::Optional(Box::new(member.clone()))
+            };
+
+            (key.clone(), optional)
+        }).collect())),
+        _ => Ok(Type::Any)
+    }
+}
+
+fn select(args: &[Value], keep: bool) -> Result<Value, SchemaError> {
+    let mut named = Vec::new();
+    for arg in &args[1..] {
+        named.push(text(arg)?);
+    }
+
+    let selected = members(&args[0])?.iter()
+        .filter(|(key, _)| named.contains(&key.as_str()) == keep)
+        .map(|(key, member)| (key.clone(), member.clone()))
+        .collect();
+
+    Ok(Value::Map(selected))
+}
+
+fn pick(args: &[Value]) -> Result<Value, SchemaError> {
+    select(args, true)
+}
+
+fn omit(args: &[Value]) -> Result<Value, SchemaError> {
+    select(args, false)
+}
+
+fn check_signature(args: &[Type]) -> Result<Type, SchemaError> {
+    accepts(args, 1, Some(1), |_, _| true)?;
+
+    Ok(Type::Bool)
+}
+
+fn is_null(args: &[Value]) -> Result<Value, SchemaError> {
+    Ok(Value::Bool(args[0] == Value::Null))
+}
+
+fn is_bool(args: &[Value]) -> Result<Value, SchemaError> {
+    Ok(Value::Bool(matches!(args[0], Value::Bool(_))))
+}
+
+fn is_number_value(args: &[Value]) -> Result<Value, SchemaError> {
+    Ok(Value::Bool(args[0].as_f64().is_some()))
+}
+
+fn is_string(args: &[Value]) -> Result<Value, SchemaError> {
+    Ok(Value::Bool(matches!(args[0], Value::Str(_))))
+}
+
+fn is_list(args: &[Value]) -> Result<Value, SchemaError> {
+    Ok(Value::Bool(matches!(args[0], Value::List(_))))
+}
+
+fn is_map_value(args: &[Value]) -> Result<Value, SchemaError> {
+    Ok(Value::Bool(matches!(args[0], Value::Map(_))))
+}
+
+fn cast_signature(args: &[Type], to: Type) -> Result<Type, SchemaError> {
+    accepts(args, 1, Some(1), |_, arg| !matches!(arg.base(), Type::List(_) | Type::Map(_) | Type::Union(_, _)))?;
+
+    Ok(to)
+}
+
+fn to_string(args: &[Value]) -> Result<Value, SchemaError> {
+    Ok(Value::Str(display(&args[0])?))
+}
+
+// Truncating any fraction.
+fn to_int(args: &[Value]) -> Result<Value, SchemaError> {
+    let invalid = || invalid_values(args);
+
+    let integer = match &args[0] {
+        Value::Str(string) => string.trim().parse::<i64>().map_err(|_| invalid())?.into(),
+        Value::Float64(value) if value.is_finite() && value.abs() < 9.3e18 => value.trunc() as i128,
+        Value::Decimal(value) => value.mantissa() / 10i128.checked_pow(value.scale()).ok_or_else(invalid)?,
+        other => other.as_integer().ok_or_else(invalid)?
+    };
+
+    Ok(Value::Int64(i64::try_from(integer).map_err(|_| invalid())?))
+}
+
+fn to_float(args: &[Value]) -> Result<Value, SchemaError> {
+    match &args[0] {
+        Value::Str(string) => string.trim().parse().map(Value::Float64).map_err(|_| invalid_values(args)),
+        other => other.as_f64().map(Value::Float64).ok_or_else(|| invalid_values(args))
+    }
+}
+
+fn to_decimal(args: &[Value]) -> Result<Value, SchemaError> {
+    Ok(Value::Decimal(match &args[0] {
+        Value::Decimal(value) => *value,
+        Value::Str(string) => Decimal::parse(string.trim())?,
+        Value::Float64(value) => Decimal::parse(&value.to_string())?,
+        other => Decimal::new(other.as_integer().ok_or_else(|| invalid_values(args))?, 0)
+    }))
+}
+
+pub(super) fn functions() -> Vec<(&'static str, NativeFunction)> {
+    Vec::from([
+        ("lower", NativeFunction::new(text_signature, lower)),
+        ("upper", NativeFunction::new(text_signature, upper)),
+        ("trim", NativeFunction::new(text_signature, trim)),
+        ("concat", NativeFunction::new(concat_signature, concat)),
+        ("split", NativeFunction::new(split_signature, split)),
+        ("format", NativeFunction::new(format_signature, format)),
+        ("abs", NativeFunction::new(abs_signature, abs)),
+        ("round", NativeFunction::new(round_signature, round)),
+        ("min", NativeFunction::new(extremum_signature, min)),
+        ("max", NativeFunction::new(extremum_signature, max)),
+        ("len", NativeFunction::new(len_signature, len)),
+        ("sum", NativeFunction::new(sum_signature, sum)),
+        ("first", NativeFunction::new(first_signature, first)),
+        ("keys", NativeFunction::new(keys_signature, keys)),
+        ("merge", NativeFunction::new(merge_signature, merge)),
+        ("pick", NativeFunction::new(selection_signature, pick)),
+        ("omit", NativeFunction::new(selection_signature, omit)),
+        ("is_null", NativeFunction::accepting_null(check_signature, is_null)),
+        ("is_bool", NativeFunction::accepting_null(check_signature, is_bool)),
+        ("is_number", NativeFunction::accepting_null(check_signature, is_number_value)),
+        ("is_string", NativeFunction::accepting_null(check_signature, is_string)),
+        ("is_list", NativeFunction::accepting_null(check_signature, is_list)),
+        ("is_map", NativeFunction::accepting_null(check_signature, is_map_value)),
+        ("to_string", NativeFunction::new(|args| cast_signature(args, Type::String), to_string)),
+        ("to_int", NativeFunction::new(|args| cast_signature(args, Type::Int64), to_int)),
+        ("to_float", NativeFunction::new(|args| cast_signature(args, Type::Float64), to_float)),
+        ("to_decimal", NativeFunction::new(|args| cast_signature(args, Type::Decimal), to_decimal))
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::functions::Functions;
+
+    #[test]
+    fn standard_functions() {
+        let functions = Functions::standard();
+        let call = |name: &str, args: &[Value]| functions.call(name, args).unwrap();
+        let strs = |values: &[&str]| Value::List(values.iter().map(|value| Value::str_from(*value)).collect());
+
+        assert_eq!(call("upper", &[Value::str_from("a")]), Value::str_from("A"));
+        assert_eq!(call("split", &[Value::str_from("a,b"), Value::str_from(",")]), strs(&["a", "b"]));
+        assert_eq!(
+            call("format", &[Value::str_from("{} of {}"), Value::Uint32(1), Value::Float64(2.5)]),
+            Value::str_from("1 of 2.5")
+        );
+        assert_eq!(call("lower", &[Value::Null]), Value::Null);
+
+        assert_eq!(call("abs", &[Value::Int32(-3)]), Value::Int32(3));
+        assert_eq!(call("round", &[Value::Float64(2.345), Value::Uint32(2)]), Value::Float64(2.35));
+        assert_eq!(call("round", &[Value::Decimal(Decimal::new(-125, 2)), Value::Uint32(1)]), Value::Decimal(Decimal::new(-13, 1)));
+        assert_eq!(call("max", &[Value::Uint32(2), Value::Float64(2.5), Value::Int32(-1)]), Value::Float64(2.5));
+
+        let numbers = Value::List(Vec::from([Value::Uint32(1), Value::Uint32(2)]));
+        assert_eq!(call("len", std::slice::from_ref(&numbers)), Value::Uint64(2));
+        assert_eq!(call("sum", std::slice::from_ref(&numbers)), Value::Uint32(3));
+        assert_eq!(call("sum", &[Value::List(Vec::new())]), Value::Null);
+        assert_eq!(call("first", &[numbers]), Value::Uint32(1));
+
+        let map = Value::map_from([("a".into(), Value::Uint32(1)), ("b".into(), Value::Uint32(2))]);
+        assert_eq!(call("keys", std::slice::from_ref(&map)), strs(&["a", "b"]));
+        assert_eq!(call("pick", &[map.clone(), Value::str_from("a")]), Value::map_from([("a".into(), Value::Uint32(1))]));
+        assert_eq!(call("omit", &[map.clone(), Value::str_from("a")]), Value::map_from([("b".into(), Value::Uint32(2))]));
+        assert_eq!(
+            call("merge", &[map, Value::map_from([("a".into(), Value::Null)])]).lookup("a").unwrap(),
+            Value::Null
+        );
+
+        assert_eq!(call("is_null", &[Value::Null]), Value::Bool(true));
+        assert_eq!(call("to_int", &[Value::str_from(" 42 ")]), Value::Int64(42));
+        assert_eq!(call("to_int", &[Value::Float64(-2.7)]), Value::Int64(-2));
+        assert_eq!(call("to_decimal", &[Value::str_from("1.50")]), Value::Decimal(Decimal::new(150, 2)));
+        assert!(functions.call("to_int", &[Value::str_from("x")]).is_err());
+        assert!(functions.call("missing", &[]).is_err());
+
+        // Calls are checked against the signature rather than trusting the caller.
+        for name in ["lower", "upper", "trim", "abs", "min", "max", "sum", "len"] {
+            assert!(functions.call(name, &[]).is_err(), "{} with no arguments", name);
+        }
+        assert!(functions.call("upper", &[Value::str_from("a"), Value::str_from("b")]).is_err());
+        assert!(functions.call("abs", &[Value::str_from("-1")]).is_err());
+        for mixed in [[Value::str_from("a"), Value::Uint32(1)], [Value::Uint32(1), Value::str_from("a")]] {
+            assert!(functions.call("sum", &[Value::List(Vec::from(mixed))]).is_err());
+        }
+
+        assert_eq!(
+            functions.result_type("lower", &[Type::Optional(Box::new(Type::String))]).unwrap(),
+            Type::Optional(Box::new(Type::String))
+        );
+        assert_eq!(functions.result_type("is_null", &[Type::Optional(Box::new(Type::String))]).unwrap(), Type::Bool);
+        assert!(functions.result_type("upper", &[Type::Uint32]).is_err());
+        assert!(functions.result_type("sum", &[Type::List(Box::new(Type::String))]).is_err());
+    }
+}
@@ -0,0 +1,649 @@
+// Validation of values against types, collecting every violation rather than stopping at
+// the first so that callers (API consumers especially) can fix a document in one pass.
+use std::collections::HashMap;
+
+use super::errors::SchemaError;
+use super::primitives::{Type, Value};
+use super::scalars::Decimal;
+
+// A single problem with a value, located by a JSON pointer (RFC 6901) from the root of
+// the validated document.
+#[derive(Debug, Clone)]
+pub struct Violation {
+    path: String,
+    // None if the value shouldn't be there at all.
+    expected: Option<Type>,
+    // None if the value is absent or null.
+    actual: Option<Type>,
+    // The tag of the union variant the value was checked as, if it's within one.
+    variant: Option<String>,
+    cause: SchemaError
+}
+
+impl Violation {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn expected(&self) -> Option<&Type> {
+        self.expected.as_ref()
+    }
+
+    pub fn actual(&self) -> Option<&Type> {
+        self.actual.as_ref()
+    }
+
+    pub fn variant(&self) -> Option<&str> {
+        self.variant.as_deref()
+    }
+
+    pub fn cause(&self) -> &SchemaError {
+        &self.cause
+    }
+
+    fn message(&self) -> String {
+        match &self.cause {
+            SchemaError::MissingKey(_) => "required member is missing".into(),
+            SchemaError::UnexpectedKey(_) => "member isn't allowed".into(),
+            SchemaError::UnknownVariant(tag) => format!("unknown variant {:?}", tag),
+            SchemaError::ConstraintViolated(constraint) => {
+                let (key, bound) = constraint.to_member();
+
+                format!("violates constraint {} ({:?})", key, bound)
+            },
+            SchemaError::InvalidType(_, _) => "value has the wrong type".into(),
+            other => format!("{:?}", other)
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        let name = |typ: Option<&Type>| match typ {
+            Some(typ) => type_name(typ),
+            None => Value::Null
+        };
+
+        let mut members = Vec::from([
+            ("path".into(), Value::str_from(self.path.as_str())),
+            ("expected".into(), name(self.expected())),
+            ("actual".into(), name(self.actual())),
+            ("message".into(), Value::str_from(self.message()))
+        ]);
+        if let Some(variant) = self.variant() {
+            members.push(("variant".into(), Value::str_from(variant)));
+        }
+
+        Value::Map(members.into_iter().collect())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    violations: Vec<Violation>
+}
+
+impl ValidationReport {
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    // The report as data, for returning to whoever supplied the document.
+    pub fn to_value(&self) -> Value {
+        Value::map_from([(
+            "violations".into(),
+            Value::List(self.violations.iter().map(Violation::to_value).collect())
+        )])
+    }
+
+    fn push(&mut self, path: &[String], expected: Option<&Type>, value: Option<&Value>, cause: SchemaError) {
+        self.violations.push(Violation {
+            path: pointer(path),
+            expected: expected.cloned(),
+            actual: value.and_then(shallow_type),
+            variant: None,
+            cause
+        });
+    }
+}
+
+// The name of a type in the definition notation, e.g. "string" or "list".
+fn type_name(typ: &Type) -> Value {
+    typ.to_value().lookup("$type").unwrap_or(Value::Null)
+}
+
+fn pointer(path: &[String]) -> String {
+    path.iter()
+        .map(|segment| format!("/{}", segment.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+// The type of a value without descending into its members.
+fn shallow_type(value: &Value) -> Option<Type> {
+    Some(match value {
+        Value::Null => return None,
+        Value::List(_) => Type::List(Box::new(Type::Any)),
+        Value::Map(_) => Type::Map(Default::default()),
+        scalar => scalar.try_into().ok()?
+    })
+}
+
+// How validation treats map members the type doesn't declare, and whether values may be
+// converted to the declared type. In every mode, decimals, bytes, timestamps and uuids
+// are also accepted as their string encodings, which is how formats like JSON carry them.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ValidationMode {
+    // Undeclared members are accepted and kept.
+    #[default]
+    Lenient,
+    // Undeclared members are violations.
+    Strict,
+    // Undeclared members are dropped.
+    Strip,
+    // Like Lenient, but values are converted to the declared type where that is lossless,
+    // e.g. Uint32(5) to Int32(5) or Float64(5.0).
+    Coerce
+}
+
+impl ValidationMode {
+    pub fn parse_from_value(value: Value) -> Result<Self, SchemaError> {
+        let name: String = value.try_into()?;
+
+        Ok(match name.as_str() {
+            "lenient" => Self::Lenient,
+            "strict" => Self::Strict,
+            "strip" => Self::Strip,
+            "coerce" => Self::Coerce,
+            _ => return Err(SchemaError::InvalidDefinition(format!("unknown validation mode {}", name)))
+        })
+    }
+}
+
+// Convert a scalar to the given scalar type if that loses nothing.
+fn coerce_scalar(typ: &Type, value: &Value) -> Option<Value> {
+    let integer = value.as_integer().or_else(|| match value {
+        Value::Float64(float) if float.fract() == 0.0 && float.abs() < 2f64.powi(63) => Some(*float as i128),
+        _ => None
+    });
+
+    match (typ, value) {
+        (Type::Int32, _) => Some(Value::Int32(integer?.try_into().ok()?)),
+        (Type::Uint32, _) => Some(Value::Uint32(integer?.try_into().ok()?)),
+        (Type::Int64, _) => Some(Value::Int64(integer?.try_into().ok()?)),
+        (Type::Uint64, _) => Some(Value::Uint64(integer?.try_into().ok()?)),
+        // Integers beyond 2^53 aren't exactly representable.
+        (Type::Float64, _) => match value.as_integer()? {
+            integer if integer.abs() <= 1 << 53 => Some(Value::Float64(integer as f64)),
+            _ => None
+        },
+        // The shortest decimal that reads back as the same float, e.g. 0.1 rather than the
+        // 55 digits of its binary value. Floats needing more than a decimal holds aren't.
+        (Type::Decimal, Value::Float64(float)) if float.is_finite() => Some(Value::Decimal(Decimal::parse(&float.to_string()).ok()?)),
+        (Type::Decimal, _) => Some(Value::Decimal(Decimal::new(value.as_integer()?, 0))),
+        _ => None
+    }
+}
+
+impl Type {
+    pub fn validate(&self, value: &Value) -> Result<(), SchemaError> {
+        self.normalize(value, ValidationMode::Lenient).map(|_| ())
+    }
+
+    // Validate a value in the given mode, returning it completed and normalized: generated
+    // members are filled in, and members are converted or dropped as the mode says.
+    pub fn normalize(&self, value: &Value, mode: ValidationMode) -> Result<Value, SchemaError> {
+        self.normalize_with(value, mode, true)
+    }
+
+    // Like normalize, for values that were normalized before: computed members that are
+    // present keep their value instead of being computed again.
+    pub fn renormalize(&self, value: &Value, mode: ValidationMode) -> Result<Value, SchemaError> {
+        self.normalize_with(value, mode, false)
+    }
+
+    fn normalize_with(&self, value: &Value, mode: ValidationMode, recompute: bool) -> Result<Value, SchemaError> {
+        let mut report = ValidationReport::default();
+        let normalized = self.check(value, mode, recompute, &mut Vec::new(), &mut report);
+
+        match report.is_empty() {
+            true => Ok(normalized),
+            false => Err(SchemaError::Invalid(report))
+        }
+    }
+
+    pub fn report(&self, value: &Value) -> ValidationReport {
+        match self.validate(value) {
+            Err(SchemaError::Invalid(report)) => report,
+            _ => ValidationReport::default()
+        }
+    }
+
+    fn check(
+        &self, value: &Value, mode: ValidationMode, recompute: bool, path: &mut Vec<String>, report: &mut ValidationReport
+    ) -> Value {
+        let invalid_type = |report: &mut ValidationReport, path: &[String]| {
+            let actual = shallow_type(value).unwrap_or(Type::Optional(Box::new(Type::Any)));
+            let cause = SchemaError::InvalidType(self.clone().into(), actual.into());
+
+            report.push(path, Some(self), Some(value), cause);
+        };
+
+        match (self, value) {
+            (Self::Any, _) | (Self::Optional(_), Value::Null) => value.clone(),
+            (Self::Optional(inner_t), _) => inner_t.check(value, mode, recompute, path, report),
+            (Self::Computed(inner_t, _), _) if !recompute && *value != Value::Null => {
+                inner_t.check(value, mode, recompute, path, report)
+            },
+            (Self::Default(inner_t, generator), Value::Null) | (Self::Computed(inner_t, generator), _) => {
+                inner_t.check(&generator.generate(), mode, recompute, path, report)
+            },
+            (Self::Default(inner_t, _), _) => inner_t.check(value, mode, recompute, path, report),
+            (Self::Ref(reference), _) => match reference.resolve() {
+                Ok(target_t) => target_t.check(value, mode, recompute, path, report),
+                Err(cause) => {
+                    report.push(path, Some(self), Some(value), cause);
+
+                    value.clone()
+                }
+            },
+            (Self::Constrained(inner_t, constraints), _) => {
+                let before = report.violations.len();
+                let value = inner_t.check(value, mode, recompute, path, report);
+
+                // Constraints only apply to values of the right shape, and not to nulls
+                // admitted by an inner Optional.
+                if report.violations.len() > before || value == Value::Null {
+                    return value;
+                }
+
+                for constraint in constraints.iter() {
+                    if let Err(cause) = constraint.check(&value) {
+                        report.push(path, Some(self), Some(&value), cause);
+                    }
+                }
+
+                value
+            },
+            (Self::List(inner_t), Value::List(members)) => {
+                let mut normalized = Vec::with_capacity(members.len());
+
+                for (i, member) in members.iter().enumerate() {
+                    path.push(i.to_string());
+                    normalized.push(inner_t.check(member, mode, recompute, path, report));
+                    path.pop();
+                }
+
+                Value::List(normalized)
+            },
+            (Self::Map(inner_ts), Value::Map(members)) => {
+                let mut normalized = HashMap::with_capacity(members.len());
+
+                let mut keys: Vec<&String> = inner_ts.keys().chain(members.keys()).collect();
+                keys.sort();
+                keys.dedup();
+
+                for key in keys {
+                    path.push(key.clone());
+                    match (inner_ts.get(key), members.get(key)) {
+                        (Some(inner_t), Some(member)) => {
+                            normalized.insert(key.clone(), inner_t.check(member, mode, recompute, path, report));
+                        },
+                        (Some(inner_t), None) if inner_t.is_generated() => {
+                            normalized.insert(key.clone(), inner_t.check(&Value::Null, mode, recompute, path, report));
+                        },
+                        (Some(inner_t), None) if !inner_t.is_optional() => {
+                            report.push(path, Some(inner_t), None, SchemaError::MissingKey(key.clone()));
+                        },
+                        (Some(_), None) => {},
+                        (None, Some(member)) => {
+                            match mode {
+                                ValidationMode::Strict => report.push(
+                                    path, None, Some(member), SchemaError::UnexpectedKey(key.clone())
+                                ),
+                                ValidationMode::Strip => {},
+                                _ => {
+                                    normalized.insert(key.clone(), member.clone());
+                                }
+                            }
+                        },
+                        (None, None) => unreachable!()
+                    }
+                    path.pop();
+                }
+
+                Value::Map(normalized)
+            },
+            (Self::Enum(options), Value::Str(string)) => {
+                if !options.contains(string) {
+                    report.push(path, Some(self), Some(value), SchemaError::UnknownVariant(string.clone()));
+                }
+
+                value.clone()
+            },
+            (Self::Union(discriminator, variants), Value::Map(members)) => {
+                path.push(discriminator.clone());
+
+                let variant_t = match members.get(discriminator) {
+                    Some(Value::Str(tag)) => {
+                        let variant_t = variants.get(tag);
+                        if variant_t.is_none() {
+                            let tag_t = self.member(discriminator).ok();
+                            let cause = SchemaError::UnknownVariant(tag.clone());
+
+                            report.push(path, tag_t.as_ref(), Some(&members[discriminator]), cause);
+                        }
+
+                        variant_t
+                    },
+                    Some(other) => {
+                        let actual = shallow_type(other).unwrap_or(Type::Any);
+                        let cause = SchemaError::InvalidType(Type::String.into(), actual.into());
+
+                        report.push(path, Some(&Type::String), Some(other), cause);
+                        None
+                    },
+                    None => {
+                        report.push(path, Some(&Type::String), None, SchemaError::MissingKey(discriminator.clone()));
+                        None
+                    }
+                };
+
+                path.pop();
+
+                let variant_t = match variant_t {
+                    Some(variant_t) => variant_t,
+                    None => return value.clone()
+                };
+
+                // The variant describes the members other than the discriminator.
+                let mut rest = members.clone();
+                let tag = rest.remove(discriminator).unwrap_or(Value::Null);
+
+                let before = report.violations.len();
+                let checked = variant_t.check(&Value::Map(rest), mode, recompute, path, report);
+
+                // Violations in nested unions keep their own, innermost variant.
+                if let Value::Str(tag) = &tag {
+                    for violation in report.violations[before..].iter_mut().filter(|violation| violation.variant.is_none()) {
+                        violation.variant = Some(tag.clone());
+                    }
+                }
+
+                match checked {
+                    Value::Map(mut normalized) => {
+                        normalized.insert(discriminator.clone(), tag);
+
+                        Value::Map(normalized)
+                    },
+                    other => other
+                }
+            },
+            (Self::List(_) | Self::Map(_) | Self::Enum(_) | Self::Union(_, _), _) => {
+                invalid_type(report, path);
+
+                value.clone()
+            },
+            (scalar_t, _) => {
+                if shallow_type(value).is_some_and(|value_t| scalar_t.primitive_eq(&value_t)) {
+                    return value.clone();
+                }
+
+                if let (Self::Decimal | Self::Bytes | Self::Timestamp | Self::Uuid, Value::Str(string)) = (scalar_t, value) {
+                    match scalar_t.decode_str(string) {
+                        Ok(decoded) => return decoded,
+                        Err(cause) => {
+                            report.push(path, Some(self), Some(value), cause);
+
+                            return value.clone();
+                        }
+                    }
+                }
+
+                if mode == ValidationMode::Coerce {
+                    if let Some(coerced) = coerce_scalar(scalar_t, value) {
+                        return coerced;
+                    }
+                }
+
+                invalid_type(report, path);
+
+                value.clone()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payment_type() -> Type {
+        Type::Union("kind".into(), HashMap::from([
+            ("card".into(), Type::Map(HashMap::from([
+                ("number".into(), Type::String),
+                ("note".into(), Type::Optional(Box::new(Type::String)))
+            ]))),
+            ("bank".into(), Type::Map(HashMap::from([
+                ("iban".into(), Type::String),
+                ("note".into(), Type::String)
+            ])))
+        ]))
+    }
+
+    fn causes(typ: &Type, value: Value) -> Vec<(String, SchemaError)> {
+        causes_in(typ, &value, ValidationMode::Lenient)
+    }
+
+    fn causes_in(typ: &Type, value: &Value, mode: ValidationMode) -> Vec<(String, SchemaError)> {
+        let report = match typ.normalize(value, mode) {
+            Err(SchemaError::Invalid(report)) => report,
+            _ => ValidationReport::default()
+        };
+
+        report.violations().iter()
+            .map(|violation| (violation.path().to_owned(), violation.cause().clone()))
+            .collect()
+    }
+
+    #[test]
+    fn validate_optional() {
+        let typ = Type::Map(HashMap::from([
+            ("name".into(), Type::String),
+            ("nickname".into(), Type::Optional(Box::new(Type::String)))
+        ]));
+
+        assert!(typ.validate(&Value::map_from([
+            ("name".into(), Value::str_from("james"))
+        ])).is_ok());
+        assert!(typ.validate(&Value::map_from([
+            ("name".into(), Value::str_from("james")),
+            ("nickname".into(), Value::Null)
+        ])).is_ok());
+        assert!(typ.validate(&Value::map_from([
+            ("name".into(), Value::Null)
+        ])).is_err());
+        assert!(typ.validate(&Value::map_from([
+            ("name".into(), Value::str_from("james")),
+            ("nickname".into(), Value::Uint32(1))
+        ])).is_err());
+
+        // Elements may be null, but the list itself may not.
+        let list_t = Type::List(Box::new(Type::Optional(Box::new(Type::Uint32))));
+        assert!(list_t.validate(&Value::List(Vec::from([Value::Uint32(1), Value::Null]))).is_ok());
+        assert!(list_t.validate(&Value::Null).is_err());
+    }
+
+    #[test]
+    fn validate_any() {
+        let typ = Type::Map(HashMap::from([
+            ("extra".into(), Type::Any)
+        ]));
+
+        assert!(Type::Any.validate(&Value::Null).is_ok());
+        assert!(typ.validate(&Value::map_from([
+            ("extra".into(), Value::List(Vec::from([Value::Uint32(1), Value::str_from("a")])))
+        ])).is_ok());
+        assert!(typ.validate(&Value::map_from([])).is_ok());
+        assert!(Type::Optional(Box::new(Type::Any)).validate(&Value::Bool(true)).is_ok());
+        assert!(Type::List(Box::new(Type::Any)).validate(&Value::List(Vec::from([
+            Value::Null, Value::Uint32(1), Value::map_from([])
+        ]))).is_ok());
+        assert!(Type::List(Box::new(Type::Any)).validate(&Value::str_from("a")).is_err());
+    }
+
+    #[test]
+    fn validate_enum() {
+        let typ = Type::Enum(Vec::from(["red".into(), "blue".into()]));
+
+        assert!(typ.validate(&Value::str_from("red")).is_ok());
+        assert!(matches!(causes(&typ, Value::str_from("green"))[..], [(_, SchemaError::UnknownVariant(_))]));
+        assert!(matches!(causes(&typ, Value::Uint32(1))[..], [(_, SchemaError::InvalidType(_, _))]));
+    }
+
+    #[test]
+    fn validate_union() {
+        let typ = payment_type();
+
+        assert!(typ.validate(&Value::map_from([
+            ("kind".into(), Value::str_from("card")),
+            ("number".into(), Value::str_from("4242"))
+        ])).is_ok());
+
+        match &causes(&typ, Value::map_from([
+            ("kind".into(), Value::str_from("bank")),
+            ("iban".into(), Value::str_from("DE00"))
+        ]))[..] {
+            [(path, SchemaError::MissingKey(_))] => assert_eq!(path, "/note"),
+            other => panic!("unexpected {:?}", other)
+        }
+
+        // Violations within a variant say which one it was.
+        let report = typ.report(&Value::map_from([
+            ("kind".into(), Value::str_from("bank")),
+            ("iban".into(), Value::Uint32(0)),
+            ("note".into(), Value::str_from("rent"))
+        ]));
+        assert_eq!(report.violations()[0].variant(), Some("bank"));
+        assert_eq!(report.violations()[0].to_value().lookup("variant").unwrap(), Value::str_from("bank"));
+        assert_eq!(typ.report(&Value::map_from([("kind".into(), Value::str_from("cash"))])).violations()[0].variant(), None);
+
+        assert!(matches!(
+            causes(&typ, Value::map_from([("kind".into(), Value::str_from("cash"))]))[..],
+            [(_, SchemaError::UnknownVariant(_))]
+        ));
+    }
+
+    #[test]
+    fn report_all_violations() {
+        let typ = Type::Map(HashMap::from([
+            ("name".into(), Type::String),
+            ("tags".into(), Type::List(Box::new(Type::String))),
+            ("a/b".into(), Type::Bool)
+        ]));
+
+        let report = typ.report(&Value::map_from([
+            ("tags".into(), Value::List(Vec::from([
+                Value::str_from("ok"), Value::Uint32(2), Value::Bool(false)
+            ]))),
+            ("a/b".into(), Value::Null)
+        ]));
+
+        let paths: Vec<&str> = report.violations().iter().map(Violation::path).collect();
+        assert_eq!(paths, ["/a~1b", "/name", "/tags/1", "/tags/2"]);
+
+        assert_eq!(report.violations()[2].expected(), Some(&Type::String));
+        assert_eq!(report.violations()[2].actual(), Some(&Type::Uint32));
+        assert_eq!(report.violations()[1].actual(), None);
+
+        assert_eq!(report.to_value().lookup("violations").unwrap().index(2).unwrap(), Value::map_from([
+            ("path".into(), Value::str_from("/tags/1")),
+            ("expected".into(), Value::str_from("string")),
+            ("actual".into(), Value::str_from("uint32")),
+            ("message".into(), Value::str_from("value has the wrong type"))
+        ]));
+    }
+
+    #[test]
+    fn validation_modes() {
+        let typ = Type::Map(HashMap::from([
+            ("count".into(), Type::Int32),
+            ("ratio".into(), Type::Float64)
+        ]));
+
+        let value = Value::map_from([
+            ("count".into(), Value::Uint32(5)),
+            ("ratio".into(), Value::Uint32(1)),
+            ("extra".into(), Value::Bool(true))
+        ]);
+
+        assert!(typ.normalize(&value, ValidationMode::Lenient).is_err());
+        assert_eq!(typ.normalize(&value, ValidationMode::Coerce).unwrap(), Value::map_from([
+            ("count".into(), Value::Int32(5)),
+            ("ratio".into(), Value::Float64(1.0)),
+            ("extra".into(), Value::Bool(true))
+        ]));
+        assert!(typ.normalize(&Value::map_from([("count".into(), Value::Float64(0.5))]), ValidationMode::Coerce).is_err());
+
+        let value = Value::map_from([
+            ("count".into(), Value::Int32(5)),
+            ("ratio".into(), Value::Float64(0.5)),
+            ("extra".into(), Value::Bool(true))
+        ]);
+
+        assert!(typ.normalize(&value, ValidationMode::Lenient).is_ok());
+        match &causes_in(&typ, &value, ValidationMode::Strict)[..] {
+            [(path, SchemaError::UnexpectedKey(_))] => assert_eq!(path, "/extra"),
+            other => panic!("unexpected {:?}", other)
+        }
+        assert_eq!(typ.normalize(&value, ValidationMode::Strip).unwrap(), Value::map_from([
+            ("count".into(), Value::Int32(5)),
+            ("ratio".into(), Value::Float64(0.5))
+        ]));
+
+        let coerce = |typ: &Type, value: Value| typ.normalize(&value, ValidationMode::Coerce).ok();
+        assert_eq!(coerce(&Type::Decimal, Value::Float64(19.99)), Some(Value::Decimal(Decimal::new(1999, 2))));
+        assert_eq!(coerce(&Type::Decimal, Value::Float64(-3.0)), Some(Value::Decimal(Decimal::new(-3, 0))));
+        assert_eq!(coerce(&Type::Decimal, Value::Float64(1e-300)), None);
+        assert_eq!(coerce(&Type::Decimal, Value::Float64(f64::NAN)), None);
+        assert!(Type::Decimal.normalize(&Value::Float64(0.5), ValidationMode::Lenient).is_err());
+
+        // The discriminator of a union isn't an unknown member of its variant.
+        let value = Value::map_from([
+            ("kind".into(), Value::str_from("card")),
+            ("number".into(), Value::str_from("4242"))
+        ]);
+        assert_eq!(payment_type().normalize(&value, ValidationMode::Strict).unwrap(), value);
+    }
+
+    #[test]
+    fn encoded_scalars() {
+        use super::super::scalars::{Timestamp, Uuid};
+
+        let typ = Type::Map(HashMap::from([
+            ("at".into(), Type::Timestamp),
+            ("id".into(), Type::Uuid),
+            ("amount".into(), Type::Decimal),
+            ("data".into(), Type::Bytes)
+        ]));
+        let value = Value::map_from([
+            ("at".into(), Value::str_from("2022-11-03T18:04:05Z")),
+            ("id".into(), Value::str_from("67e55044-10b1-426f-9247-bb680e5fe0c8")),
+            ("amount".into(), Value::str_from("-1024.50")),
+            ("data".into(), Value::str_from("aGVsbG8="))
+        ]);
+
+        assert_eq!(typ.normalize(&value, ValidationMode::Lenient).unwrap(), Value::map_from([
+            ("at".into(), Value::Timestamp(Timestamp::from_micros(1_667_498_645_000_000))),
+            ("id".into(), Value::Uuid(Uuid::from_u128(0x67e5504410b1426f9247bb680e5fe0c8))),
+            ("amount".into(), Value::Decimal(Decimal::new(-102450, 2))),
+            ("data".into(), Value::Bytes(Vec::from(*b"hello")))
+        ]));
+
+        match &causes(&Type::Timestamp, Value::str_from("yesterday"))[..] {
+            [(path, SchemaError::InvalidCast(Type::Timestamp))] => assert_eq!(path, ""),
+            other => panic!("unexpected {:?}", other)
+        }
+    }
+}
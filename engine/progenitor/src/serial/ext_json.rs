@@ -1,207 +1,237 @@
 use std::collections::HashMap;
-use std::str::Chars;
 
-use crate::schema::Value;
+use crate::schema::{Value, encode_base64};
 
 use super::errors::SerialError;
 use super::value::SerialValue;
 use super::format::SerialFormat;
 
+// An RFC 8259 parser. Numbers without a fraction or exponent are integers of the
+// narrowest variant among Uint32, Uint64 (unsigned) and Int32, Int64 (negative), and
+// integers beyond those are errors. Anything else is a Float64, and an error if it
+// isn't finite. Duplicate object keys take the last value.
 struct JsonParser<'ps> {
-    position: u32,
-    input: Chars<'ps>,
-    peeked: Option<Option<char>>
+    input: &'ps [u8],
+    position: usize,
+    depth: usize
 }
 
-// TODO: Null.
-// TODO: Malicious input protection.
-// All parse methods assume the invariant that the first token they're going to consume
-// is valid for the given to-be-parsed type.
+// Bounds the recursion of nested arrays and objects.
+const MAX_DEPTH: usize = 512;
+
 impl<'ps> JsonParser<'ps> {
     fn new(input: &'ps str) -> Self {
         Self {
-            input: input.chars(),
+            input: input.as_bytes(),
             position: 0,
-            peeked: None
+            depth: 0
         }
     }
 
     fn error(&self, message: &'static str) -> SerialError {
-        SerialError::Parse(format!("Syntax error at position {}: {}", self.position, message).into())
+        SerialError::Parse(format!("Syntax error at position {}: {}", self.position, message))
     }
 
-    fn raw_consume(&mut self, incl_ws: bool) -> Option<char> {
-        loop {
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.input.get(self.position) {
             self.position += 1;
-
-            match self.input.next() {
-                Some(token) => {
-                    if incl_ws || (token != ' ' && token != '\n' && token != '\r') {
-                        return Some(token);
-                    }
-                },
-                None => return None
-            };
         }
     }
 
-    fn peek_optional(&mut self) -> Option<char> {
-        if self.peeked.is_none() {
-            self.peeked = Some(self.raw_consume(false));
-        }
+    // The next significant byte, without consuming it.
+    fn peek(&mut self) -> Result<u8, SerialError> {
+        self.skip_whitespace();
 
-        self.peeked.unwrap()
+        self.input.get(self.position).copied().ok_or_else(|| self.error("Unterminated value"))
     }
 
-    fn peek(&mut self) -> Result<char, SerialError> {
-        self.peek_optional().ok_or_else(|| self.error("Unterminated value"))
-    }
+    fn next_raw(&mut self) -> Result<u8, SerialError> {
+        let token = self.input.get(self.position).copied().ok_or_else(|| self.error("Unterminated value"))?;
+        self.position += 1;
 
-    fn next_optional(&mut self) -> Option<char> {
-        if let Some(next) = self.peeked {
-            self.peeked = None;
+        Ok(token)
+    }
 
-            next
+    fn expect(&mut self, expected: u8, message: &'static str) -> Result<(), SerialError> {
+        match self.peek()? == expected {
+            true => {
+                self.position += 1;
+                Ok(())
+            },
+            false => Err(self.error(message))
         }
-        else {
-            self.raw_consume(false)
+    }
+
+    fn parse_literal(&mut self, literal: &'static str, value: Value) -> Result<Value, SerialError> {
+        match self.input[self.position..].starts_with(literal.as_bytes()) {
+            true => {
+                self.position += literal.len();
+                Ok(value)
+            },
+            false => Err(self.error("Invalid literal"))
         }
     }
 
-    fn next(&mut self) -> Result<char, SerialError> {
-        self.next_optional().ok_or_else(|| self.error("Unterminated value"))
+    fn parse_hex_escape(&mut self) -> Result<u32, SerialError> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = (self.next_raw()? as char).to_digit(16).ok_or_else(|| self.error("Invalid \\u escape"))?;
+
+            code = (code << 4) | digit;
+        }
+
+        Ok(code)
     }
 
     fn raw_parse_string(&mut self) -> Result<String, SerialError> {
-        let mut is_escape = false;
-        // TODO: Adaptive capacity.
-        let mut parsed = String::with_capacity(32);
-
-        self.next()?;
+        self.expect(b'"', "Expected string")?;
 
+        let mut parsed: Vec<u8> = Vec::new();
         loop {
-            // Careful, peeking above here will break things.
-            let token = self.raw_consume(true).ok_or_else(|| self.error("Unterminated string"))?;
-
-            if token == '"' && !is_escape {
-                break;
+            match self.next_raw().map_err(|_| self.error("Unterminated string"))? {
+                b'"' => break,
+                b'\\' => {
+                    let unescaped = match self.next_raw()? {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let code = match self.parse_hex_escape()? {
+                                // A UTF-16 surrogate pair.
+                                high @ 0xd800..=0xdbff => {
+                                    if self.next_raw()? != b'\\' || self.next_raw()? != b'u' {
+                                        return Err(self.error("Unpaired surrogate"));
+                                    }
+
+                                    match self.parse_hex_escape()? {
+                                        low @ 0xdc00..=0xdfff => 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00),
+                                        _ => return Err(self.error("Unpaired surrogate"))
+                                    }
+                                },
+                                0xdc00..=0xdfff => return Err(self.error("Unpaired surrogate")),
+                                code => code
+                            };
+
+                            char::from_u32(code).ok_or_else(|| self.error("Invalid \\u escape"))?
+                        },
+                        _ => return Err(self.error("Invalid escape"))
+                    };
+
+                    let mut encoded = [0; 4];
+                    parsed.extend_from_slice(unescaped.encode_utf8(&mut encoded).as_bytes());
+                },
+                0x00..=0x1f => return Err(self.error("Unescaped control character in string")),
+                token => parsed.push(token)
             }
+        }
 
-            if token == '\\' {
-                is_escape = !is_escape;
-            }
+        // The input is a str and escapes decode to whole chars, so this can't fail.
+        String::from_utf8(parsed).map_err(|_| self.error("Invalid string encoding"))
+    }
 
-            parsed.push(token);
+    fn skip_digits(&mut self) -> usize {
+        let start = self.position;
+        while let Some(b'0'..=b'9') = self.input.get(self.position) {
+            self.position += 1;
         }
 
-        Ok(parsed)
+        self.position - start
     }
 
     fn parse_number(&mut self) -> Result<Value, SerialError> {
-        let mut whole = 0;
-        let mut negative = false;
-        let mut fractional_digits = 0;
-        let mut fractional = None;
-
-        let first_token = self.peek()?;
-        if first_token == '-' {
-            negative = true;
-
-            self.next()?;
+        let start = self.position;
+        let negative = self.input[self.position] == b'-';
+        if negative {
+            self.position += 1;
         }
 
-        loop {
-            let token = self.next()?;
-
-            if token == '.' {
-                if fractional.is_some() {
-                    return Err(self.error("Repeat decimal token"));
-                }
-
-                fractional = Some(0);
-                continue;
-            }
+        // No leading zeros, and at least one digit in each part.
+        let leading_zero = self.input.get(self.position) == Some(&b'0');
+        match self.skip_digits() {
+            0 => return Err(self.error("Expected digit")),
+            digits if leading_zero && digits > 1 => return Err(self.error("Leading zero")),
+            _ => {}
+        }
 
-            let part = token.to_digit(10)
-                .ok_or_else(|| self.error("Invalid token for number"))?;
+        let mut integral = true;
+        if self.input.get(self.position) == Some(&b'.') {
+            self.position += 1;
+            integral = false;
 
-            if let Some(current) = fractional {
-                fractional = Some((current * 10) + part);
-                fractional_digits += 1;
-            }
-            else {
-                whole = (whole * 10) + part;
+            if self.skip_digits() == 0 {
+                return Err(self.error("Expected digit after ."));
             }
+        }
+        if let Some(b'e' | b'E') = self.input.get(self.position) {
+            self.position += 1;
+            integral = false;
 
-            let maybe_next_token = self.peek_optional();
-            if let Some(next_token) = maybe_next_token {
-                if next_token != '.' && !next_token.is_numeric() {
-                    break;
-                }
+            if let Some(b'+' | b'-') = self.input.get(self.position) {
+                self.position += 1;
             }
-            else {
-                break;
+            if self.skip_digits() == 0 {
+                return Err(self.error("Expected digit in exponent"));
             }
         }
 
-        // Return value resolved as:
-        // Float64 if it has a fraction part,
-        // Int32 if it is explicitly signed (negative),
-        // Uint32 otherwise.
+        // Only ASCII was consumed.
+        let lexeme = std::str::from_utf8(&self.input[start..self.position]).map_err(|_| self.error("Invalid number"))?;
 
-        if let Some(frac) = fractional {
-            let mut real = whole as f64 + ((frac as f64) / ((10_i32.pow(fractional_digits)) as f64));
-            if negative {
-                real *= -1.0;
-            }
-
-            Ok(Value::Float64(real))
+        if !integral {
+            return match lexeme.parse::<f64>() {
+                Ok(real) if real.is_finite() => Ok(Value::Float64(real)),
+                _ => Err(self.error("Numeric overflow"))
+            };
         }
-        else if negative {
-            match i32::try_from(whole) {
-                Ok(signed) => Ok(Value::Int32(signed * -1)),
-                Err(_) => Err(self.error("Numeric overflow"))
+
+        match negative {
+            true => {
+                let signed: i64 = lexeme.parse().map_err(|_| self.error("Numeric overflow"))?;
+
+                Ok(match i32::try_from(signed) {
+                    Ok(narrow) => Value::Int32(narrow),
+                    Err(_) => Value::Int64(signed)
+                })
+            },
+            false => {
+                let whole: u64 = lexeme.parse().map_err(|_| self.error("Numeric overflow"))?;
+
+                Ok(match u32::try_from(whole) {
+                    Ok(narrow) => Value::Uint32(narrow),
+                    Err(_) => Value::Uint64(whole)
+                })
             }
         }
-        else {
-            Ok(Value::Uint32(whole))
-        }
-    }
-
-    fn parse_string(&mut self) -> Result<Value, SerialError> {
-        Ok(Value::Str(self.raw_parse_string()?))
     }
 
     fn parse_object(&mut self) -> Result<Value, SerialError> {
-        // TODO: Adaptive capacity.
-        let mut result = HashMap::with_capacity(8);
+        let mut result = HashMap::new();
 
-        self.next()?;
+        self.expect(b'{', "Expected object")?;
+        if self.peek()? == b'}' {
+            self.position += 1;
+            return Ok(Value::Map(result));
+        }
 
         loop {
-            let token = self.peek()?;
-
-            if token == '}' {
-                self.next()?;
-                break;
-            }
-
             let key = self.raw_parse_string()?;
-
-            if self.next()? != ':' {
-                return Err(self.error("Object key without trailing :"))                
-            }
-
-            let value = self.parse()?;
+            self.expect(b':', "Object key without trailing :")?;
+            let value = self.parse_value()?;
 
             result.insert(key, value);
 
-            let next_token = self.peek()?;
-            if next_token == ',' {
-                self.next()?;
-            }
-            else if next_token != '}' {
-                return Err(self.error("Invalid token after object value position"));
+            match self.peek()? {
+                b',' => self.position += 1,
+                b'}' => {
+                    self.position += 1;
+                    break;
+                },
+                _ => return Err(self.error("Invalid token after object value position"))
             }
         }
 
@@ -209,151 +239,301 @@ impl<'ps> JsonParser<'ps> {
     }
 
     fn parse_array(&mut self) -> Result<Value, SerialError> {
-        // TODO: Adaptive capacity.
-        let mut result = Vec::with_capacity(8);
+        let mut result = Vec::new();
 
-        self.next()?;
+        self.expect(b'[', "Expected array")?;
+        if self.peek()? == b']' {
+            self.position += 1;
+            return Ok(Value::List(result));
+        }
 
         loop {
-            let token = self.peek()?;
+            result.push(self.parse_value()?);
 
-            if token == ']' {
-                self.next()?;
-                break;
-            }
-
-            result.push(self.parse()?);
-            
-            let next_token = self.peek()?;
-            if next_token == ',' {
-                self.next()?;
-            }
-            else if next_token != ']' {
-                return Err(self.error("Invalid token after array element position"));
+            match self.peek()? {
+                b',' => self.position += 1,
+                b']' => {
+                    self.position += 1;
+                    break;
+                },
+                _ => return Err(self.error("Invalid token after array element position"))
             }
         }
 
         Ok(Value::List(result))
     }
 
+    fn parse_value(&mut self) -> Result<Value, SerialError> {
+        match self.peek()? {
+            b'{' | b'[' => {
+                self.depth += 1;
+                if self.depth > MAX_DEPTH {
+                    return Err(self.error("Nested too deeply"));
+                }
+
+                let nested = match self.input[self.position] {
+                    b'{' => self.parse_object(),
+                    _ => self.parse_array()
+                };
+                self.depth -= 1;
+
+                nested
+            },
+            b'"' => Ok(Value::Str(self.raw_parse_string()?)),
+            b'-' | b'0'..=b'9' => self.parse_number(),
+            b't' => self.parse_literal("true", Value::Bool(true)),
+            b'f' => self.parse_literal("false", Value::Bool(false)),
+            b'n' => self.parse_literal("null", Value::Null),
+            _ => Err(self.error("Invalid token in value position"))
+        }
+    }
+
+    // A whole document: one value, with nothing but whitespace after it.
     fn parse(&mut self) -> Result<Value, SerialError> {
-        let next_token = self.peek()?;
+        let value = self.parse_value()?;
 
-        if next_token == '-' || next_token.is_numeric() {
-            return self.parse_number();
+        self.skip_whitespace();
+        match self.position == self.input.len() {
+            true => Ok(value),
+            false => Err(self.error("Trailing characters after value"))
         }
+    }
+}
 
-        match next_token {
-            '{' => self.parse_object(),
-            '[' => self.parse_array(),
-            '"' => self.parse_string(),
-            _ => Err(self.error("Invalid token in value position"))
+// How floats without a JSON representation (NaN and the infinities) are written.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum NanPolicy {
+    // Writing fails.
+    #[default]
+    Error,
+    // As null.
+    Null,
+    // As the strings "NaN", "Infinity" and "-Infinity".
+    Str
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum KeyOrder {
+    #[default]
+    Sorted,
+    // Map iteration order, which skips sorting but differs between writes.
+    Unordered
+}
+
+// Written as a map of any of:
+//  pretty: bool, indenting nested values two spaces per level
+//  key_order: "sorted" or "unordered"
+//  ascii_only: bool, escaping everything beyond ASCII as \u escapes
+//  nan: "error", "null" or "string"
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct JsonOptions {
+    pub pretty: bool,
+    pub key_order: KeyOrder,
+    pub ascii_only: bool,
+    pub nan: NanPolicy
+}
+
+impl JsonOptions {
+    pub fn parse_from_value(value: Value) -> Result<Self, SerialError> {
+        Self::default().overlay(value)
+    }
+
+    // These options with those set in a value replaced.
+    pub fn overlay(mut self, value: Value) -> Result<Self, SerialError> {
+        let members = match value {
+            Value::Map(members) => members,
+            Value::Null => return Ok(self),
+            _ => return Err(SerialError::Format("JSON options must be a map".into()))
+        };
+
+        for (key, member) in members.into_iter() {
+            let invalid = || SerialError::Format(format!("Invalid JSON option {}", key));
+            let flag = |member: Value| bool::try_from(member).map_err(|_| invalid());
+            let name = |member: Value| String::try_from(member).map_err(|_| invalid());
+
+            match key.as_str() {
+                "pretty" => self.pretty = flag(member)?,
+                "ascii_only" => self.ascii_only = flag(member)?,
+                "key_order" => self.key_order = match name(member)?.as_str() {
+                    "sorted" => KeyOrder::Sorted,
+                    "unordered" => KeyOrder::Unordered,
+                    _ => return Err(invalid())
+                },
+                "nan" => self.nan = match name(member)?.as_str() {
+                    "error" => NanPolicy::Error,
+                    "null" => NanPolicy::Null,
+                    "string" => NanPolicy::Str,
+                    _ => return Err(invalid())
+                },
+                _ => return Err(SerialError::Format(format!("Unknown JSON option {}", key)))
+            }
         }
+
+        Ok(self)
     }
 }
 
 struct JsonWriter<'wr> {
     output: String,
-    input: &'wr Value
+    input: &'wr Value,
+    options: JsonOptions,
+    depth: usize
 }
 
-// TODO: Formatting.
 impl<'wr> JsonWriter<'wr> {
-    fn new(input: &'wr Value) -> Self {
+    fn new(input: &'wr Value, options: JsonOptions) -> Self {
         Self {
             input,
+            options,
+            depth: 0,
             // TODO: Intelligent capacity. sizeof?
             output: String::with_capacity(128)
         }
     }
 
     fn raw_append(&mut self, string: &str) {
-        self.output.push_str(&string)
+        self.output.push_str(string)
+    }
+
+    // Line break and indentation before a nested value, when pretty.
+    fn append_break(&mut self) {
+        if self.options.pretty {
+            self.output.push('\n');
+            for _ in 0..self.depth {
+                self.raw_append("  ");
+            }
+        }
     }
 
     fn append_string(&mut self, string: &str) {
         self.raw_append("\"");
 
-        let mut did_escape = false;
         for token in string.chars() {
-            // TODO: Pretty sure it's more complex than this.
-            if token == '"' {
-                self.output.push('\\');
+            match token {
+                '"' => self.raw_append("\\\""),
+                '\\' => self.raw_append("\\\\"),
+                '\n' => self.raw_append("\\n"),
+                '\r' => self.raw_append("\\r"),
+                '\t' => self.raw_append("\\t"),
+                '\u{8}' => self.raw_append("\\b"),
+                '\u{c}' => self.raw_append("\\f"),
+                '\u{0}'..='\u{1f}' => self.raw_append(&format!("\\u{:04x}", token as u32)),
+                _ if token.is_ascii() || !self.options.ascii_only => self.output.push(token),
+                // Beyond the basic multilingual plane as a surrogate pair.
+                _ => {
+                    for unit in token.encode_utf16(&mut [0; 2]) {
+                        self.raw_append(&format!("\\u{:04x}", unit));
+                    }
+                }
             }
-
-            self.output.push(token);
-
-            did_escape = token == '\\';
         }
 
-        if did_escape {
-            self.raw_append("\\");
-        }
         self.raw_append("\"");
     }
 
-    fn append_object(&mut self, contents: &HashMap<String, Value>) {
-        self.raw_append("{");
-
+    fn append_object(&mut self, contents: &HashMap<String, Value>) -> Result<(), SerialError> {
         let mut keys = contents.keys().collect::<Vec<&String>>();
-        keys.sort();
-
-        let max = contents.len() - 1;
-        let mut idx = 0;
-        for key in keys {
-            let value = contents.get(key).unwrap();
-
-            self.append_string(key);
-            self.raw_append(":");
-            self.append_value(value);
+        if self.options.key_order == KeyOrder::Sorted {
+            keys.sort();
+        }
 
-            if idx != max {
+        self.raw_append("{");
+        self.depth += 1;
+        for (idx, key) in keys.into_iter().enumerate() {
+            if idx > 0 {
                 self.raw_append(",");
             }
-            idx += 1;
+            self.append_break();
+
+            self.append_string(key);
+            self.raw_append(if self.options.pretty { ": " } else { ":" });
+            self.append_value(&contents[key])?;
         }
+        self.depth -= 1;
 
+        if !contents.is_empty() {
+            self.append_break();
+        }
         self.raw_append("}");
+
+        Ok(())
     }
 
-    fn append_array(&mut self, contents: &Vec<Value>) {
+    fn append_array(&mut self, contents: &[Value]) -> Result<(), SerialError> {
         self.raw_append("[");
-
-        let mut idx = 0;
-        for value in contents.iter() {
-            self.append_value(value);
-
-            if idx != contents.len() - 1 {
+        self.depth += 1;
+        for (idx, value) in contents.iter().enumerate() {
+            if idx > 0 {
                 self.raw_append(",");
             }
-            idx += 1;
+            self.append_break();
+
+            self.append_value(value)?;
         }
+        self.depth -= 1;
 
+        if !contents.is_empty() {
+            self.append_break();
+        }
         self.raw_append("]");
+
+        Ok(())
     }
 
-    fn append_value(&mut self, value: &Value) {
+    fn append_float(&mut self, num: f64) -> Result<(), SerialError> {
+        // Debug keeps a fraction on integral floats, so they read back as floats.
+        if num.is_finite() {
+            self.raw_append(&format!("{:?}", num));
+            return Ok(());
+        }
+
+        match self.options.nan {
+            NanPolicy::Error => return Err(SerialError::Format(format!("{} has no JSON representation", num))),
+            NanPolicy::Null => self.raw_append("null"),
+            NanPolicy::Str => self.append_string(match num {
+                _ if num.is_nan() => "NaN",
+                _ if num > 0.0 => "Infinity",
+                _ => "-Infinity"
+            })
+        }
+
+        Ok(())
+    }
+
+    fn append_value(&mut self, value: &Value) -> Result<(), SerialError> {
         match value {
             Value::Null => self.raw_append("null"),
             // TODO: What's faster than format!?
             Value::Bool(flag) => self.raw_append(&format!("{}", flag)),
             Value::Uint32(num) => self.raw_append(&format!("{}", num)),
             Value::Int32(num) => self.raw_append(&format!("{}", num)),
-            Value::Float64(num) => self.raw_append(&format!("{}", num)),
+            Value::Uint64(num) => self.raw_append(&format!("{}", num)),
+            Value::Int64(num) => self.raw_append(&format!("{}", num)),
+            Value::Float64(num) => self.append_float(*num)?,
             Value::Str(string) => self.append_string(string),
-            Value::Map(contents) => self.append_object(contents),
-            Value::List(contents) => self.append_array(contents)
+            // No native JSON representation for these, so they use their string encodings.
+            Value::Decimal(num) => self.append_string(&num.to_string()),
+            Value::Bytes(data) => self.append_string(&encode_base64(data)),
+            Value::Timestamp(instant) => self.append_string(&instant.to_string()),
+            Value::Uuid(id) => self.append_string(&id.to_string()),
+            Value::Map(contents) => self.append_object(contents)?,
+            Value::List(contents) => self.append_array(contents)?
         };
+
+        Ok(())
     }
 
     fn write(mut self) -> Result<String, SerialError> {
-        self.append_value(self.input);
+        self.append_value(self.input)?;
 
         Ok(self.output)
     }
 }
 
-pub struct JsonSerial;
+#[derive(Default)]
+pub struct JsonSerial {
+    options: JsonOptions
+}
 
 impl SerialFormat for JsonSerial {
     fn parse(&self, serial: SerialValue) -> Result<Value, SerialError> {
@@ -366,7 +546,15 @@ impl SerialFormat for JsonSerial {
     }
 
     fn write(&self, value: &Value) -> Result<SerialValue, SerialError> {
-        let string = JsonWriter::new(value).write()?;
+        let string = JsonWriter::new(value, self.options).write()?;
+
+        Ok(SerialValue::from_string(string))
+    }
+
+    // Options given, e.g. by a write_resp archetype, override those the format was
+    // registered with.
+    fn write_with(&self, value: &Value, options: Value) -> Result<SerialValue, SerialError> {
+        let string = JsonWriter::new(value, self.options.overlay(options)?).write()?;
 
         Ok(SerialValue::from_string(string))
     }
@@ -374,13 +562,19 @@ impl SerialFormat for JsonSerial {
 
 impl JsonSerial {
     pub fn new() -> Self {
-        Self { }
+        Self::default()
+    }
+
+    // Default write options, which writes with options of their own override.
+    pub fn with_options(options: JsonOptions) -> Self {
+        Self { options }
     }
 }
 
-// TODO: Parse fail successfully tests.
 #[cfg(test)]
 mod tests {
+    use crate::schema::{Decimal, Timestamp, Uuid};
+
     use super::*;
 
     #[test]
@@ -391,6 +585,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_null() {
+        assert_eq!(
+            JsonParser::new("{\"a\": null}").parse(),
+            Ok(Value::Map(HashMap::from([
+                ("a".to_owned(), Value::Null)
+            ])))
+        );
+
+        assert!(JsonParser::new("nul").parse().is_err());
+    }
+
+    #[test]
+    fn parse_wide_number() {
+        assert_eq!(
+            JsonParser::new("[4294967296, -2147483649, 18446744073709551615]").parse(),
+            Ok(Value::List(Vec::from([
+                Value::Uint64(4294967296),
+                Value::Int64(-2147483649),
+                Value::Uint64(u64::MAX)
+            ])))
+        );
+
+        assert!(JsonParser::new("18446744073709551616").parse().is_err());
+    }
+
     #[test]
     fn parse_string() {
         assert_eq!(
@@ -445,10 +665,111 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_literals() {
+        assert_eq!(
+            JsonParser::new("[true,\tfalse, null]").parse(),
+            Ok(Value::List(Vec::from([Value::Bool(true), Value::Bool(false), Value::Null])))
+        );
+    }
+
+    #[test]
+    fn parse_escapes() {
+        assert_eq!(
+            JsonParser::new(r#""a\"b\\c\/d\b\f\n\r\t\u00e9\ud83d\ude00""#).parse(),
+            Ok(Value::Str("a\"b\\c/d\u{8}\u{c}\n\r\té😀".into()))
+        );
+    }
+
+    #[test]
+    fn parse_exponent() {
+        assert_eq!(
+            JsonParser::new("[1e3, -2.5E-2, 0.1e+1]").parse(),
+            Ok(Value::List(Vec::from([Value::Float64(1000.0), Value::Float64(-0.025), Value::Float64(1.0)])))
+        );
+
+        assert!(JsonParser::new("1e400").parse().is_err());
+    }
+
+    // Cases from JSONTestSuite: y_ must be accepted and n_ rejected.
+    #[test]
+    fn conformance() {
+        let accepted = [
+            ("y_array_empty", "[]"),
+            ("y_array_with_leading_space", " [1]"),
+            ("y_array_with_trailing_space", "[2] "),
+            ("y_array_arraysWithSpaces", "[[]   ]"),
+            ("y_number_0e+1", "[0e+1]"),
+            ("y_number_minus_zero", "[-0]"),
+            ("y_number_real_capital_e_neg_exp", "[1E-2]"),
+            ("y_number_simple_real", "[123.456789]"),
+            ("y_object_empty", "{}"),
+            ("y_object_empty_key", "{\"\":0}"),
+            ("y_object_duplicated_key", "{\"a\":\"b\",\"a\":\"c\"}"),
+            ("y_string_accepted_surrogate_pair", "[\"\\uD801\\udc37\"]"),
+            ("y_string_allowed_escapes", "[\"\\\"\\\\\\/\\b\\f\\n\\r\\t\"]"),
+            ("y_string_null_escape", "[\"\\u0000\"]"),
+            ("y_string_utf8", "[\"€𝄞\"]"),
+            ("y_structure_lonely_false", "false"),
+            ("y_structure_lonely_string", "\"asd\""),
+            ("y_structure_whitespace_array", " [] "),
+            ("y_structure_trailing_newline", "[\"a\"]\n")
+        ];
+        for (name, input) in accepted {
+            assert!(JsonParser::new(input).parse().is_ok(), "{} should be accepted", name);
+        }
+
+        let rejected = [
+            ("n_array_extra_comma", "[\"\",]"),
+            ("n_array_just_comma", "[,]"),
+            ("n_array_unclosed", "[\"\""),
+            ("n_array_comma_after_close", "[\"\"],"),
+            ("n_incomplete_true", "[tru]"),
+            ("n_number_+1", "[+1]"),
+            ("n_number_.2e-3", "[.2e-3]"),
+            ("n_number_2.e3", "[2.e3]"),
+            ("n_number_with_leading_zero", "[012]"),
+            ("n_number_neg_int_starting_with_zero", "[-012]"),
+            ("n_number_0_capital_E", "[0E]"),
+            ("n_number_infinity", "[Infinity]"),
+            ("n_number_NaN", "[NaN]"),
+            ("n_number_hex_1_digit", "[0x1]"),
+            ("n_object_trailing_comma", "{\"id\":0,}"),
+            ("n_object_missing_colon", "{\"a\" b}"),
+            ("n_object_non_string_key", "{1:1}"),
+            ("n_object_single_quote", "{'a':0}"),
+            ("n_string_escaped_ctrl_char_tab", "[\"\\\t\"]"),
+            ("n_string_invalid_backslash_esc", "[\"\\a\"]"),
+            ("n_string_incomplete_surrogate_escape_invalid", "[\"\\uD800\\uD800\\x\"]"),
+            ("n_string_1_surrogate_then_escape", "[\"\\uD800\\\"]"),
+            ("n_string_unescaped_newline", "[\"new\nline\"]"),
+            ("n_string_unescaped_tab", "[\"\t\"]"),
+            ("n_string_no_quotes_with_bad_escape", "[\\n]"),
+            ("n_structure_trailing_#", "{\"a\":\"b\"}#{}"),
+            ("n_structure_double_array", "[][]"),
+            ("n_structure_object_with_trailing_garbage", "{\"a\": true} \"x\""),
+            ("n_structure_UTF8_BOM_no_data", "\u{feff}"),
+            ("n_structure_no_data", ""),
+            ("n_structure_whitespace_formfeed", "[\u{c}]"),
+            ("n_structure_capitalized_True", "[True]")
+        ];
+        for (name, input) in rejected {
+            assert!(JsonParser::new(input).parse().is_err(), "{} should be rejected", name);
+        }
+
+        // Deep nesting is refused rather than overflowing the stack.
+        assert!(JsonParser::new(&"[".repeat(100000)).parse().is_err());
+        assert!(JsonParser::new(&format!("{}{}", "[".repeat(500), "]".repeat(500))).parse().is_ok());
+
+        // Lone surrogates have no UTF-8 encoding.
+        assert!(JsonParser::new("\"\\ud800\"").parse().is_err());
+        assert!(JsonParser::new("\"\\udc00\"").parse().is_err());
+    }
+
     #[test]
     fn write_null() {
         assert_eq!(
-            JsonWriter::new(&Value::Null).write(),
+            JsonWriter::new(&Value::Null, JsonOptions::default()).write(),
             Ok("null".into())
         );
     }
@@ -456,30 +777,47 @@ mod tests {
     #[test]
     fn write_number() {
         assert_eq!(
-            JsonWriter::new(&Value::Uint32(4)).write(),
+            JsonWriter::new(&Value::Uint32(4), JsonOptions::default()).write(),
             Ok("4".into())
         );
 
         assert_eq!(
-            JsonWriter::new(&Value::Int32(-4)).write(),
+            JsonWriter::new(&Value::Int32(-4), JsonOptions::default()).write(),
             Ok("-4".into())
         );
 
         assert_eq!(
-            JsonWriter::new(&Value::Float64(-4.2)).write(),
+            JsonWriter::new(&Value::Float64(-4.2), JsonOptions::default()).write(),
             Ok("-4.2".into())
         );
     }
 
+    #[test]
+    fn write_encoded_scalars() {
+        assert_eq!(
+            JsonWriter::new(&Value::List(Vec::from([
+                Value::Uint64(u64::MAX),
+                Value::Decimal(Decimal::new(-102450, 2)),
+                Value::Bytes(Vec::from(*b"hello")),
+                Value::Timestamp(Timestamp::from_micros(1_667_498_645_250_000)),
+                Value::Uuid(Uuid::from_u128(0x67e5504410b1426f9247bb680e5fe0c8))
+            ])), JsonOptions::default()).write(),
+            Ok(concat!(
+                "[18446744073709551615,\"-1024.50\",\"aGVsbG8=\",",
+                "\"2022-11-03T18:04:05.250000Z\",\"67e55044-10b1-426f-9247-bb680e5fe0c8\"]"
+            ).into())
+        );
+    }
+
     #[test]
     fn write_string() {
         assert_eq!(
-            JsonWriter::new(&Value::Str("foo \"bar\"".into())).write(),
+            JsonWriter::new(&Value::Str("foo \"bar\"".into()), JsonOptions::default()).write(),
             Ok("\"foo \\\"bar\\\"\"".into())
         );
 
         assert_eq!(
-            JsonWriter::new(&Value::Str("foo \"bar\"\\".into())).write(),
+            JsonWriter::new(&Value::Str("foo \"bar\"\\".into()), JsonOptions::default()).write(),
             Ok("\"foo \\\"bar\\\"\\\\\"".into())
         );
     }
@@ -491,7 +829,7 @@ mod tests {
                 Value::Str("foo".into()),
                 Value::Float64(-2.2),
                 Value::Null
-            ]))).write(),
+            ])), JsonOptions::default()).write(),
             Ok("[\"foo\",-2.2,null]".into())
         );
     }
@@ -505,8 +843,104 @@ mod tests {
                     Value::Null,
                     Value::Int32(-5)
                 ])))
-            ]))).write(),
+            ])), JsonOptions::default()).write(),
             Ok("{\"a\":[null,-5],\"foo\":\"bar\"}".into())
         );
     }
+
+    #[test]
+    fn write_escapes() {
+        let written = JsonWriter::new(&Value::str_from("a\\\n\t\u{1}\u{7f}é😀"), JsonOptions::default()).write();
+        assert_eq!(written, Ok("\"a\\\\\\n\\t\\u0001\u{7f}é😀\"".into()));
+
+        let ascii = JsonOptions { ascii_only: true, ..JsonOptions::default() };
+        assert_eq!(
+            JsonWriter::new(&Value::str_from("é😀"), ascii).write(),
+            Ok("\"\\u00e9\\ud83d\\ude00\"".into())
+        );
+    }
+
+    #[test]
+    fn write_empty() {
+        assert_eq!(
+            JsonWriter::new(&Value::List(Vec::from([Value::Map(HashMap::new()), Value::List(Vec::new())])), JsonOptions::default()).write(),
+            Ok("[{},[]]".into())
+        );
+    }
+
+    #[test]
+    fn write_pretty() {
+        let value = Value::Map(HashMap::from([
+            ("b".into(), Value::List(Vec::from([Value::Uint32(1), Value::Map(HashMap::new())]))),
+            ("a".into(), Value::Float64(1.0))
+        ]));
+
+        assert_eq!(
+            JsonWriter::new(&value, JsonOptions { pretty: true, ..JsonOptions::default() }).write(),
+            Ok("{\n  \"a\": 1.0,\n  \"b\": [\n    1,\n    {}\n  ]\n}".into())
+        );
+    }
+
+    #[test]
+    fn write_nan() {
+        let value = Value::List(Vec::from([Value::Float64(f64::NAN), Value::Float64(f64::NEG_INFINITY)]));
+        let with_policy = |nan| JsonWriter::new(&value, JsonOptions { nan, ..JsonOptions::default() }).write();
+
+        assert!(with_policy(NanPolicy::Error).is_err());
+        assert_eq!(with_policy(NanPolicy::Null), Ok("[null,null]".into()));
+        assert_eq!(with_policy(NanPolicy::Str), Ok("[\"NaN\",\"-Infinity\"]".into()));
+    }
+
+    #[test]
+    fn options() {
+        let options = JsonOptions::parse_from_value(Value::map_from([
+            ("pretty".into(), Value::Bool(true)),
+            ("key_order".into(), Value::str_from("unordered")),
+            ("nan".into(), Value::str_from("null"))
+        ])).unwrap();
+        assert_eq!(options, JsonOptions { pretty: true, key_order: KeyOrder::Unordered, ascii_only: false, nan: NanPolicy::Null });
+
+        let overlaid = options.overlay(Value::map_from([("pretty".into(), Value::Bool(false))])).unwrap();
+        assert_eq!(overlaid, JsonOptions { pretty: false, ..options });
+
+        assert!(JsonOptions::parse_from_value(Value::map_from([("nan".into(), Value::str_from("zero"))])).is_err());
+        assert!(JsonOptions::parse_from_value(Value::map_from([("indent".into(), Value::Uint32(2))])).is_err());
+    }
+
+    #[test]
+    fn write_with_options() {
+        let value = Value::List(Vec::from([Value::Float64(f64::NAN), Value::Map(HashMap::new())]));
+        let serial = JsonSerial::with_options(JsonOptions { nan: NanPolicy::Null, ..JsonOptions::default() });
+        let written = |serial: SerialValue| String::from_utf8(serial.try_into_bytes().unwrap().to_vec()).unwrap();
+
+        assert_eq!(written(serial.write(&value).unwrap()), "[null,{}]");
+
+        // Options given override the format's own, and the rest are kept.
+        let pretty = Value::map_from([("pretty".into(), Value::Bool(true))]);
+        assert_eq!(written(serial.write_with(&value, pretty).unwrap()), "[\n  null,\n  {}\n]");
+
+        let strict = Value::map_from([("nan".into(), Value::str_from("error"))]);
+        assert!(serial.write_with(&value, strict).is_err());
+        assert!(serial.write_with(&value, Value::str_from("pretty")).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let value = Value::Map(HashMap::from([
+            ("s".into(), Value::str_from("\"\\/\u{0}\u{1f}\n ü 😀 \\")),
+            ("n".into(), Value::List(Vec::from([
+                Value::Float64(1.0), Value::Float64(1e300), Value::Float64(-2.5e-8), Value::Int64(i64::MIN), Value::Uint64(u64::MAX)
+            ]))),
+            ("e".into(), Value::Map(HashMap::new()))
+        ]));
+
+        for options in [
+            JsonOptions::default(),
+            JsonOptions { pretty: true, key_order: KeyOrder::Unordered, ascii_only: true, nan: NanPolicy::Error }
+        ] {
+            let written = JsonWriter::new(&value, options).write().unwrap();
+
+            assert_eq!(JsonParser::new(&written).parse(), Ok(value.clone()), "{}", written);
+        }
+    }
 }
@@ -6,4 +6,10 @@ use super::value::SerialValue;
 pub trait SerialFormat {
     fn parse(&self, serial: SerialValue) -> Result<Value, SerialError>;
     fn write(&self, value: &Value) -> Result<SerialValue, SerialError>;
+
+    // Writes with format specific options, e.g. from an archetype. Formats without any
+    // ignore them.
+    fn write_with(&self, value: &Value, _options: Value) -> Result<SerialValue, SerialError> {
+        self.write(value)
+    }
 }
@@ -12,5 +12,5 @@ pub use value::SerialValue;
 pub use format::SerialFormat;
 
 pub mod ext {
-    pub use super::ext_json::JsonSerial;
+    pub use super::ext_json::{JsonSerial, JsonOptions, KeyOrder, NanPolicy};
 }
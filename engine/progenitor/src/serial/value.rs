@@ -4,7 +4,7 @@ use super::errors::SerialError;
 
 // TODO: First class stream, maybe even async.
 // Intermediate container for serial data.
-#[derive(Clone)] // TODO: No!
+#[derive(Clone, Debug)] // TODO: No!
 pub enum SerialValue {
     Buffer(Bytes)
 }
@@ -1,6 +1,6 @@
 use async_trait::async_trait;
 
-use crate::schema::{Value, Condition, Mutation};
+use crate::schema::{Value, Type, Condition, Mutation};
 
 use super::errors::StoreError;
 
@@ -10,7 +10,8 @@ where
     Self: Sync + Send
 {
     async fn load(&self, filter: Option<Condition>, offset: usize, limit: Option<usize>) -> Result<Vec<Value>, StoreError>;
-    async fn update(&self, filter: Condition, update: &Mutation) -> Result<usize, StoreError>;
+    // Nothing is updated unless every updated value is of the schema.
+    async fn update(&self, filter: Condition, update: &Mutation, schema: &Type) -> Result<usize, StoreError>;
     async fn delete(&self, filter: Condition) -> Result<usize, StoreError>;
     // TODO: Will need a return value.
     async fn insert(&self, data: Vec<Value>) -> Result<(), StoreError>;
@@ -3,7 +3,7 @@ use std::sync::{Mutex, MutexGuard};
 
 use async_trait::async_trait;
 
-use crate::schema::{Value, Condition, Mutation};
+use crate::schema::{Value, Type, Condition, Mutation, ValidationMode};
 
 use super::errors::StoreError;
 use super::driver::StoreDriver;
@@ -87,13 +87,13 @@ impl StoreDriver for MemStore {
         Ok(found)
     }
 
-    async fn update(&self, filter: Condition, update: &Mutation) -> Result<usize, StoreError> {
+    async fn update(&self, filter: Condition, update: &Mutation, schema: &Type) -> Result<usize, StoreError> {
         let mut data = self.internal_mem()?;
 
         let mut updates: Vec<(usize, Value)> = Vec::new();
         for (i, item) in data.as_slice().into_iter().enumerate() {
             if filter.evaluate(item)? {
-                updates.push((i, update.execute(item)?));
+                updates.push((i, schema.renormalize(&update.execute(item)?, ValidationMode::Lenient)?));
             }
         }
 
@@ -115,8 +115,9 @@ impl StoreDriver for MemStore {
             }
         }
 
+        // Remove from the back so earlier removals don't shift the later indices.
         let removal_count = removals.len();
-        for i in removals {
+        for i in removals.into_iter().rev() {
             data.remove(i);
         }
 
@@ -132,3 +133,46 @@ impl StoreDriver for MemStore {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::task::{Context, Poll, Waker};
+    use std::time::Duration;
+
+    use crate::schema::{Generator, Path};
+    use crate::store::Store;
+
+    use super::*;
+
+    // The memory store never waits on anything, so a single poll completes it.
+    fn complete<T>(future: impl Future<Output = T>) -> T {
+        match std::pin::pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("store is waiting")
+        }
+    }
+
+    #[test]
+    fn update_keeps_computed() {
+        let schema = Type::Map([
+            ("name".into(), Type::String),
+            ("created".into(), Type::Computed(Box::new(Type::Timestamp), Generator::Now))
+        ].into());
+        let store = Store::new(schema, Box::new(MemStore::new("update_keeps_computed")));
+
+        complete(store.put(Value::map_from([("name".into(), Value::str_from("a"))]))).unwrap();
+        let created = complete(store.query().all()).unwrap()[0].lookup("created").unwrap();
+        assert!(matches!(created, Value::Timestamp(_)));
+
+        // So that computing the timestamp again would give a different one.
+        std::thread::sleep(Duration::from_millis(2));
+
+        let rename = Mutation::Set(Path::parse("name").unwrap(), Value::str_from("b"));
+        assert_eq!(complete(store.update(Condition::True, &rename)).unwrap(), 1);
+
+        let updated = complete(store.query().all()).unwrap().remove(0);
+        assert_eq!(updated.lookup("name").unwrap(), Value::str_from("b"));
+        assert_eq!(updated.lookup("created").unwrap(), created);
+    }
+}
@@ -1,6 +1,6 @@
 use async_trait::async_trait;
 
-use crate::schema::{Value, Type, Condition};
+use crate::schema::{Value, Type, Condition, Mutation, ValidationMode};
 
 use super::errors::StoreError;
 use super::driver::StoreDriver;
@@ -37,6 +37,16 @@ impl Store {
     }
 
     pub async fn put(&self, item: Value) -> Result<(), StoreError> {
-        self.driver.insert(Vec::from([item.clone()])).await
+        let item = self.schema.normalize(&item, ValidationMode::Lenient)?;
+
+        self.driver.insert(Vec::from([item])).await
+    }
+
+    // Apply a mutation to every item the filter matches, returning how many there were.
+    pub async fn update(&self, filter: Condition, mutation: &Mutation) -> Result<usize, StoreError> {
+        filter.validate(&self.schema)?;
+        mutation.validate(&self.schema)?;
+
+        self.driver.update(filter, mutation, &self.schema).await
     }
 }
@@ -13,7 +13,7 @@ use super::errors::ExecError;
 
 use self::effect::author_effect;
 use self::scribe::Scribe;
-use self::schema::author_schema_fn;
+use self::schema::{author_schema_fn, author_types};
 use self::value::author_value;
 
 pub struct AuthorInput {
@@ -31,6 +31,7 @@ pub fn author(input: AuthorInput) -> Result<Bytes, ExecError> {
     scribe = match archetype.as_str() {
         "value" => author_value(scribe, value)?,
         "type" => author_schema_fn(scribe, name?.try_into()?, value)?,
+        "types" => author_types(scribe, value)?,
         "effect" => author_effect(scribe, name?.try_into()?, value)?,
         _ => return Err(ExecError::Io(format!("invalid archetype {}", archetype)))
     };
@@ -1,7 +1,8 @@
-use progenitor::{Type, Value};
+use progenitor::{Type, Value, Constraint, Format, Generator};
 
 use super::super::errors::ExecError;
 use super::scribe::Scribe;
+use super::value::author_value_elem;
 
 // TODO: Rework to "type", and also not return a function.
 
@@ -43,20 +44,207 @@ fn author_schema_elem(mut scribe: Scribe, typ: &Type) -> Scribe {
                 .tab_out().line()
                 .write("))")
         },
+        Type::Optional(inner) => {
+            scribe = scribe
+                .line()
+                .write_ext("Type", "progenitor")
+                .write("::Optional(")
+                .write_ext("Box", "std::boxed")
+                .write("::new(")
+                .tab_in();
+
+            author_schema_elem(scribe, inner.as_ref())
+                .tab_out().line()
+                .write("))")
+        },
+        Type::Enum(options) => {
+            scribe = scribe
+                .line()
+                .write_ext("Type", "progenitor")
+                .write("::Enum(")
+                .write_ext("Vec", "std::vec")
+                .write("::from([")
+                .tab_in();
+
+            for option in options.iter() {
+                scribe = scribe.line().write(format!("{:?}.into(),", option).as_str());
+            }
+
+            scribe.tab_out().line().write("]))")
+        },
+        Type::Union(discriminator, variants) => {
+            scribe = scribe
+                .line()
+                .write_ext("Type", "progenitor")
+                .write(format!("::Union({:?}.into(), ", discriminator).as_str())
+                .write_ext("HashMap", "std::collections")
+                .write("::from([")
+                .tab_in();
+
+            for (tag, variant_type) in variants.iter() {
+                scribe = scribe
+                    .line().write("(")
+                    .tab_in().line()
+                    .write(format!("{:?}.into(),", tag).as_str());
+
+                scribe = author_schema_elem(scribe, variant_type)
+                    .tab_out().line()
+                    .write("),");
+            }
+
+            scribe.tab_out().line().write("]))")
+        },
+        Type::Constrained(inner, constraints) => {
+            scribe = scribe
+                .line()
+                .write_ext("Type", "progenitor")
+                .write("::Constrained(")
+                .write_ext("Box", "std::boxed")
+                .write("::new(")
+                .tab_in();
+
+            scribe = author_schema_elem(scribe, inner.as_ref())
+                .tab_out().line()
+                .write("), ")
+                .write_ext("Vec", "std::vec")
+                .write("::from([")
+                .tab_in();
+
+            for constraint in constraints.iter() {
+                scribe = author_constraint(scribe, constraint).write(",");
+            }
+
+            scribe.tab_out().line().write("]))")
+        },
+        Type::Default(inner, generator) | Type::Computed(inner, generator) => {
+            let name = match typ {
+                Type::Default(_, _) => "::Default(",
+                _ => "::Computed("
+            };
+
+            scribe = scribe
+                .line()
+                .write_ext("Type", "progenitor")
+                .write(name)
+                .write_ext("Box", "std::boxed")
+                .write("::new(")
+                .tab_in();
+
+            scribe = author_schema_elem(scribe, inner.as_ref())
+                .tab_out().line()
+                .write("), ");
+
+            author_generator(scribe, generator).write(")")
+        },
+        Type::Ref(reference) => {
+            scribe
+                .line()
+                .write_ext("Type", "progenitor")
+                .write("::Ref(")
+                .write_ext("TypeRef", "progenitor")
+                .write(format!("::new({:?}))", reference.name()).as_str())
+        },
+        Type::Any => scribe.line().write("Type::Any"),
         Type::String => scribe.line().write("Type::String"),
         Type::Int32 => scribe.line().write("Type::Int32"),
         Type::Uint32 => scribe.line().write("Type::Uint32"),
+        Type::Int64 => scribe.line().write("Type::Int64"),
+        Type::Uint64 => scribe.line().write("Type::Uint64"),
         Type::Float64 => scribe.line().write("Type::Float64"),
+        Type::Decimal => scribe.line().write("Type::Decimal"),
+        Type::Bytes => scribe.line().write("Type::Bytes"),
+        Type::Timestamp => scribe.line().write("Type::Timestamp"),
+        Type::Uuid => scribe.line().write("Type::Uuid"),
         Type::Bool => scribe.line().write("Type::Bool")
     }
 }
 
+fn author_generator(scribe: Scribe, generator: &Generator) -> Scribe {
+    let scribe = scribe.write_ext("Generator", "progenitor");
+
+    match generator {
+        Generator::Constant(constant) => {
+            author_value_elem(scribe.write("::Constant(").tab_in(), constant)
+                .tab_out().line()
+                .write(")")
+        },
+        Generator::Now => scribe.write("::Now")
+    }
+}
+
+fn author_constraint(mut scribe: Scribe, constraint: &Constraint) -> Scribe {
+    scribe = scribe.line().write_ext("Constraint", "progenitor");
+
+    match constraint {
+        Constraint::Min(bound) | Constraint::Max(bound) => {
+            let name = match constraint {
+                Constraint::Min(_) => "::Min(",
+                _ => "::Max("
+            };
+
+            scribe = scribe.write(name).tab_in();
+
+            author_value_elem(scribe, bound)
+                .tab_out().line()
+                .write(")")
+        },
+        Constraint::MinLength(length) => scribe.write(format!("::MinLength({})", length).as_str()),
+        Constraint::MaxLength(length) => scribe.write(format!("::MaxLength({})", length).as_str()),
+        Constraint::Pattern(pattern) => {
+            scribe
+                .write("::Pattern(")
+                .write_ext("Pattern", "progenitor")
+                .write(format!("::new({:?}).unwrap())", pattern.source()).as_str())
+        },
+        Constraint::Format(format) => {
+            let name = match format {
+                Format::Email => "Email",
+                Format::Url => "Url"
+            };
+
+            scribe
+                .write("::Format(")
+                .write_ext("Format", "progenitor")
+                .write(format!("::{})", name).as_str())
+        },
+        Constraint::Unique => scribe.write("::Unique")
+    }
+}
+
 pub(super) fn author_schema_fn(
-    mut scribe: Scribe, name: String, schema_ex: Value
+    mut scribe: Scribe, name: String, schema_def: Value
 ) -> Result<Scribe, ExecError> {
-    let schema: Type = schema_ex.try_into()?;
+    let schema = Type::parse_from_value(schema_def)?;
 
     scribe = scribe.write("pub ").start_fn(format!("{}_type", name).as_str(), "Type");
     
     Ok(author_schema_elem(scribe, &schema).end_fn())
 }
+
+// Named types are authored as one function each, referring to each other by name, plus a
+// list of them all for the Registry.
+pub(super) fn author_types(mut scribe: Scribe, type_defs: Value) -> Result<Scribe, ExecError> {
+    let mut names: Vec<String> = match &type_defs {
+        Value::Map(members) => members.keys().cloned().collect(),
+        _ => return Err(ExecError::Io("types must be a map of definitions".into()))
+    };
+    names.sort();
+
+    for name in names.iter() {
+        scribe = author_schema_fn(scribe, name.clone(), type_defs.lookup(name.as_str())?)?
+            .line().line();
+    }
+
+    scribe = scribe
+        .write("pub ")
+        .start_fn("types", "Vec<(&'static str, Type)>")
+        .line()
+        .write("Vec::from([")
+        .tab_in();
+
+    for name in names.iter() {
+        scribe = scribe.line().write(format!("({:?}, {}_type()),", name, name).as_str());
+    }
+
+    Ok(scribe.tab_out().line().write("])").end_fn())
+}
@@ -3,7 +3,7 @@ use progenitor::Value;
 use super::super::errors::ExecError;
 use super::scribe::Scribe;
 
-fn author_value_elem(mut scribe: Scribe, value: &Value) -> Scribe {
+pub(super) fn author_value_elem(mut scribe: Scribe, value: &Value) -> Scribe {
     match value {
         Value::Map(members) => {
             scribe = scribe
@@ -43,7 +43,33 @@ fn author_value_elem(mut scribe: Scribe, value: &Value) -> Scribe {
         Value::Str(inner) => scribe.line().write(format!("Value::str_from(\"{}\")", inner).as_str()),
         Value::Int32(inner) => scribe.line().write(format!("Value::Int32({})", inner).as_str()),
         Value::Uint32(inner) => scribe.line().write(format!("Value::Uint32({})", inner).as_str()),
-        Value::Float64(inner) => scribe.line().write(format!("Value::Float64({})", inner).as_str()),
+        Value::Int64(inner) => scribe.line().write(format!("Value::Int64({})", inner).as_str()),
+        Value::Uint64(inner) => scribe.line().write(format!("Value::Uint64({})", inner).as_str()),
+        Value::Float64(inner) => scribe.line().write(format!("Value::Float64({:?})", inner).as_str()),
+        Value::Decimal(inner) => {
+            scribe
+                .line().write("Value::Decimal(")
+                .write_ext("Decimal", "progenitor")
+                .write(format!("::new({}, {}))", inner.mantissa(), inner.scale()).as_str())
+        },
+        Value::Bytes(inner) => {
+            scribe
+                .line().write("Value::Bytes(")
+                .write_ext("Vec", "std::vec")
+                .write(format!("::from({:?}))", inner).as_str())
+        },
+        Value::Timestamp(inner) => {
+            scribe
+                .line().write("Value::Timestamp(")
+                .write_ext("Timestamp", "progenitor")
+                .write(format!("::from_micros({}))", inner.micros()).as_str())
+        },
+        Value::Uuid(inner) => {
+            scribe
+                .line().write("Value::Uuid(")
+                .write_ext("Uuid", "progenitor")
+                .write(format!("::from_u128({:#034x}))", inner.as_u128()).as_str())
+        },
         Value::Bool(inner) => scribe.line().write(format!("Value::Bool({})", inner).as_str()),
         Value::Null => scribe.line().write("Value::Null")
     }
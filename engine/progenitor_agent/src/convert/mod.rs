@@ -0,0 +1,62 @@
+// Convert converts between progenitor schema definitions and JSON Schema documents.
+//
+// Definitions are exchanged as {"schema": <definition>, "types": {<name>: <definition>}},
+// where the named types are optional and can be authored with the "types" archetype.
+use std::collections::HashMap;
+
+use bytes::Bytes;
+
+use progenitor::{SerialFormat, Type, Value};
+use progenitor::ext::JsonSerial;
+
+use super::errors::ExecError;
+
+pub enum ConvertOperation {
+    Import,
+    Export
+}
+
+pub struct ConvertInput {
+    pub op: ConvertOperation,
+    pub value: Value
+}
+
+fn definitions(types: &HashMap<String, Type>) -> Value {
+    Value::Map(types.iter()
+        .map(|(name, typ)| (name.clone(), typ.to_value()))
+        .collect())
+}
+
+pub fn convert(input: ConvertInput) -> Result<Bytes, ExecError> {
+    let output = match input.op {
+        ConvertOperation::Import => {
+            let (schema, types) = Type::parse_json_schema(input.value)?;
+
+            Value::map_from([
+                ("schema".into(), schema.to_value()),
+                ("types".into(), definitions(&types))
+            ])
+        },
+        ConvertOperation::Export => {
+            let schema = Type::parse_from_value(input.value.lookup("schema")?)?;
+
+            let mut types = HashMap::new();
+            if let Ok(Value::Map(type_defs)) = input.value.lookup("types") {
+                for (name, type_def) in type_defs {
+                    types.insert(name, Type::parse_from_value(type_def)?);
+                }
+            }
+
+            // Linked references are exported as $defs.
+            let resolve = |name: &str| types.get(name).cloned();
+            for typ in types.values() {
+                typ.link(&resolve)?;
+            }
+            schema.link(&resolve)?;
+
+            schema.to_json_schema()?
+        }
+    };
+
+    Ok(JsonSerial::new().write(&output)?.try_into_bytes()?)
+}
@@ -1,7 +1,9 @@
 mod errors;
 mod author;
 mod port;
+mod convert;
 
 pub use self::errors::ExecError;
 pub use self::author::author;
 pub use self::port::port;
+pub use self::convert::convert;
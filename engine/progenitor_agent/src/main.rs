@@ -2,6 +2,7 @@ mod cli;
 mod errors;
 mod author;
 mod port;
+mod convert;
 
 use std::process;
 use std::io::{Read, Write, stdin, stdout};
@@ -16,6 +17,7 @@ use self::errors::ExecError;
 use self::cli::{CLIArgs, CLITemplate, CLIVerbTemplate, CLIOptionTemplate};
 use self::author::{author, AuthorInput};
 use self::port::{port, PortInput, PortOperation};
+use self::convert::{convert, ConvertInput, ConvertOperation};
 
 fn cli_template() -> CLITemplate {
     CLITemplate { verbs: vec![
@@ -77,6 +79,30 @@ fn cli_template() -> CLITemplate {
                 }
             ],
             description: "manage cargo repository"
+        },
+        CLIVerbTemplate {
+            verb: "convert",
+            options: vec![
+                CLIOptionTemplate {
+                    key: "op",
+                    key_shorthand: None,
+                    takes_value: true,
+                    description: "'import' (JSON Schema to definition) or 'export' (definition to JSON Schema)"
+                },
+                CLIOptionTemplate {
+                    key: "in",
+                    key_shorthand: Some("i"),
+                    takes_value: true,
+                    description: "file path to read from, or 'stdin'"
+                },
+                CLIOptionTemplate {
+                    key: "out",
+                    key_shorthand: Some("o"),
+                    takes_value: true,
+                    description: "file path to write to, or 'stdout'"
+                }
+            ],
+            description: "convert between schema definitions and JSON Schema"
         }
     ] }
 }
@@ -182,6 +208,25 @@ fn main() {
                 target
             }));
         },
+        "convert" => {
+            let op = match get_required_option!("op", "no operation specified").as_str() {
+                "import" => ConvertOperation::Import,
+                "export" => ConvertOperation::Export,
+                _ => { error_exit!("invalid operation"); }
+            };
+            let input_src = get_required_option!("in", "no input specified");
+            let output_dest = get_required_option!("out", "no output specified");
+
+            let input = handle_result!(read_input(input_src));
+            let value = handle_result!(JsonSerial::new().parse(SerialValue::Buffer(input)));
+
+            let output = handle_result!(convert(ConvertInput {
+                op,
+                value
+            }));
+
+            handle_result!(write_output(output_dest, output));
+        },
         _ => { error_exit!("invalid verb"); }
     }
 }
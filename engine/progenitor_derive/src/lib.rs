@@ -0,0 +1,373 @@
+// Derived conversions between Rust types and progenitor's indirectly represented data.
+//
+// #[derive(Typed)] implements progenitor::{FromValue, IntoValue, Typed}, TryFrom<Value>
+// and Into<Value> for:
+//
+//  structs with named fields, as maps (a Type::Map)
+//  structs with one unnamed field, as that field
+//  enums of unit variants, as strings (a Type::Enum)
+//  other enums of unit and named field variants, as maps with the variant named by a
+//  discriminator member, "type" unless set with #[value(tag = "...")] (a Type::Union)
+//
+// Fields and variants can be renamed with #[value(rename = "...")]. Absent members are
+// null, so they're only accepted for optional fields. Fields written as Vec<u8> or
+// Option<Vec<u8>> are bytes (a Type::Bytes); an alias of Vec<u8> isn't recognised.
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Error, Fields, FieldsNamed, Ident, LitStr};
+
+#[proc_macro_derive(Typed, attributes(value))]
+pub fn derive_typed(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand(&input) {
+        Ok(expanded) => expanded.into(),
+        Err(err) => err.to_compile_error().into()
+    }
+}
+
+#[derive(Default)]
+struct Options {
+    rename: Option<String>,
+    tag: Option<String>
+}
+
+fn options(attrs: &[Attribute]) -> Result<Options, Error> {
+    let mut options = Options::default();
+
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("value")) {
+        attr.parse_nested_meta(|meta| {
+            let value: LitStr = meta.value()?.parse()?;
+
+            if meta.path.is_ident("rename") {
+                options.rename = Some(value.value());
+            }
+            else if meta.path.is_ident("tag") {
+                options.tag = Some(value.value());
+            }
+            else {
+                return Err(meta.error("expected rename or tag"));
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(options)
+}
+
+fn name_of(ident: &Ident, attrs: &[Attribute]) -> Result<String, Error> {
+    Ok(options(attrs)?.rename.unwrap_or_else(|| ident.to_string().trim_start_matches("r#").to_owned()))
+}
+
+// The type argument of a type written as <name><T>, e.g. Option<T>.
+fn type_argument<'t>(ty: &'t syn::Type, name: &str) -> Option<&'t syn::Type> {
+    let segment = match ty {
+        syn::Type::Path(path) if path.qself.is_none() => path.path.segments.last()?,
+        _ => return None
+    };
+    let arguments = match &segment.arguments {
+        syn::PathArguments::AngleBracketed(arguments) if segment.ident == name && arguments.args.len() == 1 => arguments,
+        _ => return None
+    };
+
+    match arguments.args.first()? {
+        syn::GenericArgument::Type(argument) => Some(argument),
+        _ => None
+    }
+}
+
+fn is_bytes(ty: &syn::Type) -> bool {
+    matches!(type_argument(ty, "Vec"), Some(syn::Type::Path(path)) if path.path.is_ident("u8"))
+}
+
+// Converts a Value expression to a field's type, as a Result.
+fn field_from_value(ty: &syn::Type, value: TokenStream2) -> TokenStream2 {
+    match type_argument(ty, "Option") {
+        _ if is_bytes(ty) => quote! { <::std::vec::Vec<u8>>::try_from(#value) },
+        Some(inner) if is_bytes(inner) => quote! {
+            match #value {
+                ::progenitor::Value::Null => Ok(None),
+                bytes => <::std::vec::Vec<u8>>::try_from(bytes).map(Some)
+            }
+        },
+        _ => quote! { ::progenitor::FromValue::from_value(#value) }
+    }
+}
+
+// Converts an expression of a field's type to a Value.
+fn field_into_value(ty: &syn::Type, field: TokenStream2) -> TokenStream2 {
+    match type_argument(ty, "Option") {
+        _ if is_bytes(ty) => quote! { ::progenitor::Value::Bytes(#field) },
+        Some(inner) if is_bytes(inner) => quote! {
+            (#field).map_or(::progenitor::Value::Null, ::progenitor::Value::Bytes)
+        },
+        _ => quote! { ::progenitor::IntoValue::into_value(#field) }
+    }
+}
+
+fn field_type(ty: &syn::Type) -> TokenStream2 {
+    match type_argument(ty, "Option") {
+        _ if is_bytes(ty) => quote! { ::progenitor::Type::Bytes },
+        Some(inner) if is_bytes(inner) => quote! {
+            ::progenitor::Type::Optional(Box::new(::progenitor::Type::Bytes))
+        },
+        _ => quote! { <#ty as ::progenitor::Typed>::value_type() }
+    }
+}
+
+// Each field's identifier, member name and type.
+fn named_fields(fields: &FieldsNamed) -> Result<Vec<(Ident, String, syn::Type)>, Error> {
+    let mut named = Vec::new();
+    for field in fields.named.iter() {
+        let ident = field.ident.clone().ok_or_else(|| Error::new_spanned(field, "expected a named field"))?;
+        let name = name_of(&ident, &field.attrs)?;
+
+        named.push((ident, name, field.ty.clone()));
+    }
+
+    Ok(named)
+}
+
+// The map type of named fields.
+fn fields_type(fields: &[(Ident, String, syn::Type)]) -> TokenStream2 {
+    let members = fields.iter().map(|(_, name, ty)| {
+        let typ = field_type(ty);
+
+        quote! { (#name.to_owned(), #typ) }
+    });
+
+    quote! {
+        ::progenitor::Type::Map(::std::collections::HashMap::from([#(#members),*]))
+    }
+}
+
+// Constructs the named fields from a map of members in scope.
+fn fields_from_members(fields: &[(Ident, String, syn::Type)]) -> TokenStream2 {
+    let inits = fields.iter().map(|(ident, name, ty)| {
+        let (from_member, from_null) = (
+            field_from_value(ty, quote! { member }), field_from_value(ty, quote! { ::progenitor::Value::Null })
+        );
+
+        quote! {
+            #ident: match members.remove(#name) {
+                Some(member) => #from_member?,
+                None => #from_null.map_err(|_| ::progenitor::SchemaError::MissingKey(#name.to_owned()))?
+            }
+        }
+    });
+
+    quote! { #(#inits),* }
+}
+
+// The members of named fields in scope as bindings.
+fn members_from_fields(fields: &[(Ident, String, syn::Type)]) -> TokenStream2 {
+    let members = fields.iter().map(|(ident, name, ty)| {
+        let member = field_into_value(ty, quote! { #ident });
+
+        quote! { (#name.to_owned(), #member) }
+    });
+
+    quote! { #(#members),* }
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(&input.generics, "generic types can't derive Typed"));
+    }
+
+    let ident = &input.ident;
+    let (from_value, into_value, value_type) = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(named) => {
+                let fields = named_fields(named)?;
+                let idents: Vec<&Ident> = fields.iter().map(|(ident, _, _)| ident).collect();
+                let (inits, members, typ) = (fields_from_members(&fields), members_from_fields(&fields), fields_type(&fields));
+
+                (
+                    quote! {
+                        let mut members = match value {
+                            ::progenitor::Value::Map(members) => members,
+                            _ => return Err(::progenitor::SchemaError::InvalidCast(<Self as ::progenitor::Typed>::value_type()))
+                        };
+
+                        Ok(Self { #inits })
+                    },
+                    quote! {
+                        let Self { #(#idents),* } = self;
+
+                        ::progenitor::Value::Map(::std::collections::HashMap::from([#members]))
+                    },
+                    typ
+                )
+            },
+            Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => {
+                let ty = &unnamed.unnamed[0].ty;
+                let (from_value, into_value) = (field_from_value(ty, quote! { value }), field_into_value(ty, quote! { self.0 }));
+
+                (
+                    quote! { Ok(Self(#from_value?)) },
+                    into_value,
+                    field_type(ty)
+                )
+            },
+            _ => return Err(Error::new_spanned(ident, "only structs with named fields or one unnamed field can derive Typed"))
+        },
+        Data::Enum(data) => {
+            let tag = options(&input.attrs)?.tag.unwrap_or_else(|| "type".to_owned());
+
+            let mut names = Vec::new();
+            let mut variant_fields = Vec::new();
+            for variant in data.variants.iter() {
+                names.push(name_of(&variant.ident, &variant.attrs)?);
+                variant_fields.push(match &variant.fields {
+                    Fields::Unit => Vec::new(),
+                    Fields::Named(named) => named_fields(named)?,
+                    Fields::Unnamed(_) => {
+                        return Err(Error::new_spanned(variant, "only unit or named field variants can derive Typed"));
+                    }
+                });
+
+                // The tag would overwrite the field's member, or be read as the variant.
+                if let Some((ident, name, _)) = variant_fields.last().into_iter().flatten().find(|(_, name, _)| *name == tag) {
+                    return Err(Error::new_spanned(
+                        ident, format!("field {:?} has the same name as the tag; rename it or set another tag", name)
+                    ));
+                }
+            }
+            let variants: Vec<&Ident> = data.variants.iter().map(|variant| &variant.ident).collect();
+
+            if data.variants.iter().all(|variant| matches!(variant.fields, Fields::Unit)) {
+                (
+                    quote! {
+                        match String::try_from(value)?.as_str() {
+                            #(#names => Ok(Self::#variants),)*
+                            other => Err(::progenitor::SchemaError::UnknownVariant(other.to_owned()))
+                        }
+                    },
+                    quote! {
+                        match self {
+                            #(Self::#variants => ::progenitor::Value::Str(#names.to_owned())),*
+                        }
+                    },
+                    quote! {
+                        ::progenitor::Type::Enum(Vec::from([#(#names.to_owned()),*]))
+                    }
+                )
+            }
+            else {
+                let inits = variant_fields.iter().map(|fields| fields_from_members(fields));
+                let members = variant_fields.iter().map(|fields| members_from_fields(fields));
+                let types = variant_fields.iter().map(|fields| fields_type(fields));
+                let bindings: Vec<Vec<&Ident>> = variant_fields.iter()
+                    .map(|fields| fields.iter().map(|(ident, _, _)| ident).collect())
+                    .collect();
+                let patterns = bindings.iter().map(|idents| quote! { { #(#idents),* } });
+
+                (
+                    quote! {
+                        let mut members = match value {
+                            ::progenitor::Value::Map(members) => members,
+                            _ => return Err(::progenitor::SchemaError::InvalidCast(<Self as ::progenitor::Typed>::value_type()))
+                        };
+
+                        let variant: String = members.remove(#tag)
+                            .ok_or_else(|| ::progenitor::SchemaError::MissingKey(#tag.to_owned()))?
+                            .try_into()?;
+
+                        match variant.as_str() {
+                            #(#names => Ok(Self::#variants { #inits }),)*
+                            other => Err(::progenitor::SchemaError::UnknownVariant(other.to_owned()))
+                        }
+                    },
+                    quote! {
+                        let (variant, mut members) = match self {
+                            #(Self::#variants #patterns => (
+                                #names, ::std::collections::HashMap::<String, ::progenitor::Value>::from([#members])
+                            )),*
+                        };
+                        members.insert(#tag.to_owned(), ::progenitor::Value::str_from(variant));
+
+                        ::progenitor::Value::Map(members)
+                    },
+                    quote! {
+                        ::progenitor::Type::Union(#tag.to_owned(), ::std::collections::HashMap::from([
+                            #((#names.to_owned(), #types)),*
+                        ]))
+                    }
+                )
+            }
+        },
+        Data::Union(_) => return Err(Error::new_spanned(ident, "unions can't derive Typed"))
+    };
+
+    Ok(quote! {
+        impl ::progenitor::FromValue for #ident {
+            fn from_value(value: ::progenitor::Value) -> Result<Self, ::progenitor::SchemaError> {
+                #from_value
+            }
+        }
+
+        impl ::progenitor::IntoValue for #ident {
+            fn into_value(self) -> ::progenitor::Value {
+                #into_value
+            }
+        }
+
+        impl ::progenitor::Typed for #ident {
+            fn value_type() -> ::progenitor::Type {
+                #value_type
+            }
+        }
+
+        impl TryFrom<::progenitor::Value> for #ident {
+            type Error = ::progenitor::SchemaError;
+
+            fn try_from(value: ::progenitor::Value) -> Result<Self, Self::Error> {
+                ::progenitor::FromValue::from_value(value)
+            }
+        }
+
+        impl From<#ident> for ::progenitor::Value {
+            fn from(data: #ident) -> Self {
+                ::progenitor::IntoValue::into_value(data)
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    fn error(input: DeriveInput) -> String {
+        expand(&input).err().map(|err| err.to_string()).unwrap_or_default()
+    }
+
+    #[test]
+    fn tag_collisions() {
+        assert!(error(parse_quote! {
+            enum Shape { Circle { r#type: String }, Square }
+        }).contains("same name as the tag"));
+        assert!(error(parse_quote! {
+            #[value(tag = "kind")]
+            enum Shape { Circle { #[value(rename = "kind")] radius: u32 }, Square }
+        }).contains("same name as the tag"));
+
+        assert!(expand(&parse_quote! {
+            #[value(tag = "kind")]
+            enum Shape { Circle { r#type: String }, Square }
+        }).is_ok());
+    }
+
+    #[test]
+    fn bytes() {
+        assert!(is_bytes(&parse_quote! { Vec<u8> }));
+        assert!(is_bytes(&parse_quote! { ::std::vec::Vec<u8> }));
+        assert!(!is_bytes(&parse_quote! { Vec<u32> }));
+        assert!(!is_bytes(&parse_quote! { Option<Vec<u8>> }));
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use progenitor::{FromValue, IntoValue, Type, Typed, Value};
+use progenitor_derive::Typed;
+
+#[derive(Debug, Clone, PartialEq, Typed)]
+struct Address {
+    city: String,
+    #[value(rename = "post_code")]
+    code: Option<String>
+}
+
+#[derive(Debug, Clone, PartialEq, Typed)]
+struct Id(u64);
+
+#[derive(Debug, Clone, PartialEq, Typed)]
+enum Role {
+    Admin,
+    #[value(rename = "member")]
+    Member
+}
+
+#[derive(Debug, Clone, PartialEq, Typed)]
+#[value(tag = "kind")]
+enum Payment {
+    Card { number: String, expiry: u32 },
+    Cash
+}
+
+#[derive(Debug, Clone, PartialEq, Typed)]
+struct Person {
+    id: Id,
+    name: String,
+    roles: Vec<Role>,
+    address: Option<Address>,
+    payments: Vec<Payment>,
+    extra: Value
+}
+
+#[derive(Debug, Clone, PartialEq, Typed)]
+struct Attachment {
+    data: Vec<u8>,
+    thumbnail: Option<Vec<u8>>,
+    sizes: Vec<u32>
+}
+
+fn person() -> Person {
+    Person {
+        id: Id(7),
+        name: "ada".into(),
+        roles: Vec::from([Role::Admin, Role::Member]),
+        address: Some(Address { city: "london".into(), code: None }),
+        payments: Vec::from([Payment::Card { number: "4111".into(), expiry: 1230 }, Payment::Cash]),
+        extra: Value::Bool(true)
+    }
+}
+
+#[test]
+fn round_trip() {
+    let value: Value = person().into();
+
+    assert_eq!(value.lookup("id").unwrap(), Value::Uint64(7));
+    assert_eq!(value.lookup("roles").unwrap(), Value::List(Vec::from([Value::str_from("Admin"), Value::str_from("member")])));
+    assert_eq!(value.lookup("address").unwrap().lookup("post_code").unwrap(), Value::Null);
+    assert_eq!(value.lookup("payments").unwrap().index(1).unwrap(), Value::map_from([("kind".into(), Value::str_from("Cash"))]));
+
+    assert_eq!(Person::try_from(value.clone()).unwrap(), person());
+    assert!(Person::value_type().validate(&value).is_ok());
+}
+
+#[test]
+fn absent_and_invalid() {
+    let address = Address::from_value(Value::map_from([("city".into(), Value::str_from("paris"))])).unwrap();
+    assert_eq!(address, Address { city: "paris".into(), code: None });
+
+    assert!(Address::from_value(Value::map_from([("post_code".into(), Value::str_from("x"))])).is_err());
+    assert!(Address::from_value(Value::str_from("paris")).is_err());
+    assert!(Role::from_value(Value::str_from("Member")).is_err());
+    assert!(Payment::from_value(Value::map_from([("kind".into(), Value::str_from("Cheque"))])).is_err());
+    assert!(Payment::from_value(Value::map_from([("number".into(), Value::str_from("1"))])).is_err());
+}
+
+#[test]
+fn types() {
+    assert_eq!(Address::value_type(), Type::Map(HashMap::from([
+        ("city".into(), Type::String),
+        ("post_code".into(), Type::Optional(Box::new(Type::String)))
+    ])));
+    assert_eq!(Id::value_type(), Type::Uint64);
+    assert_eq!(Role::value_type(), Type::Enum(Vec::from(["Admin".into(), "member".into()])));
+    assert_eq!(Payment::value_type(), Type::Union("kind".into(), HashMap::from([
+        ("Card".into(), Type::Map(HashMap::from([("number".into(), Type::String), ("expiry".into(), Type::Uint32)]))),
+        ("Cash".into(), Type::Map(HashMap::new()))
+    ])));
+
+    // Typed data agrees with schemas written as values.
+    let schema = Type::parse_from_value(Value::map_from([
+        ("$type".into(), Value::str_from("map")),
+        ("fields".into(), Value::map_from([
+            ("city".into(), Value::map_from([("$type".into(), Value::str_from("string"))])),
+            ("post_code".into(), Value::map_from([
+                ("$type".into(), Value::str_from("optional")),
+                ("of".into(), Value::map_from([("$type".into(), Value::str_from("string"))]))
+            ]))
+        ]))
+    ])).unwrap();
+    assert_eq!(schema, Address::value_type());
+    assert!(schema.validate(&Address { city: "rome".into(), code: None }.into_value()).is_ok());
+}
+
+#[test]
+fn bytes() {
+    let attachment = Attachment { data: Vec::from([1, 2]), thumbnail: None, sizes: Vec::from([3]) };
+    let value = attachment.clone().into_value();
+
+    assert_eq!(value.lookup("data").unwrap(), Value::Bytes(Vec::from([1, 2])));
+    assert_eq!(value.lookup("thumbnail").unwrap(), Value::Null);
+    assert_eq!(Attachment::from_value(value.clone()).unwrap(), attachment);
+    assert!(Attachment::from_value(Value::map_from([("sizes".into(), Value::List(Vec::new()))])).is_err());
+
+    assert_eq!(Attachment::value_type(), Type::Map(HashMap::from([
+        ("data".into(), Type::Bytes),
+        ("thumbnail".into(), Type::Optional(Box::new(Type::Bytes))),
+        ("sizes".into(), Type::List(Box::new(Type::Uint32)))
+    ])));
+    assert!(Attachment::value_type().validate(&value).is_ok());
+}
@@ -38,14 +38,24 @@ async fn prep_request(hyper_req: hyper::Request<hyper::body::Incoming>) -> Resul
 }
 
 async fn prep_response(resp: Response) -> hyper::Response<Full<Bytes>> {
-    hyper::Response::new(
+    // A weak validator: equal content hashes are equal data, but not necessarily equal
+    // bytes, e.g. 1 and 1.0 or the same members in another order.
+    let etag = resp.etag().map(|hash| format!("W/\"{}\"", hash));
+
+    let mut hyper_resp = hyper::Response::new(
         match resp.payload().try_into_bytes() {
             Ok(bytes) => Full::new(bytes),
             Err(_) => {
                 todo!("TODO: Response error handling");
             }
         }
-    )
+    );
+
+    if let Some(etag) = etag.and_then(|etag| hyper::header::HeaderValue::from_str(&etag).ok()) {
+        hyper_resp.headers_mut().insert(hyper::header::ETAG, etag);
+    }
+
+    hyper_resp
 }
 
 #[derive(Clone)] // TODO: No.
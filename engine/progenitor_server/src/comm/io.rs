@@ -1,4 +1,4 @@
-use progenitor::SerialValue;
+use progenitor::{ContentHash, SerialValue};
 
 #[derive(Clone)]
 pub struct Route {
@@ -41,7 +41,9 @@ impl Request {
 }
 
 pub struct Response {
-    payload: SerialValue
+    payload: SerialValue,
+    // The content hash of the value written, sent as the response's (weak) ETag.
+    etag: Option<ContentHash>
 }
 
 // TODO: No.
@@ -52,14 +54,25 @@ impl Clone for Response {
         };
 
         Self {
-            payload
+            payload,
+            etag: self.etag
         }
     }
 }
 
 impl Response {
     pub fn new(payload: SerialValue) -> Self {
-        Self { payload }
+        Self { payload, etag: None }
+    }
+
+    pub fn with_etag(mut self, etag: ContentHash) -> Self {
+        self.etag = Some(etag);
+
+        self
+    }
+
+    pub fn etag(&self) -> Option<&ContentHash> {
+        self.etag.as_ref()
     }
 
     pub fn payload(self) -> SerialValue {
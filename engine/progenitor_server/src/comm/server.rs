@@ -56,6 +56,10 @@ where
 
             let result = context.execute("main".into(), None).await;
             if let Err(err) = result {
+                if let Some(payload) = err.rejection() {
+                    return Response::new(payload.clone());
+                }
+
                 return self.err_response(CommError::from(err));
             };
 
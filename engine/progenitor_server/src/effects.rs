@@ -1,4 +1,4 @@
-use progenitor::{EffectError, Type, Context, Value, effect_fn};
+use progenitor::{EffectError, SchemaError, Type, ValidationMode, Context, Value, effect_fn};
 
 use super::comm::{Response, Request};
 
@@ -13,7 +13,14 @@ pub async fn write_resp<'ef>(context: &'ef mut Context) -> Result<(), EffectErro
 
     let format = context.registry().get_serial_format(format_name.as_str())?;
 
-    let response = Response::new(format.write(value)?);
+    // Format specific write options, e.g. {"pretty": true} for JSON.
+    let serial = match archetype.lookup("options") {
+        Ok(options) => format.write_with(value, options)?,
+        Err(_) => format.write(value)?
+    };
+
+    // Tagged with the value's content hash, so clients can tell whether it changed.
+    let response = Response::new(serial).with_etag(value.content_hash());
 
     context.set("resp", response)?;
 
@@ -26,7 +33,11 @@ pub async fn read_req<'ef>(context: &'ef mut Context) -> Result<(), EffectError>
 
     let format_name: String = archetype.lookup("format")?.try_into()?;
     let state_key_name: String = archetype.lookup("to_state")?.try_into()?;
-    let validate_as: Type = archetype.lookup("schema")?.try_into()?;
+    let validate_as = context.registry().resolve_type(Type::parse_from_value(archetype.lookup("schema")?)?)?;
+    let mode = match archetype.lookup("mode") {
+        Ok(mode) => ValidationMode::parse_from_value(mode)?,
+        Err(_) => ValidationMode::default()
+    };
 
     let req = context.get::<Request>("req")?;
 
@@ -35,7 +46,12 @@ pub async fn read_req<'ef>(context: &'ef mut Context) -> Result<(), EffectError>
     // TODO: Clone really dumb.
     let value = format.parse(req.payload().clone())?;
 
-    validate_as.validate(&value)?;
+    // Report every problem with the payload back to the client in its own format.
+    let value = match validate_as.normalize(&value, mode) {
+        Ok(normalized) => normalized,
+        Err(SchemaError::Invalid(report)) => return Err(EffectError::Reject(format.write(&report.to_value())?)),
+        Err(err) => return Err(err.into())
+    };
 
     context.set(state_key_name, value)?;
 
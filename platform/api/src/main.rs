@@ -3,44 +3,52 @@
 extern crate macro_rules_attribute;
 
 use std::env;
-use std::collections::HashMap;
 use std::sync::Arc;
 
 use progenitor::{
-    InitError, EffectError, Value, Context, Registry,
+    InitError, EffectError, Value, Context, Registry, Typed as _,
     effect_fn, archetype_effect, sequence_effect
 };
-use progenitor::effect::{store_read, store_write, open_store};
+use progenitor_derive::Typed;
+use progenitor::effect::{store_read, store_write, store_patch, open_store, compute};
 use progenitor_server::{Server, Request};
 use progenitor_server::effect::{read_req, write_resp};
 
 use progenitor::ext::{JsonSerial, MemStore};
 use progenitor_server::ext::Http1Comm;
 
+#[derive(Typed)]
+struct Client {
+    name: String
+}
+
+#[derive(Typed)]
+struct Greeting {
+    message: String
+}
+
 #[apply(effect_fn)]
 async fn greet<'ef>(context: &'ef mut Context) -> Result<(), EffectError> {
-    let client = context.get::<Value>("client")?;
+    let client = Client::try_from(context.get::<Value>("client")?.clone())?;
 
-    let greeting = Value::Map(HashMap::from([
-        ("message".into(), Value::Str(format!("hi, {}", String::try_from(client.lookup("name")?)?)))
-    ]));
+    let greeting = Greeting {
+        message: format!("hi, {}", client.name)
+    };
 
-    drop(client);
-
-    context.set("greeting", greeting)?;
+    context.set("greeting", Value::from(greeting))?;
 
     Ok(())
 }
 
 #[apply(effect_fn)]
 async fn poke<'ef>(context: &'ef mut Context) -> Result<(), EffectError> {
-    let client = context.get::<Value>("client")?;
+    let client = Client::try_from(context.get::<Value>("client")?.clone())?;
 
-    let greeting = Value::Map(HashMap::from([
-        ("message".into(), Value::Str(format!("hi, {}", String::try_from(client.lookup("name")?)?)))
-    ]));
+    let greeting = Greeting {
+        message: format!("hi, {}", client.name)
+    };
 
-    context.set("greeting", greeting)?;
+    context.set("greeting", Value::from(greeting))?;
 
     Ok(())
 }
@@ -59,7 +67,8 @@ archetype_effect!(read_req_client, "read_req", Value::map_from([
     ("format".into(), Value::str_from("json")),
     ("to_state".into(), Value::str_from("client")),
     ("schema".into(), Value::map_from([
-        ("name".into(), Value::str_from("james"))
+        ("$type".into(), Value::str_from("ref")),
+        ("name".into(), Value::str_from("client"))
     ]))
 ]));
 
@@ -67,7 +76,8 @@ archetype_effect!(open_visits_store, "open_store", Value::map_from([
     ("driver".into(), Value::str_from("memory")),
     ("name".into(), Value::str_from("visits")),
     ("schema".into(), Value::map_from([
-        ("name".into(), Value::str_from("james"))
+        ("$type".into(), Value::str_from("ref")),
+        ("name".into(), Value::str_from("client"))
     ]))
 ]));
 
@@ -78,7 +88,10 @@ archetype_effect!(write_resp_greeting, "write_resp", Value::map_from([
 
 archetype_effect!(write_resp_visits, "write_resp", Value::map_from([
     ("format".into(), Value::str_from("json")),
-    ("from_state".into(), Value::str_from("visits"))
+    ("from_state".into(), Value::str_from("visits")),
+    ("options".into(), Value::map_from([
+        ("pretty".into(), Value::Bool(true))
+    ]))
 ]));
 
 sequence_effect!(prep_client, vec![
@@ -125,7 +138,9 @@ fn main() {
         vec![
             ("store_read", store_read),
             ("store_write", store_write),
+            ("store_patch", store_patch),
             ("open_store", open_store),
+            ("compute", compute),
             ("read_req", read_req),
             ("write_resp", write_resp),
             ("read_req_client", read_req_client),
@@ -142,6 +157,9 @@ fn main() {
             ("greet", greet),
             ("main", entrypoint)
         ],
+        vec![
+            ("client", Client::value_type())
+        ],
         vec![
             ("memory", Box::new(|_: &Registry, name: String| Box::new(MemStore::new(name.as_str()))))
         ],
@@ -152,7 +170,7 @@ fn main() {
             let look_key = key.to_uppercase();
             env::var(look_key).or_else(|_| Err(InitError::Config(format!("invalid key {}", key).into())))
         })
-    ));
+    ).unwrap());
 
     let server = Server::<Http1Comm>::new(registry).unwrap();
 